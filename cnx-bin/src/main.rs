@@ -5,8 +5,8 @@ use cnx::widgets::*;
 use cnx::{Cnx, Position};
 use cnx_contrib::widgets::battery::*;
 use cnx_contrib::widgets::disk_usage::*;
+use cnx_contrib::widgets::weather::{Noaa, WeatherInfo};
 use cnx_contrib::widgets::*;
-use weathernoaa::weather::WeatherInfo;
 
 fn pango_markup_render(color: Color, start_text: String, text: String) -> String {
     format!(
@@ -40,11 +40,11 @@ fn weather_sky_condition(condition: String) -> &'static str {
 }
 
 fn main() -> Result<()> {
+    env_logger::init();
+
     let attr = Attributes {
-        font: Font::new("Ubuntu Mono Bold 14"),
-        fg_color: Color::white(),
-        bg_color: None,
-        padding: Padding::new(0.0, 0.0, 0.0, 0.0),
+        font: "Ubuntu Mono Bold 14".into(),
+        ..Default::default()
     };
 
     let mut cnx = Cnx::new(Position::Bottom);
@@ -57,7 +57,7 @@ fn main() -> Result<()> {
         pango_markup_single_render(Color::white(), default_text)
     });
 
-    let battery = Battery::new(attr.clone(), Color::red(), None, Some(battery_render));
+    let battery = Battery::new(attr.clone(), Color::red(), None, None, Some(battery_render));
     let render = Box::new(|load| {
         let mut color = Color::yellow().to_hex();
         if load < 5 {
@@ -86,7 +86,8 @@ fn main() -> Result<()> {
         pango_markup_single_render(Color::white(), disk_text)
     });
 
-    let disk_usage = disk_usage::DiskUsage::new(attr.clone(), "/home".into(), Some(disk_render));
+    let disk_usage =
+        disk_usage::DiskUsage::new(attr.clone(), vec!["/home".into()], Some(disk_render));
 
     let weather_render = Box::new(|weather: WeatherInfo| {
         let sky_condition = weather_sky_condition(weather.sky_condition);
@@ -95,28 +96,26 @@ fn main() -> Result<()> {
         pango_markup_render(Color::white(), weather_text, weather_temp)
     });
 
-    let weather = weather::Weather::new(attr.clone(), "VOBL".into(), Some(weather_render));
+    let weather = weather::Weather::new(
+        attr.clone(),
+        Box::new(Noaa::new("VOBL")),
+        Some(weather_render),
+    );
 
     let active_attr = Attributes {
-        font: Font::new("Ubuntu Mono Bold 14"),
-        fg_color: Color::white(),
-        bg_color: Some(Color::blue()),
-        padding: Padding::new(8.0, 8.0, 0.0, 0.0),
-    };
-    let inactive_attr = Attributes {
-        bg_color: None,
-        ..active_attr.clone()
-    };
-    let non_empty_attr = Attributes {
-        fg_color: Color::blue(),
-        ..inactive_attr.clone()
+        font: "Ubuntu Mono Bold 14".into(),
+        background: Background::Solid(Color::blue()),
+        padding: Padding::horizontal(8.0),
+        ..Default::default()
     };
+    let inactive_attr = active_attr.clone().with_bg_color(None);
+    let non_empty_attr = inactive_attr.clone().with_fg_color(Color::blue());
     let pager_attrs = PagerAttributes {
         active_attr,
         inactive_attr,
         non_empty_attr,
     };
-    let pager = Pager::new(pager_attrs);
+    let pager = Pager::new(pager_attrs).with_scroll_desktops();
 
     cnx.add_widget(pager);
     cnx.add_widget(ActiveWindowTitle::new(attr.clone()));
@@ -129,7 +128,7 @@ fn main() -> Result<()> {
     // cnx.add_widget(sensors);
     cnx.add_widget(battery);
     let time_template = Some("<span foreground=\"#808080\">[</span>%d-%m-%Y %a %I:%M %p<span foreground=\"#808080\">]</span>".into());
-    cnx.add_widget(Clock::new(attr, time_template));
+    cnx.add_widget(Clock::new(attr, time_template, Precision::Minutes));
     cnx.run()?;
 
     Ok(())
@@ -1,2 +1,3 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
+pub mod proc_sampler;
 pub mod widgets;
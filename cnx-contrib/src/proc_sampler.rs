@@ -0,0 +1,251 @@
+//! A shared source of CPU and memory statistics from `/proc`.
+//!
+//! Widgets which show CPU or memory usage would otherwise each read and parse
+//! `/proc/stat` and `/proc/meminfo` on their own timers. A [`ProcSampler`] can
+//! be shared between them, so that the files are only read once per update.
+
+use anyhow::{anyhow, Context, Result};
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Time spent by a CPU (or all CPUs) in each state, in clock ticks.
+///
+/// These are counters which increase over time. The usage over a period is
+/// found by comparing two samples.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+}
+
+impl CpuTimes {
+    /// The time spent running tasks.
+    pub fn busy(&self) -> u64 {
+        self.user + self.nice + self.system + self.irq + self.softirq + self.steal
+    }
+
+    /// The total time, whether busy or not.
+    pub fn total(&self) -> u64 {
+        self.busy() + self.idle + self.iowait
+    }
+}
+
+/// Memory statistics from `/proc/meminfo`, in kB.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub buffers: u64,
+    pub cached: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+/// A single reading of `/proc/stat` and `/proc/meminfo`.
+#[derive(Clone, Debug)]
+pub struct ProcSnapshot {
+    /// When the snapshot was taken.
+    pub taken: Instant,
+    /// The time spent by all CPUs combined.
+    pub cpu: CpuTimes,
+    /// The time spent by each CPU, in the order the kernel lists them.
+    pub cpus: Vec<CpuTimes>,
+    pub memory: MemInfo,
+}
+
+impl ProcSnapshot {
+    fn read() -> Result<ProcSnapshot> {
+        let stat = fs::read_to_string("/proc/stat").context("Failed to read /proc/stat")?;
+        let meminfo =
+            fs::read_to_string("/proc/meminfo").context("Failed to read /proc/meminfo")?;
+        let (cpu, cpus) = parse_stat(&stat)?;
+        Ok(ProcSnapshot {
+            taken: Instant::now(),
+            cpu,
+            cpus,
+            memory: parse_meminfo(&meminfo)?,
+        })
+    }
+}
+
+// Parses the `cpu` lines of `/proc/stat`, returning the combined times and
+// the times of each CPU.
+fn parse_stat(stat: &str) -> Result<(CpuTimes, Vec<CpuTimes>)> {
+    let mut combined = None;
+    let mut cpus = Vec::new();
+    for line in stat.lines() {
+        let mut fields = line.split_whitespace();
+        let name = match fields.next() {
+            Some(name) if name.starts_with("cpu") => name,
+            _ => continue,
+        };
+        let values = fields
+            .map(str::parse)
+            .collect::<Result<Vec<u64>, _>>()
+            .with_context(|| format!("Invalid line in /proc/stat: {line}"))?;
+        if values.len() < 4 {
+            return Err(anyhow!("Missing data in /proc/stat: {line}"));
+        }
+        // Older kernels have fewer columns.
+        let value = |i: usize| values.get(i).copied().unwrap_or(0);
+        let times = CpuTimes {
+            user: value(0),
+            nice: value(1),
+            system: value(2),
+            idle: value(3),
+            iowait: value(4),
+            irq: value(5),
+            softirq: value(6),
+            steal: value(7),
+        };
+        if name == "cpu" {
+            combined = Some(times);
+        } else {
+            cpus.push(times);
+        }
+    }
+    let combined = combined.ok_or_else(|| anyhow!("Missing data in /proc/stat"))?;
+    Ok((combined, cpus))
+}
+
+fn parse_meminfo(meminfo: &str) -> Result<MemInfo> {
+    let mut info = MemInfo::default();
+    for line in meminfo.lines() {
+        let (key, value) = match line.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let field = match key {
+            "MemTotal" => &mut info.total,
+            "MemFree" => &mut info.free,
+            "MemAvailable" => &mut info.available,
+            "Buffers" => &mut info.buffers,
+            "Cached" => &mut info.cached,
+            "SwapTotal" => &mut info.swap_total,
+            "SwapFree" => &mut info.swap_free,
+            _ => continue,
+        };
+        *field = value
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid line in /proc/meminfo: {line}"))?;
+    }
+    Ok(info)
+}
+
+struct Inner {
+    interval: Duration,
+    last: Option<Rc<ProcSnapshot>>,
+}
+
+/// Reads `/proc/stat` and `/proc/meminfo` on behalf of several widgets.
+///
+/// Clones of a `ProcSampler` share the same snapshots. A snapshot is read at
+/// most once per half `interval`: widgets which update every `interval` and
+/// ask for a snapshot at around the same time are all given the same one.
+///
+/// # Examples
+///
+/// ```
+/// # use cnx::*;
+/// # use cnx::text::*;
+/// # use cnx_contrib::proc_sampler::ProcSampler;
+/// # use cnx_contrib::widgets::cpu::Cpu;
+/// # use anyhow::Result;
+/// # use std::time::Duration;
+/// #
+/// # fn run() -> Result<()> {
+/// let sampler = ProcSampler::new(Duration::from_secs(10));
+///
+/// let mut cnx = Cnx::new(Position::Top);
+/// cnx.add_widget(Cpu::new(Attributes::default(), None)?.with_sampler(sampler.clone())?);
+/// # Ok(())
+/// # }
+/// # fn main() { run().unwrap(); }
+/// ```
+#[derive(Clone)]
+pub struct ProcSampler {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl ProcSampler {
+    /// Creates a new `ProcSampler` for widgets which update every `interval`.
+    pub fn new(interval: Duration) -> ProcSampler {
+        ProcSampler {
+            inner: Rc::new(RefCell::new(Inner {
+                interval,
+                last: None,
+            })),
+        }
+    }
+
+    /// Returns a recent snapshot, reading `/proc` if there isn't one.
+    pub fn snapshot(&self) -> Result<Rc<ProcSnapshot>> {
+        let mut inner = self.inner.borrow_mut();
+        let max_age = inner.interval / 2;
+        if let Some(last) = &inner.last {
+            if last.taken.elapsed() < max_age {
+                return Ok(last.clone());
+            }
+        }
+
+        let snapshot = Rc::new(ProcSnapshot::read()?);
+        inner.last = Some(snapshot.clone());
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_meminfo, parse_stat, CpuTimes};
+
+    #[test]
+    fn parses_stat() {
+        let stat = "cpu  10 20 30 40 50 60 70 80 0 0\n\
+                    cpu0 1 2 3 4 5 6 7 8 0 0\n\
+                    cpu1 9 18 27 36 45 54 63 72 0 0\n\
+                    intr 12345 0 0\n";
+        let (cpu, cpus) = parse_stat(stat).unwrap();
+        assert_eq!(
+            cpu,
+            CpuTimes {
+                user: 10,
+                nice: 20,
+                system: 30,
+                idle: 40,
+                iowait: 50,
+                irq: 60,
+                softirq: 70,
+                steal: 80,
+            }
+        );
+        assert_eq!(cpu.busy(), 270);
+        assert_eq!(cpu.total(), 360);
+        assert_eq!(cpus.len(), 2);
+        assert_eq!(cpus[1].idle, 36);
+    }
+
+    #[test]
+    fn parses_meminfo() {
+        let meminfo = "MemTotal:       16318480 kB\n\
+                       MemFree:         1234567 kB\n\
+                       MemAvailable:    8765432 kB\n\
+                       HugePages_Total:       0\n";
+        let info = parse_meminfo(meminfo).unwrap();
+        assert_eq!(info.total, 16318480);
+        assert_eq!(info.free, 1234567);
+        assert_eq!(info.available, 8765432);
+        assert_eq!(info.swap_total, 0);
+    }
+}
@@ -132,16 +132,15 @@ impl Battery {
             attr.fg_color = self.warning_color.clone()
         }
 
-        Ok(vec![Text {
-            attr,
-            text,
-            stretch: false,
-            markup: false,
-        }])
+        Ok(vec![Text::new(attr, text).with_markup(false)])
     }
 }
 
 impl Widget for Battery {
+    fn name(&self) -> &str {
+        "Battery"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let interval = time::interval(self.update_interval);
         let stream = IntervalStream::new(interval).map(move |_| self.tick());
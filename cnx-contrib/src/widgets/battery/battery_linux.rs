@@ -1,13 +1,24 @@
 use anyhow::{anyhow, Context, Error, Result};
-use cnx::text::{Attributes, Color, Text};
+use cnx::text::{Attributes, Color, Font, Text};
 use cnx::widgets::{Widget, WidgetStream};
-use std::fs::File;
+use log::warn;
+use nix::sys::socket::{
+    bind, recv, socket, AddressFamily, MsgFlags, NetlinkAddr, SockAddr, SockFlag, SockProtocol,
+    SockType,
+};
+use std::collections::VecDeque;
+use std::fs::{self, File};
 use std::io::Read;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::Path;
+use std::pin::Pin;
 use std::str::FromStr;
+use std::task::Poll;
 use std::time::Duration;
+use tokio::io::unix::AsyncFd;
 use tokio::time;
 use tokio_stream::wrappers::IntervalStream;
-use tokio_stream::StreamExt;
+use tokio_stream::{Stream, StreamExt};
 
 /// Represent Battery's operating status
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -32,31 +43,160 @@ impl FromStr for Status {
     }
 }
 
+const POWER_SUPPLY_PATH: &str = "/sys/class/power_supply";
+
+// How many of the most recent (dis)charge rate samples are averaged.
+const RATE_SAMPLES: usize = 5;
+
+// A moving average of the (dis)charge rate. The instantaneous rate jumps
+// around as the load changes, which makes time estimates based on it useless.
+#[derive(Debug, Default)]
+struct RateAverage {
+    samples: VecDeque<f64>,
+}
+
+impl RateAverage {
+    fn push(&mut self, rate: f64) {
+        if self.samples.len() == RATE_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rate);
+    }
+
+    fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    fn average(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.samples.iter().sum::<f64>() / self.samples.len() as f64)
+    }
+}
+
+// The units a battery reports its charge in: either μAh (`charge_*`), with
+// the rate in μA (`current_now`), or μWh (`energy_*`), with the rate in μW
+// (`power_now`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Units {
+    Charge,
+    Energy,
+}
+
+impl Units {
+    // The files holding the current charge, the full charge and the rate.
+    fn files(self) -> [&'static str; 3] {
+        match self {
+            Units::Charge => ["charge_now", "charge_full", "current_now"],
+            Units::Energy => ["energy_now", "energy_full", "power_now"],
+        }
+    }
+}
+
+// The charge and (dis)charge rate of a battery, in its own units.
+#[derive(Clone, Debug, PartialEq)]
+struct Reading {
+    units: Units,
+    now: f64,
+    full: f64,
+    rate: f64,
+}
+
+impl Reading {
+    // The charge as a fraction of the full charge.
+    fn capacity(&self) -> f64 {
+        if self.full > 0.0 {
+            self.now / self.full
+        } else {
+            0.0
+        }
+    }
+}
+
+// Sums the readings of several batteries, if they all use the same units.
+fn total_reading(readings: &[Reading]) -> Option<Reading> {
+    let units = readings.first()?.units;
+    if readings.iter().any(|reading| reading.units != units) {
+        return None;
+    }
+    Some(Reading {
+        units,
+        now: readings.iter().map(|reading| reading.now).sum(),
+        full: readings.iter().map(|reading| reading.full).sum(),
+        rate: readings.iter().map(|reading| reading.rate).sum(),
+    })
+}
+
+// The combined charge of several batteries, as a fraction of their full
+// charge. Charges in different units can't be summed, so in that case each
+// battery's fraction is averaged instead.
+fn combined_capacity(readings: &[Reading]) -> f64 {
+    match total_reading(readings) {
+        Some(total) => total.capacity(),
+        None => readings.iter().map(Reading::capacity).sum::<f64>() / readings.len().max(1) as f64,
+    }
+}
+
+// The overall status of several batteries: we're charging if any battery is
+// charging, otherwise discharging if any battery is discharging.
+fn combined_status(statuses: &[Status]) -> Status {
+    if statuses.contains(&Status::Charging) {
+        Status::Charging
+    } else if statuses.contains(&Status::Discharging) {
+        Status::Discharging
+    } else if !statuses.is_empty() && statuses.iter().all(|s| *s == Status::Full) {
+        Status::Full
+    } else {
+        Status::Unknown
+    }
+}
+
 /// Shows battery charge percentage
 ///
-/// This widget shows the battery's current charge percentage.
+/// This widget shows the battery's current charge percentage. If the machine
+/// has more than one battery, the charge of all of them is combined into a
+/// single percentage.
 ///
 /// When the battery has less than 10% charge remaining, the widget's text will
-/// change to the specified `warning_color`.
+/// change to the specified `warning_color` (and `warning_font`, if given).
+///
+/// Battery charge information is read from [`/sys/class/power_supply/BAT*/`].
+/// It is re-read whenever the kernel reports a change to a power supply (e.g.
+/// when the charger is plugged in or unplugged), and at least once a minute.
 ///
-/// Battery charge information is read from [`/sys/class/power_supply/BAT0/`].
+/// While the battery is charging or discharging, the time until it is full or
+/// empty is shown too, e.g. `(85% 2:13)`. This is estimated from an average of
+/// the last few readings of the (dis)charge rate, so that it doesn't jump
+/// around whenever the load changes.
 ///
-/// [`/sys/class/power_supply/BAT0/`]: https://www.kernel.org/doc/Documentation/power/power_supply_class.txt
+/// [`/sys/class/power_supply/BAT*/`]: https://www.kernel.org/doc/Documentation/power/power_supply_class.txt
 pub struct Battery {
     update_interval: Duration,
-    battery: String,
+    batteries: Option<Vec<String>>,
     attr: Attributes,
     warning_color: Color,
+    warning_font: Option<Font>,
     render: Option<Box<dyn Fn(BatteryInfo) -> String>>,
+    rate: RateAverage,
+    rate_status: Status,
+    rate_units: Option<Units>,
 }
 
 /// Represent Battery information
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct BatteryInfo {
     /// Battery Status
     pub status: Status,
     /// Capacity in percentage
     pub capacity: u8,
+    /// The average rate at which the battery is (dis)charging, in μA or μW
+    /// (depending on the battery), if it is charging or discharging. This is
+    /// `None` if the batteries report their charge in different units.
+    pub rate: Option<f64>,
+    /// The estimated time until the battery is full (when charging) or empty
+    /// (when discharging), based on `rate`.
+    pub time_remaining: Option<Duration>,
 }
 
 impl Battery {
@@ -65,7 +205,13 @@ impl Battery {
     ///  Creates a new `Battery` widget, whose text will be displayed with the
     ///  given [`Attributes`]. The caller can provide use the `warning_color`
     ///  argument, to control the [`Color`] of the text once the battery has
-    ///  less than 10% charge remaining.
+    ///  less than 10% charge remaining. If a `warning_font` is given, it
+    ///  replaces the [`Font`] from `attr` at the same time, e.g. to make the
+    ///  text bold.
+    ///
+    ///  The `batteries` argument lists the names of the batteries to combine,
+    ///  e.g. `vec!["BAT0".into(), "BAT1".into()]`. If it is `None`, every
+    ///  `BAT*` device in `/sys/class/power_supply/` is used.
     ///
     ///  The [`cnx::Cnx`] instance is borrowed during construction in order to get
     ///  access to handles of its event loop. However, it is not borrowed for
@@ -86,14 +232,13 @@ impl Battery {
     /// #
     /// # fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 21"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
-    /// cnx.add_widget(Battery::new(attr.clone(), Color::red(), None, None));
+    /// cnx.add_widget(Battery::new(attr.clone(), Color::red(), None, None, None));
     /// # Ok(())
     /// # }
     /// # fn main() { run().unwrap(); }
@@ -101,24 +246,54 @@ impl Battery {
     pub fn new(
         attr: Attributes,
         warning_color: Color,
-        battery: Option<String>,
+        warning_font: Option<Font>,
+        batteries: Option<Vec<String>>,
         render: Option<Box<dyn Fn(BatteryInfo) -> String>>,
     ) -> Battery {
         Battery {
+            // We're woken up by uevents when the battery changes state, so
+            // this is only a fallback to keep the capacity fresh.
             update_interval: Duration::from_secs(60),
-            battery: battery.unwrap_or_else(|| "BAT0".into()),
+            batteries,
             attr,
             warning_color,
+            warning_font,
             render,
+            rate: RateAverage::default(),
+            rate_status: Status::Unknown,
+            rate_units: None,
         }
     }
 
-    fn load_value_inner<T>(&self, file: &str) -> Result<T>
+    // The names of the batteries to read, discovering them if none were given.
+    fn battery_names(&self) -> Result<Vec<String>> {
+        if let Some(batteries) = &self.batteries {
+            return Ok(batteries.clone());
+        }
+
+        let entries = fs::read_dir(POWER_SUPPLY_PATH)
+            .with_context(|| format!("Failed to read directory: {POWER_SUPPLY_PATH}"))?;
+        let mut batteries = Vec::new();
+        for entry in entries {
+            let name = entry?.file_name().to_string_lossy().into_owned();
+            if name.starts_with("BAT") {
+                batteries.push(name);
+            }
+        }
+        batteries.sort();
+
+        if batteries.is_empty() {
+            return Err(anyhow!("No batteries found in {}", POWER_SUPPLY_PATH));
+        }
+        Ok(batteries)
+    }
+
+    fn load_value_inner<T>(&self, battery: &str, file: &str) -> Result<T>
     where
         T: FromStr,
         <T as FromStr>::Err: Into<Error>,
     {
-        let path = format!("/sys/class/power_supply/{}/{}", self.battery, file);
+        let path = format!("{POWER_SUPPLY_PATH}/{battery}/{file}");
         let mut file = File::open(path)?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)?;
@@ -128,27 +303,96 @@ impl Battery {
         Ok(s)
     }
 
-    fn load_value<T>(&self, file: &str) -> Result<T>
+    fn load_value<T>(&self, battery: &str, file: &str) -> Result<T>
     where
         T: FromStr,
         <T as FromStr>::Err: Into<Error>,
     {
-        let value = self
-            .load_value_inner(file)
-            .with_context(|| format!("Could not load value from battery status file: {file}"))?;
+        let value = self.load_value_inner(battery, file).with_context(|| {
+            format!("Could not load value from battery status file: {battery}/{file}")
+        })?;
         Ok(value)
     }
 
-    fn get_value(&self) -> Result<BatteryInfo> {
-        let capacity: u8 = self.load_value("capacity")?;
-        let status: Status = self.load_value("status")?;
-        Ok(BatteryInfo { capacity, status })
+    // Reads a battery's charge and rate, using whichever units it reports.
+    fn load_reading(&self, battery: &str) -> Result<Reading> {
+        let units = if Path::new(&format!("{POWER_SUPPLY_PATH}/{battery}/charge_now")).exists() {
+            Units::Charge
+        } else {
+            Units::Energy
+        };
+        let [now, full, rate] = units.files();
+        Ok(Reading {
+            units,
+            now: self.load_value(battery, now)?,
+            full: self.load_value(battery, full)?,
+            // Not every battery reports its rate, in which case there's no
+            // estimate.
+            rate: self.load_value::<f64>(battery, rate).unwrap_or(0.0).abs(),
+        })
     }
 
-    fn tick(&self) -> Result<Vec<Text>> {
+    fn get_value(&mut self) -> Result<BatteryInfo> {
+        let mut readings = Vec::new();
+        let mut statuses = Vec::new();
+        for battery in self.battery_names()? {
+            readings.push(self.load_reading(&battery)?);
+            statuses.push(self.load_value::<Status>(&battery, "status")?);
+        }
+
+        let capacity = (combined_capacity(&readings) * 100.0).round().min(100.0) as u8;
+        let status = combined_status(&statuses);
+
+        // Rates in different units can't be summed, so there's no estimate
+        // unless every battery uses the same units.
+        let total = total_reading(&readings);
+        let units = total.as_ref().map(|total| total.units);
+
+        // Samples from before the charger was plugged in (or unplugged) say
+        // nothing about the current rate.
+        if status != self.rate_status || units != self.rate_units {
+            self.rate.clear();
+            self.rate_status = status.clone();
+            self.rate_units = units;
+        }
+        let rate = match (&status, &total) {
+            (Status::Charging | Status::Discharging, Some(total)) if total.rate > 0.0 => {
+                self.rate.push(total.rate);
+                self.rate.average()
+            }
+            _ => None,
+        };
+        let time_remaining = rate.zip(total).map(|(rate, total)| {
+            let remaining = match status {
+                Status::Charging => total.full - total.now,
+                _ => total.now,
+            };
+            Duration::from_secs_f64((remaining.max(0.0) / rate) * 60.0 * 60.0)
+        });
+
+        Ok(BatteryInfo {
+            capacity,
+            status,
+            rate,
+            time_remaining,
+        })
+    }
+
+    fn tick(&mut self) -> Result<Vec<Text>> {
         let battery_info = self.get_value()?;
 
-        let default_text = format!("({percentage:.0}%)", percentage = battery_info.capacity,);
+        let default_text = match battery_info.time_remaining {
+            Some(time) => {
+                let minutes = time.as_secs() / 60;
+                format!(
+                    "({percentage:.0}% {hours}:{minutes:02})",
+                    percentage = battery_info.capacity,
+                    hours = minutes / 60,
+                    minutes = minutes % 60,
+                )
+            }
+            None => format!("({percentage:.0}%)", percentage = battery_info.capacity),
+        };
         let text = self
             .render
             .as_ref()
@@ -158,23 +402,178 @@ impl Battery {
         // special warning color.
         let mut attr = self.attr.clone();
         if battery_info.status == Status::Discharging && battery_info.capacity <= 10 {
-            attr.fg_color = self.warning_color.clone()
+            attr.fg_color = self.warning_color.clone();
+            if let Some(font) = &self.warning_font {
+                attr = attr.with_font(font.clone());
+            }
         }
 
-        Ok(vec![Text {
-            attr,
-            text,
-            stretch: false,
-            markup: self.render.is_some(),
-        }])
+        Ok(vec![
+            Text::new(attr, text).with_markup(self.render.is_some())
+        ])
     }
 }
 
 impl Widget for Battery {
-    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+    fn name(&self) -> &str {
+        "Battery"
+    }
+
+    fn into_stream(mut self: Box<Self>) -> Result<WidgetStream> {
         let interval = time::interval(self.update_interval);
-        let stream = IntervalStream::new(interval).map(move |_| self.tick());
+        let ticks = IntervalStream::new(interval).map(|_| ());
+
+        // Re-read the battery as soon as the kernel tells us that a power
+        // supply has changed (e.g. the charger was plugged in), falling back
+        // to just polling if we can't listen for uevents.
+        let wake_ups: Pin<Box<dyn Stream<Item = ()>>> = match UeventStream::new() {
+            Ok(uevents) => Box::pin(ticks.merge(uevents)),
+            Err(err) => {
+                warn!("Battery widget not listening for power supply uevents: {err}");
+                Box::pin(ticks)
+            }
+        };
+        let stream = wake_ups.map(move |()| self.tick());
 
         Ok(Box::pin(stream))
     }
 }
+
+// A netlink socket subscribed to kernel uevents.
+struct UeventSocket(RawFd);
+
+impl UeventSocket {
+    fn new() -> Result<UeventSocket> {
+        let fd = socket(
+            AddressFamily::Netlink,
+            SockType::Datagram,
+            SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC,
+            SockProtocol::NetlinkKObjectUEvent,
+        )
+        .context("Failed to open uevent netlink socket")?;
+        let socket = UeventSocket(fd);
+
+        // Multicast group 1 receives the kernel's uevents.
+        let addr = SockAddr::Netlink(NetlinkAddr::new(0, 1));
+        bind(socket.0, &addr).context("Failed to bind uevent netlink socket")?;
+
+        Ok(socket)
+    }
+
+    // Reads all pending uevents, returning whether any were for a power supply.
+    fn drain(&self) -> bool {
+        let mut power_supply_changed = false;
+        let mut buf = [0; 8192];
+        // The socket is non-blocking, so this stops once it would block.
+        while let Ok(len) = recv(self.0, &mut buf, MsgFlags::empty()) {
+            // Each uevent is a sequence of NUL-separated KEY=VALUE pairs.
+            power_supply_changed |= buf[..len]
+                .split(|byte| *byte == 0)
+                .any(|field| field == b"SUBSYSTEM=power_supply");
+        }
+        power_supply_changed
+    }
+}
+
+impl AsRawFd for UeventSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl Drop for UeventSocket {
+    fn drop(&mut self) {
+        let _ = nix::unistd::close(self.0);
+    }
+}
+
+// A `Stream` which yields each time a `power_supply` uevent is received.
+//
+// Like the ALSA volume widget's event stream, we just yield unit: the caller
+// is expected to re-read the battery state when woken up.
+struct UeventStream {
+    poll: AsyncFd<UeventSocket>,
+}
+
+impl UeventStream {
+    fn new() -> Result<UeventStream> {
+        Ok(UeventStream {
+            poll: AsyncFd::new(UeventSocket::new()?)?,
+        })
+    }
+}
+
+impl Stream for UeventStream {
+    type Item = ();
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut std::task::Context) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.poll.poll_read_ready(cx) {
+                Poll::Ready(Ok(mut r)) => {
+                    let power_supply_changed = self.poll.get_ref().drain();
+                    r.clear_ready();
+                    if power_supply_changed {
+                        return Poll::Ready(Some(()));
+                    }
+                }
+                Poll::Ready(Err(_)) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{combined_capacity, total_reading, RateAverage, Reading, Units, RATE_SAMPLES};
+
+    #[test]
+    fn rate_average_keeps_recent_samples() {
+        let mut rate = RateAverage::default();
+        assert_eq!(rate.average(), None);
+
+        rate.push(1000.0);
+        rate.push(3000.0);
+        assert_eq!(rate.average(), Some(2000.0));
+
+        for _ in 0..RATE_SAMPLES {
+            rate.push(500.0);
+        }
+        assert_eq!(rate.average(), Some(500.0));
+
+        rate.clear();
+        assert_eq!(rate.average(), None);
+    }
+
+    #[test]
+    fn combines_readings() {
+        let charge = Reading {
+            units: Units::Charge,
+            now: 1_000_000.0,
+            full: 4_000_000.0,
+            rate: 500_000.0,
+        };
+        let energy = Reading {
+            units: Units::Energy,
+            now: 30_000_000.0,
+            full: 40_000_000.0,
+            rate: 10_000_000.0,
+        };
+
+        // Readings in the same units are summed.
+        let both = [charge.clone(), charge.clone()];
+        assert_eq!(combined_capacity(&both), 0.25);
+        assert_eq!(
+            total_reading(&both).map(|total| total.rate),
+            Some(1_000_000.0)
+        );
+
+        // Readings in different units can't be, so their fractions are
+        // averaged and there's no combined rate.
+        let mixed = [charge, energy];
+        assert_eq!(combined_capacity(&mixed), 0.5);
+        assert_eq!(total_reading(&mixed), None);
+
+        assert_eq!(combined_capacity(&[]), 0.0);
+    }
+}
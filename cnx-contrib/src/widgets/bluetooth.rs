@@ -0,0 +1,186 @@
+use anyhow::{Context, Result};
+use async_stream::try_stream;
+use cnx::text::{escape_markup, Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::collections::HashMap;
+use tokio_stream::StreamExt;
+use zbus::fdo::{ManagedObjects, ObjectManagerProxy};
+use zbus::names::OwnedInterfaceName;
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, MatchRule, MessageStream, MessageType};
+
+const BLUEZ_DESTINATION: &str = "org.bluez";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+/// Represents a connected Bluetooth device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BtDevice {
+    /// The name of the device, e.g. `WH-1000XM4`.
+    pub name: String,
+    /// The Bluetooth address of the device, e.g. `00:11:22:33:44:55`.
+    pub address: String,
+    /// The remaining battery, in percent, if the device reports it.
+    pub battery: Option<u8>,
+}
+
+type Properties = HashMap<String, OwnedValue>;
+
+fn interface<'a>(
+    interfaces: &'a HashMap<OwnedInterfaceName, Properties>,
+    name: &str,
+) -> Option<&'a Properties> {
+    interfaces
+        .iter()
+        .find(|(interface, _)| interface.as_str() == name)
+        .map(|(_, properties)| properties)
+}
+
+fn property<'a, T>(properties: &'a Properties, name: &str) -> Option<T>
+where
+    T: TryFrom<&'a OwnedValue>,
+{
+    properties
+        .get(name)
+        .and_then(|value| T::try_from(value).ok())
+}
+
+// Finds the connected devices among the objects BlueZ manages, which also
+// include its adapters.
+fn connected_devices(objects: &ManagedObjects) -> Vec<BtDevice> {
+    let mut devices: Vec<BtDevice> = objects
+        .values()
+        .filter_map(|interfaces| {
+            let device = interface(interfaces, DEVICE_INTERFACE)?;
+            if !property::<bool>(device, "Connected").unwrap_or(false) {
+                return None;
+            }
+            let address = property::<&str>(device, "Address").unwrap_or_default();
+            // The alias defaults to the name, but can be set by the user.
+            let name = property::<&str>(device, "Alias")
+                .or_else(|| property(device, "Name"))
+                .unwrap_or(address);
+            let battery = interface(interfaces, BATTERY_INTERFACE)
+                .and_then(|battery| property::<u8>(battery, "Percentage"));
+            Some(BtDevice {
+                name: name.to_owned(),
+                address: address.to_owned(),
+                battery,
+            })
+        })
+        .collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+/// Shows the connected Bluetooth devices, and their battery levels.
+///
+/// This widget talks to BlueZ over the D-Bus system bus, and updates whenever
+/// a device connects, disconnects or reports a new battery level. By default,
+/// it shows each connected device's battery level, e.g. `🎧 80%`, or its name
+/// if it doesn't report one. Nothing is shown if no devices are connected.
+pub struct Bluetooth {
+    attr: Attributes,
+    render: Option<Box<dyn Fn(Vec<BtDevice>) -> String>>,
+}
+
+impl Bluetooth {
+    /// Creates a new [`Bluetooth`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives every connected [`BtDevice`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::bluetooth::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Bluetooth::new(attr, None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(attr: Attributes, render: Option<Box<dyn Fn(Vec<BtDevice>) -> String>>) -> Self {
+        Self { attr, render }
+    }
+
+    fn on_change(&self, devices: Vec<BtDevice>) -> Vec<Text> {
+        if let Some(render) = &self.render {
+            return vec![Text::new(self.attr.clone(), (render)(devices))];
+        }
+
+        devices
+            .into_iter()
+            .map(|device| {
+                let text = match device.battery {
+                    Some(battery) => format!("🎧 {battery}%"),
+                    None => format!("🎧 {}", escape_markup(&device.name)),
+                };
+                Text::new(self.attr.clone(), text)
+            })
+            .collect()
+    }
+}
+
+impl Widget for Bluetooth {
+    fn name(&self) -> &str {
+        "Bluetooth"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            let conn = Connection::system()
+                .await
+                .context("Failed to connect to the D-Bus system bus")?;
+            let manager = ObjectManagerProxy::builder(&conn)
+                .destination(BLUEZ_DESTINATION)?
+                .path("/")?
+                .build()
+                .await
+                .context("Failed to create proxy for BlueZ")?;
+
+            // Devices connecting and batteries changing are reported as
+            // property changes on the individual devices.
+            let rule = MatchRule::builder()
+                .msg_type(MessageType::Signal)
+                .sender(BLUEZ_DESTINATION)?
+                .interface(PROPERTIES_INTERFACE)?
+                .member("PropertiesChanged")?
+                .build();
+            let properties_changed = MessageStream::for_match_rule(rule, &conn, None)
+                .await
+                .context("Failed to subscribe to BlueZ's PropertiesChanged")?
+                .map(|_| ());
+            let added = manager.receive_interfaces_added().await?.map(|_| ());
+            let removed = manager.receive_interfaces_removed().await?.map(|_| ());
+            let mut changes = properties_changed.merge(added).merge(removed);
+
+            yield self.on_change(connected_devices(&manager.get_managed_objects().await?));
+            while changes.next().await.is_some() {
+                yield self.on_change(connected_devices(&manager.get_managed_objects().await?));
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -1,16 +1,28 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use cnx::text::{Attributes, Text};
 use cnx::widgets::{Widget, WidgetStream};
-use std::process::Command as Process;
+use std::process::{Command as Process, ExitStatus};
 use std::time::Duration;
 use tokio::time;
 use tokio_stream::wrappers::IntervalStream;
 use tokio_stream::StreamExt;
 
+/// Represents the output of a command run by the [`Command`] widget.
+#[derive(Clone, Debug)]
+pub struct CommandOutput {
+    /// Standard output of the command
+    pub stdout: String,
+    /// Standard error of the command
+    pub stderr: String,
+    /// Exit status of the command
+    pub exit_status: ExitStatus,
+}
+
 pub struct Command {
     attr: Attributes,
     command: String,
     update_interval: Duration,
+    render: Option<Box<dyn Fn(CommandOutput) -> String>>,
 }
 
 impl Command {
@@ -25,6 +37,11 @@ impl Command {
     ///
     /// * `update_interval` - Time interval between updates.
     ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. [`CommandOutput`] represents the output of
+    /// the command. By default, the command's standard output is shown
+    /// with any trailing newline removed.
+    ///
     /// # Examples
     ///
     /// ```
@@ -39,48 +56,62 @@ impl Command {
     ///
     /// fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 16"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 16".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
-    /// cnx.add_widget(Command::new(attr, "echo foo".into(), Duration::from_secs(10)));
+    /// cnx.add_widget(Command::new(attr, "echo foo".into(), Duration::from_secs(10), None));
     /// Ok(())
     /// }
     /// fn main() { run().unwrap(); }
     /// ```
-    pub fn new(attr: Attributes, command: String, update_interval: Duration) -> Self {
+    pub fn new(
+        attr: Attributes,
+        command: String,
+        update_interval: Duration,
+        render: Option<Box<dyn Fn(CommandOutput) -> String>>,
+    ) -> Self {
         Self {
             attr,
             command,
             update_interval,
+            render,
         }
     }
 
-    fn tick(&self) -> Vec<Text> {
+    fn tick(&self) -> Result<Vec<Text>> {
         let output = Process::new("sh")
             .arg("-c")
             .arg(self.command.clone())
             .output()
-            .expect("failed to execute process");
+            .with_context(|| format!("Failed to execute command: {}", self.command))?;
 
-        let texts = vec![Text {
-            attr: self.attr.clone(),
-            text: String::from_utf8(output.stdout).unwrap_or_else(|_| "error".into()),
-            stretch: false,
-            markup: true,
-        }];
+        let output = CommandOutput {
+            stdout: String::from_utf8(output.stdout).unwrap_or_else(|_| "error".into()),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            exit_status: output.status,
+        };
+        let text = match &self.render {
+            Some(render) => (render)(output),
+            None => output.stdout.trim_end_matches('\n').to_owned(),
+        };
 
-        texts
+        let texts = vec![Text::new(self.attr.clone(), text)];
+
+        Ok(texts)
     }
 }
 
 impl Widget for Command {
+    fn name(&self) -> &str {
+        "Command"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let interval = time::interval(self.update_interval);
-        let stream = IntervalStream::new(interval).map(move |_| Ok(self.tick()));
+        let stream = IntervalStream::new(interval).map(move |_| self.tick());
 
         Ok(Box::pin(stream))
     }
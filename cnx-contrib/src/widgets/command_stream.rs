@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Context, Result};
+use async_stream::try_stream;
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use log::warn;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command as Process;
+
+/// Shows each line of output from a long-running command.
+///
+/// Unlike the `Command` widget, which runs a command to completion on an
+/// interval, this widget spawns the command once and shows each line it
+/// writes to its standard output as soon as it is written. This suits
+/// commands such as `tail -f` or scripts which print their status whenever it
+/// changes.
+///
+/// If the command exits, it is restarted after a short delay.
+pub struct CommandStream {
+    attr: Attributes,
+    command: String,
+    restart_delay: Duration,
+    render: Option<Box<dyn Fn(String) -> String>>,
+}
+
+impl CommandStream {
+    /// Creates a new [`CommandStream`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `command` - Command to be executed. It is run with `sh -c`.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives the latest line of output, without
+    /// its trailing newline. By default, the line is shown as-is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::command_stream::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(CommandStream::new(attr, "tail -f /tmp/status".into(), None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(
+        attr: Attributes,
+        command: String,
+        render: Option<Box<dyn Fn(String) -> String>>,
+    ) -> Self {
+        Self {
+            attr,
+            command,
+            restart_delay: Duration::from_secs(1),
+            render,
+        }
+    }
+
+    fn on_line(&self, line: String) -> Vec<Text> {
+        let text = match &self.render {
+            Some(render) => (render)(line),
+            None => line,
+        };
+        vec![Text::new(self.attr.clone(), text)]
+    }
+}
+
+impl Widget for CommandStream {
+    fn name(&self) -> &str {
+        "CommandStream"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            loop {
+                let mut child = Process::new("sh")
+                    .arg("-c")
+                    .arg(&self.command)
+                    .stdout(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .with_context(|| format!("Failed to execute command: {}", self.command))?;
+                let stdout = child
+                    .stdout
+                    .take()
+                    .ok_or_else(|| anyhow!("Failed to capture stdout of: {}", self.command))?;
+
+                let mut lines = BufReader::new(stdout).lines();
+                while let Some(line) = lines.next_line().await.context("Failed to read command output")? {
+                    yield self.on_line(line);
+                }
+
+                let status = child.wait().await.context("Failed to wait for command")?;
+                warn!("Command '{}' exited with {status}, restarting", self.command);
+                tokio::time::sleep(self.restart_delay).await;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
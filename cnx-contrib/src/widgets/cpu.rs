@@ -1,3 +1,4 @@
+use crate::proc_sampler::{CpuTimes, ProcSampler};
 use anyhow::{anyhow, Result};
 use cnx::text::{Attributes, Text};
 use cnx::widgets::{Widget, WidgetStream};
@@ -13,7 +14,10 @@ use tokio_stream::StreamExt;
 pub struct Cpu {
     attr: Attributes,
     cpu_data: CpuData,
+    // The last usage shown, which is shown again if a tick has no data.
+    cpu_usage: u64,
     render: Option<Box<dyn Fn(u64) -> String>>,
+    sampler: Option<ProcSampler>,
 }
 
 impl Cpu {
@@ -41,10 +45,9 @@ impl Cpu {
     /// #
     /// # fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 21"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
@@ -58,51 +61,91 @@ impl Cpu {
         Ok(Cpu {
             attr,
             cpu_data,
+            cpu_usage: 0,
             render,
+            sampler: None,
         })
     }
 
-    fn tick(&mut self) -> Result<Vec<Text>> {
-        let cpu_data = CpuData::get_values()?;
+    /// Returns a new [`Cpu`] widget which reads `/proc/stat` through a
+    /// [`ProcSampler`] shared with other widgets.
+    pub fn with_sampler(self, sampler: ProcSampler) -> Result<Self> {
+        let cpu_data = CpuData::from(&sampler.snapshot()?.cpu);
+        Ok(Self {
+            cpu_data,
+            sampler: Some(sampler),
+            ..self
+        })
+    }
 
-        // https://github.com/jaor/xmobar/blob/61d075d3c275366c3344d59c058d7dd0baf21ef2/src/Xmobar/Plugins/Monitors/Cpu.hs#L128
-        let previous = &self.cpu_data;
-        let current = cpu_data;
-        let diff_total = (current.user_time - previous.user_time)
-            + (current.nice_time - previous.nice_time)
-            + (current.system_time - previous.system_time)
-            + (current.idle_time - previous.idle_time)
-            + (current.iowait_time - previous.iowait_time);
-        let percentage = match diff_total {
-            0 => 0.0,
-            _ => (current.total_time - previous.total_time) as f64 / diff_total as f64,
+    fn tick(&mut self) -> Result<Vec<Text>> {
+        let cpu_data = match &self.sampler {
+            Some(sampler) => CpuData::from(&sampler.snapshot()?.cpu),
+            None => CpuData::get_values()?,
         };
 
-        let cpu_usage = (percentage * 100.0) as u64;
+        if let Some(cpu_usage) = cpu_usage(&self.cpu_data, &cpu_data) {
+            self.cpu_usage = cpu_usage;
+        }
+        let cpu_usage = self.cpu_usage;
         let text = self
             .render
             .as_ref()
             .map_or(format!("{cpu_usage} %"), |x| (x)(cpu_usage));
-        self.cpu_data = current;
-        let texts = vec![Text {
-            attr: self.attr.clone(),
-            text,
-            stretch: false,
-            markup: true,
-        }];
+        self.cpu_data = cpu_data;
+        let texts = vec![Text::new(self.attr.clone(), text)];
         Ok(texts)
     }
 }
 
+// Returns the CPU usage between two samples, as a percentage, or `None` if
+// the counters went backwards (e.g. they wrapped, or a CPU was hotplugged).
+//
+// https://github.com/jaor/xmobar/blob/61d075d3c275366c3344d59c058d7dd0baf21ef2/src/Xmobar/Plugins/Monitors/Cpu.hs#L128
+fn cpu_usage(previous: &CpuData, current: &CpuData) -> Option<u64> {
+    let deltas = [
+        current.user_time - previous.user_time,
+        current.nice_time - previous.nice_time,
+        current.system_time - previous.system_time,
+        current.idle_time - previous.idle_time,
+        current.iowait_time - previous.iowait_time,
+    ];
+    if deltas.iter().sum::<i64>() < 0 {
+        return None;
+    }
+
+    // A single counter can still go backwards, so don't let it cancel out
+    // the others.
+    let [user, nice, system, idle, iowait] = deltas.map(|delta| delta.max(0));
+    let diff_busy = user + nice + system;
+    let diff_total = diff_busy + idle + iowait;
+    let percentage = match diff_total {
+        0 => 0.0,
+        _ => diff_busy as f64 / diff_total as f64,
+    };
+    Some((percentage * 100.0) as u64)
+}
+
 struct CpuData {
     user_time: i64,
     nice_time: i64,
     system_time: i64,
     idle_time: i64,
-    total_time: i64,
     iowait_time: i64,
 }
 
+impl From<&CpuTimes> for CpuData {
+    fn from(times: &CpuTimes) -> CpuData {
+        CpuData {
+            user_time: times.user as i64,
+            nice_time: times.nice as i64,
+            system_time: times.system as i64,
+            idle_time: times.idle as i64,
+            iowait_time: times.iowait as i64,
+        }
+    }
+}
+
 impl CpuData {
     fn get_values() -> Result<CpuData> {
         // https://www.kernel.org/doc/Documentation/filesystems/proc.txt
@@ -119,7 +162,6 @@ impl CpuData {
             nice_time: 0,
             system_time: 0,
             idle_time: 0,
-            total_time: 0,
             iowait_time: 0,
         };
         match val[..] {
@@ -134,7 +176,6 @@ impl CpuData {
                 cpu_data.system_time = system_time;
                 cpu_data.idle_time = idle_time;
                 cpu_data.iowait_time = iowait_time;
-                cpu_data.total_time = user_time + nice_time + system_time;
             }
             _ => return Err(anyhow!("Missing data in /proc/stat")),
         }
@@ -143,6 +184,10 @@ impl CpuData {
 }
 
 impl Widget for Cpu {
+    fn name(&self) -> &str {
+        "Cpu"
+    }
+
     fn into_stream(mut self: Box<Self>) -> Result<WidgetStream> {
         let ten_seconds = Duration::from_secs(10);
         let interval = time::interval(ten_seconds);
@@ -150,3 +195,28 @@ impl Widget for Cpu {
         Ok(Box::pin(stream))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{cpu_usage, CpuData};
+    use crate::proc_sampler::CpuTimes;
+
+    fn cpu_data(user: u64, system: u64, idle: u64) -> CpuData {
+        CpuData::from(&CpuTimes {
+            user,
+            system,
+            idle,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn usage_ignores_decreasing_counters() {
+        let previous = cpu_data(1000, 500, 8000);
+        assert_eq!(cpu_usage(&previous, &cpu_data(1300, 600, 8600)), Some(40));
+        // Every counter went backwards, so there is no data.
+        assert_eq!(cpu_usage(&previous, &cpu_data(10, 5, 80)), None);
+        // Only the system counter went backwards.
+        assert_eq!(cpu_usage(&previous, &cpu_data(1300, 400, 8700)), Some(30));
+    }
+}
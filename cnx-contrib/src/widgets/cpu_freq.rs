@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Context, Result};
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::fs;
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+
+/// Reads the current frequency (in MHz) of every core, ordered by core
+/// number.
+fn load_frequencies() -> Result<Vec<u32>> {
+    let entries =
+        fs::read_dir(CPU_PATH).with_context(|| format!("Failed to read directory: {CPU_PATH}"))?;
+    let mut cores = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let core = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix("cpu"))
+            .and_then(|n| n.parse::<u32>().ok());
+        let core = match core {
+            Some(core) => core,
+            None => continue,
+        };
+        // Offline cores, or those without a cpufreq driver, have no
+        // frequency to show.
+        let path = entry.path().join("cpufreq/scaling_cur_freq");
+        if let Ok(khz) = fs::read_to_string(path) {
+            cores.push((core, khz.trim().parse::<u32>()? / 1000));
+        }
+    }
+    if cores.is_empty() {
+        return Err(anyhow!("No CPU frequencies found in {CPU_PATH}"));
+    }
+    cores.sort_unstable();
+    Ok(cores.into_iter().map(|(_, mhz)| mhz).collect())
+}
+
+// Summarises per-core frequencies (in MHz) as e.g. `2.4 GHz`.
+fn format_frequency(frequencies: &[u32], max: bool) -> String {
+    let mhz = if max {
+        frequencies.iter().copied().max().unwrap_or(0) as f64
+    } else {
+        frequencies.iter().map(|&f| f as f64).sum::<f64>() / frequencies.len().max(1) as f64
+    };
+    format!("{:.1} GHz", mhz / 1000.0)
+}
+
+/// Shows the current CPU clock speed.
+///
+/// This widget reads each core's frequency from
+/// `/sys/devices/system/cpu/cpu*/cpufreq/scaling_cur_freq` every couple of
+/// seconds. By default, it shows the average across all cores, e.g.
+/// `2.4 GHz`.
+pub struct CpuFreq {
+    attr: Attributes,
+    max: bool,
+    render: Option<Box<dyn Fn(Vec<u32>) -> String>>,
+    update_interval: Duration,
+}
+
+impl CpuFreq {
+    /// Creates a new [`CpuFreq`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives the current frequency of each core
+    /// in MHz, ordered by core number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::cpu_freq::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(CpuFreq::new(attr, None).with_max());
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(attr: Attributes, render: Option<Box<dyn Fn(Vec<u32>) -> String>>) -> Self {
+        Self {
+            attr,
+            max: false,
+            render,
+            update_interval: Duration::from_secs(2),
+        }
+    }
+
+    /// Returns a new [`CpuFreq`] widget which shows the frequency of the
+    /// fastest core, rather than the average.
+    pub fn with_max(self) -> Self {
+        Self { max: true, ..self }
+    }
+
+    fn tick(&self) -> Result<Vec<Text>> {
+        let frequencies = load_frequencies()?;
+        let text = match &self.render {
+            Some(render) => (render)(frequencies),
+            None => format_frequency(&frequencies, self.max),
+        };
+        Ok(vec![Text::new(self.attr.clone(), text)])
+    }
+}
+
+impl Widget for CpuFreq {
+    fn name(&self) -> &str {
+        "CpuFreq"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let interval = time::interval(self.update_interval);
+        let stream = IntervalStream::new(interval).map(move |_| self.tick());
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_frequency;
+
+    #[test]
+    fn formats_frequency() {
+        let frequencies = [1200, 3600, 2400];
+        assert_eq!(format_frequency(&frequencies, false), "2.4 GHz");
+        assert_eq!(format_frequency(&frequencies, true), "3.6 GHz");
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::{anyhow, Context, Result};
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::fs;
+use std::time::{Duration, Instant};
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+
+// `/proc/diskstats` counts sectors of 512 bytes, whatever the device's
+// actual sector size.
+const SECTOR_SIZE: u64 = 512;
+
+/// The rate at which a block device is being read from and written to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IoRates {
+    /// Bytes read per second.
+    pub read: u64,
+    /// Bytes written per second.
+    pub write: u64,
+}
+
+// The number of sectors read and written by a device, as of `taken`.
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    taken: Instant,
+    sectors_read: u64,
+    sectors_written: u64,
+}
+
+impl Sample {
+    fn read(device: &str) -> Result<Sample> {
+        let contents =
+            fs::read_to_string("/proc/diskstats").context("Failed to read /proc/diskstats")?;
+        let (sectors_read, sectors_written) = parse_diskstats(&contents, device)?;
+        Ok(Sample {
+            taken: Instant::now(),
+            sectors_read,
+            sectors_written,
+        })
+    }
+
+    // Returns the rates between an earlier sample and this one.
+    fn rates_since(&self, previous: &Sample) -> IoRates {
+        let elapsed = self.taken.duration_since(previous.taken).as_secs_f64();
+        if elapsed <= 0.0 {
+            return IoRates::default();
+        }
+        // The counters wrap around on 32-bit kernels.
+        let rate = |now: u64, before: u64| {
+            (now.saturating_sub(before) as f64 * SECTOR_SIZE as f64 / elapsed) as u64
+        };
+        IoRates {
+            read: rate(self.sectors_read, previous.sectors_read),
+            write: rate(self.sectors_written, previous.sectors_written),
+        }
+    }
+}
+
+// Finds the sectors read and written by `device` in the contents of
+// `/proc/diskstats`, whose lines look like:
+//
+//   8       0 sda 1234 56 78901 234 5678 90 123456 789 0 1011 1213 ...
+//
+// See https://www.kernel.org/doc/Documentation/ABI/testing/procfs-diskstats
+fn parse_diskstats(contents: &str, device: &str) -> Result<(u64, u64)> {
+    for line in contents.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.get(2) != Some(&device) {
+            continue;
+        }
+        return match fields[..] {
+            [_, _, _, _, _, read, _, _, _, written, ..] => Ok((read.parse()?, written.parse()?)),
+            _ => Err(anyhow!("Missing data for {device} in /proc/diskstats")),
+        };
+    }
+    Err(anyhow!("No block device named {device} in /proc/diskstats"))
+}
+
+// Formats a number of bytes compactly, e.g. `5M`.
+fn format_bytes(bytes: u64) -> String {
+    let mut value = bytes as f64;
+    for unit in ["B", "K", "M", "G"] {
+        if value < 1024.0 {
+            return format!("{value:.0}{unit}");
+        }
+        value /= 1024.0;
+    }
+    format!("{value:.0}T")
+}
+
+/// Shows how quickly a block device is being read from and written to.
+///
+/// This widget reads `/proc/diskstats` every couple of seconds, and shows the
+/// number of bytes read and written per second since the last reading, e.g.
+/// `sda ↓5M ↑1M`.
+pub struct DiskIO {
+    attr: Attributes,
+    device: String,
+    // The last reading, so that the rates can be worked out from the next.
+    previous: Option<Sample>,
+    render: Option<Box<dyn Fn(IoRates) -> String>>,
+    update_interval: Duration,
+}
+
+impl DiskIO {
+    /// Creates a new [`DiskIO`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `device` - The name of the block device, as listed in
+    /// `/proc/diskstats`, e.g. `sda` or `nvme0n1`.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. [`IoRates`] holds the bytes read and written
+    /// per second.
+    ///
+    /// If `device` doesn't exist, the widget shows an error once the bar is
+    /// running.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::disk_io::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(DiskIO::new(attr, "sda", None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new<S: Into<String>>(
+        attr: Attributes,
+        device: S,
+        render: Option<Box<dyn Fn(IoRates) -> String>>,
+    ) -> DiskIO {
+        DiskIO {
+            attr,
+            device: device.into(),
+            previous: None,
+            render,
+            update_interval: Duration::from_secs(2),
+        }
+    }
+
+    fn tick(&mut self) -> Result<Vec<Text>> {
+        let sample = Sample::read(&self.device)?;
+        // There is nothing to compare the first reading with.
+        let rates = self
+            .previous
+            .map_or_else(IoRates::default, |previous| sample.rates_since(&previous));
+        self.previous = Some(sample);
+
+        let text = match &self.render {
+            Some(render) => (render)(rates),
+            None => format!(
+                "{} ↓{} ↑{}",
+                self.device,
+                format_bytes(rates.read),
+                format_bytes(rates.write)
+            ),
+        };
+        Ok(vec![Text::new(self.attr.clone(), text)])
+    }
+}
+
+impl Widget for DiskIO {
+    fn name(&self) -> &str {
+        "DiskIO"
+    }
+
+    fn into_stream(mut self: Box<Self>) -> Result<WidgetStream> {
+        let interval = time::interval(self.update_interval);
+        let stream = IntervalStream::new(interval).map(move |_| self.tick());
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{format_bytes, parse_diskstats};
+
+    #[test]
+    fn parses_diskstats() {
+        let contents = "   8       0 sda 1234 56 78901 234 5678 90 123456 789 0 1011 1213\n\
+                        8       1 sda1 100 0 2000 10 50 0 4000 20 0 30 30\n";
+        assert_eq!(parse_diskstats(contents, "sda").unwrap(), (78901, 123456));
+        assert_eq!(parse_diskstats(contents, "sda1").unwrap(), (2000, 4000));
+        assert!(parse_diskstats(contents, "sdb").is_err());
+    }
+
+    #[test]
+    fn formats_bytes() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5M");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024 * 1024), "3T");
+    }
+}
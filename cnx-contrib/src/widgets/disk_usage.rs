@@ -1,6 +1,6 @@
 use anyhow::Result;
 use byte_unit::{Byte, ByteUnit};
-use cnx::text::{Attributes, Text};
+use cnx::text::{escape_markup, Attributes, Text};
 use cnx::widgets::{Widget, WidgetStream};
 use nix::sys::statvfs::statvfs;
 use std::time::Duration;
@@ -11,6 +11,8 @@ use tokio_stream::StreamExt;
 /// Represent Information about the mounted filesystem
 #[derive(Debug)]
 pub struct DiskInfo {
+    /// Path that the information was read for, as given to [`DiskUsage::new`]
+    pub path: String,
     /// Total size of the filesystem
     pub total: Byte,
     /// Total used space of the filesystem
@@ -29,16 +31,21 @@ impl DiskInfo {
         let used = byte_unit::Byte::from_bytes(used as u128);
         let free: Byte = byte_unit::Byte::from_bytes(available as u128);
 
-        let disk_info = DiskInfo { total, used, free };
+        let disk_info = DiskInfo {
+            path: path.to_owned(),
+            total,
+            used,
+            free,
+        };
         Ok(disk_info)
     }
 }
 
 /// Disk usage widget to show current usage and remaining free space
-/// in the mounted filesystem.
+/// in one or more mounted filesystems.
 pub struct DiskUsage {
     attr: Attributes,
-    path: String,
+    paths: Vec<String>,
     render: Option<Box<dyn Fn(DiskInfo) -> String>>,
 }
 
@@ -50,11 +57,12 @@ impl DiskUsage {
     /// * `attr` - Represents `Attributes` which controls properties like
     /// `Font`, foreground and background color etc.
     ///
-    /// * `path` - Pathname of any file within the mounted filesystem.
-
+    /// * `paths` - Pathname of any file within each mounted filesystem. A
+    /// separate text is shown for each path, in the order given.
+    ///
     /// * `render` - We use the closure to control the way output is
-    /// displayed in the bar. [`DiskInfo`] represents the details
-    /// about the mounted filesystem.
+    /// displayed in the bar. It is called once for each path, and
+    /// [`DiskInfo`] represents the details about its mounted filesystem.
     ///
     /// # Examples
     ///
@@ -69,49 +77,62 @@ impl DiskUsage {
     /// #
     /// # fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 21"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
-    /// cnx.add_widget(DiskUsage::new(attr, "/home".into(), None));
+    /// cnx.add_widget(DiskUsage::new(attr, vec!["/".into(), "/home".into()], None));
     /// # Ok(())
     /// # }
     /// # fn main() { run().unwrap(); }
     /// ```
     pub fn new(
         attr: Attributes,
-        path: String,
+        paths: Vec<String>,
         render: Option<Box<dyn Fn(DiskInfo) -> String>>,
     ) -> Self {
-        Self { attr, render, path }
+        Self {
+            attr,
+            render,
+            paths,
+        }
     }
 
     fn tick(&self) -> Result<Vec<Text>> {
-        let disk_info = DiskInfo::new(self.path.as_ref())?;
-        let disk_default_str = format!(
-            "Disk: {}/{}",
-            disk_info.used.get_adjusted_unit(ByteUnit::GiB).format(0),
-            disk_info.total.get_adjusted_unit(ByteUnit::GiB).format(0)
-        );
+        self.paths
+            .iter()
+            .map(|path| {
+                let disk_info = DiskInfo::new(path)?;
+                // Only label the filesystems when there's more than one.
+                let label = if self.paths.len() > 1 {
+                    escape_markup(path)
+                } else {
+                    "Disk".to_owned()
+                };
+                let disk_default_str = format!(
+                    "{}: {}/{}",
+                    label,
+                    disk_info.used.get_adjusted_unit(ByteUnit::GiB).format(0),
+                    disk_info.total.get_adjusted_unit(ByteUnit::GiB).format(0)
+                );
 
-        let text: String = self
-            .render
-            .as_ref()
-            .map_or(disk_default_str, |disk| (disk)(disk_info));
-        let texts = vec![Text {
-            attr: self.attr.clone(),
-            text,
-            stretch: false,
-            markup: true,
-        }];
-        Ok(texts)
+                let text: String = self
+                    .render
+                    .as_ref()
+                    .map_or(disk_default_str, |disk| (disk)(disk_info));
+                Ok(Text::new(self.attr.clone(), text))
+            })
+            .collect()
     }
 }
 
 impl Widget for DiskUsage {
+    fn name(&self) -> &str {
+        "DiskUsage"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let one_hour = Duration::from_secs(3600);
         let interval = time::interval(one_hour);
@@ -120,3 +141,17 @@ impl Widget for DiskUsage {
         Ok(Box::pin(stream))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::DiskInfo;
+
+    #[test]
+    fn root_filesystem() {
+        let disk_info = DiskInfo::new("/").unwrap();
+        let total = disk_info.total.get_bytes();
+        assert!(total > 0);
+        assert!(disk_info.used.get_bytes() <= total);
+        assert!(disk_info.free.get_bytes() <= total);
+    }
+}
@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use async_stream::stream;
+use bollard::container::ListContainersOptions;
+use bollard::models::ContainerSummary;
+use bollard::system::EventsOptions;
+use bollard::Docker as DockerClient;
+use cnx::text::{escape_markup, Attributes, Color, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use log::warn;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::StreamExt;
+
+async fn list_containers(docker: &DockerClient) -> Result<Vec<ContainerSummary>> {
+    let options = ListContainersOptions::<String> {
+        all: true,
+        ..Default::default()
+    };
+    docker
+        .list_containers(Some(options))
+        .await
+        .context("Failed to list Docker containers")
+}
+
+fn is_running(container: &ContainerSummary) -> bool {
+    container.state.as_deref() == Some("running")
+}
+
+// Docker reports container names with a leading `/`.
+fn has_name(container: &ContainerSummary, name: &str) -> bool {
+    container
+        .names
+        .iter()
+        .flatten()
+        .any(|n| n.trim_start_matches('/') == name)
+}
+
+/// Shows how many Docker containers are running.
+///
+/// This widget talks to the Docker daemon over its socket (usually
+/// `/var/run/docker.sock`), and updates whenever a container changes state.
+/// If the daemon's event stream isn't available, it falls back to polling.
+/// While the daemon can't be reached, `🐳 ?` is shown instead, in the
+/// warning color.
+///
+/// By default, it shows the number of running containers out of the total,
+/// e.g. `🐳 5/6`, followed by any of the `watched` containers which aren't
+/// running, in the warning color.
+pub struct Docker {
+    attr: Attributes,
+    warning_color: Color,
+    watched: Vec<String>,
+    render: Option<Box<dyn Fn(Vec<ContainerSummary>) -> String>>,
+    poll_interval: Duration,
+}
+
+impl Docker {
+    /// Creates a new [`Docker`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `warning_color` - The color used while the daemon can't be reached
+    /// and for `watched` containers which aren't running.
+    ///
+    /// * `watched` - The names of containers which should be running. Any
+    /// which aren't are highlighted.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives a summary of every container,
+    /// including those which have stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::docker::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Docker::new(attr, Color::red(), vec!["postgres"], None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new<S: Into<String>>(
+        attr: Attributes,
+        warning_color: Color,
+        watched: Vec<S>,
+        render: Option<Box<dyn Fn(Vec<ContainerSummary>) -> String>>,
+    ) -> Self {
+        Self {
+            attr,
+            warning_color,
+            watched: watched.into_iter().map(Into::into).collect(),
+            render,
+            poll_interval: Duration::from_secs(30),
+        }
+    }
+
+    // Shown while the daemon can't be reached.
+    fn on_error(&self) -> Vec<Text> {
+        let mut attr = self.attr.clone();
+        attr.fg_color = self.warning_color.clone();
+        vec![Text::new(attr, "🐳 ?")]
+    }
+
+    fn on_change(&self, containers: Vec<ContainerSummary>) -> Vec<Text> {
+        if let Some(render) = &self.render {
+            return vec![Text::new(self.attr.clone(), (render)(containers))];
+        }
+
+        let running = containers.iter().filter(|c| is_running(c)).count();
+        let mut texts = vec![Text::new(
+            self.attr.clone(),
+            format!("🐳 {running}/{}", containers.len()),
+        )];
+        for name in &self.watched {
+            let up = containers
+                .iter()
+                .any(|c| has_name(c, name) && is_running(c));
+            if !up {
+                let mut attr = self.attr.clone();
+                attr.fg_color = self.warning_color.clone();
+                texts.push(Text::new(attr, format!("{} down", escape_markup(name))));
+            }
+        }
+        texts
+    }
+}
+
+impl Widget for Docker {
+    fn name(&self) -> &str {
+        "Docker"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let docker = DockerClient::connect_with_local_defaults()
+            .context("Failed to connect to the Docker daemon")?;
+        let events_options = || EventsOptions::<String> {
+            filters: HashMap::from([("type".to_owned(), vec!["container".to_owned()])]),
+            ..Default::default()
+        };
+
+        let stream = stream! {
+            let mut events = Box::pin(docker.events(Some(events_options())));
+            loop {
+                match list_containers(&docker).await {
+                    Ok(containers) => yield Ok(self.on_change(containers)),
+                    // The daemon may be restarting, so show that it is
+                    // unavailable and keep trying, rather than ending the
+                    // stream.
+                    Err(err) => {
+                        warn!("{err:#}");
+                        yield Ok(self.on_error());
+                        time::sleep(self.poll_interval).await;
+                        events = Box::pin(docker.events(Some(events_options())));
+                        continue;
+                    }
+                }
+                match events.next().await {
+                    Some(Ok(_)) => {}
+                    // The daemon doesn't support events or the connection
+                    // broke, so poll instead, trying to subscribe again
+                    // each time.
+                    _ => {
+                        time::sleep(self.poll_interval).await;
+                        events = Box::pin(docker.events(Some(events_options())));
+                    }
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
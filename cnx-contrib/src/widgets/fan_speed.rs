@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+
+const HWMON_PATH: &str = "/sys/class/hwmon";
+
+/// Reads the speed (in RPM) of every fan of every hwmon device, keyed by its
+/// label.
+fn load_fans() -> Result<HashMap<String, u32>> {
+    let mut fans = HashMap::new();
+    let devices = fs::read_dir(HWMON_PATH)
+        .with_context(|| format!("Failed to read directory: {HWMON_PATH}"))?;
+    for device in devices {
+        let device = device?.path();
+        // Devices without fans, or which can't currently be read, are skipped
+        // rather than failing the whole widget.
+        let entries = match fs::read_dir(&device) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let name = entry?.file_name();
+            let fan = name
+                .to_str()
+                .and_then(|name| name.strip_suffix("_input"))
+                .filter(|fan| fan.starts_with("fan"));
+            if let Some(fan) = fan {
+                if let Ok((label, rpm)) = load_fan(&device, fan) {
+                    fans.insert(label, rpm);
+                }
+            }
+        }
+    }
+    Ok(fans)
+}
+
+fn load_fan(device: &Path, fan: &str) -> Result<(String, u32)> {
+    // Not every driver provides labels, so fall back to the name of the fan
+    // (e.g. `fan1`).
+    let label = fs::read_to_string(device.join(format!("{fan}_label")))
+        .map(|label| label.trim().to_owned())
+        .unwrap_or_else(|_| fan.to_owned());
+    let rpm = fs::read_to_string(device.join(format!("{fan}_input")))?
+        .trim()
+        .parse()?;
+    Ok((label, rpm))
+}
+
+/// Shows the speed of one or more fans.
+///
+/// This widget reads the speed of the named fans directly from
+/// [`/sys/class/hwmon/`], so unlike the `Sensors` widget it doesn't need
+/// `lm_sensors` to be installed. Fans are identified by the contents of their
+/// `fan*_label` file, or by their name (e.g. `fan1`) if the driver doesn't
+/// provide a label.
+///
+/// [`/sys/class/hwmon/`]: https://www.kernel.org/doc/Documentation/hwmon/sysfs-interface
+pub struct FanSpeed {
+    update_interval: Duration,
+    attr: Attributes,
+    fans: Vec<String>,
+    render: Option<Box<dyn Fn(Vec<(String, u32)>) -> String>>,
+}
+
+impl FanSpeed {
+    /// Creates a new [`FanSpeed`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `fans` - The labels of the fans to show. You can list the available
+    /// labels with `cat /sys/class/hwmon/hwmon*/fan*_label`.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives a `(label, rpm)` pair for each of
+    /// the requested fans that could be read, in the order they were
+    /// requested.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::fan_speed::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(FanSpeed::new(attr, vec!["cpu_fan"], None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new<S: Into<String>>(
+        attr: Attributes,
+        fans: Vec<S>,
+        render: Option<Box<dyn Fn(Vec<(String, u32)>) -> String>>,
+    ) -> Self {
+        Self {
+            update_interval: Duration::from_secs(5),
+            attr,
+            fans: fans.into_iter().map(Into::into).collect(),
+            render,
+        }
+    }
+
+    fn tick(&self) -> Result<Vec<Text>> {
+        let mut speeds = load_fans()?;
+        let values: Vec<(String, u32)> = self
+            .fans
+            .iter()
+            .filter_map(|fan| speeds.remove(fan).map(|rpm| (fan.clone(), rpm)))
+            .collect();
+
+        let text = match &self.render {
+            Some(render) => (render)(values),
+            None => values
+                .iter()
+                .map(|(label, rpm)| format!("{label}: {rpm} RPM"))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+
+        Ok(vec![
+            Text::new(self.attr.clone(), text).with_markup(self.render.is_some())
+        ])
+    }
+}
+
+impl Widget for FanSpeed {
+    fn name(&self) -> &str {
+        "FanSpeed"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let interval = time::interval(self.update_interval);
+        let stream = IntervalStream::new(interval).map(move |_| self.tick());
+
+        Ok(Box::pin(stream))
+    }
+}
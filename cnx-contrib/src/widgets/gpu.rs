@@ -0,0 +1,248 @@
+use anyhow::{anyhow, Context, Result};
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+
+#[cfg(feature = "gpu-nvidia")]
+use nvml_wrapper::{enum_wrappers::device::TemperatureSensor, Nvml};
+
+const DRM_PATH: &str = "/sys/class/drm";
+
+/// Represents the state of a GPU.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GpuInfo {
+    /// Utilization of the GPU, as a percentage
+    pub utilization: u32,
+    /// Temperature of the GPU, in °C
+    pub temperature: u32,
+    /// Video memory in use, in bytes
+    pub memory_used: u64,
+    /// Total video memory, in bytes
+    pub memory_total: u64,
+}
+
+/// The driver that the [`Gpu`] widget reads its state from.
+enum Backend {
+    /// Read the `amdgpu` driver's sysfs files for the given device.
+    Amd { device: Option<PathBuf> },
+    /// Query the NVIDIA driver using NVML.
+    #[cfg(feature = "gpu-nvidia")]
+    Nvidia { index: u32 },
+}
+
+/// Returns the `device` directory of the first DRM card that reports its
+/// utilization, i.e. the first card driven by `amdgpu`.
+fn find_amd_device() -> Result<PathBuf> {
+    let entries =
+        fs::read_dir(DRM_PATH).with_context(|| format!("Failed to read directory: {DRM_PATH}"))?;
+    let mut devices = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            // Skip connectors such as `card0-DP-1`.
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.strip_prefix("card")
+                .map_or(false, |n| n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(|entry| entry.path().join("device"))
+        .filter(|device| device.join("gpu_busy_percent").exists())
+        .collect::<Vec<_>>();
+    devices.sort();
+    devices
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("No GPU reporting gpu_busy_percent in {DRM_PATH}"))
+}
+
+fn load_value<T>(path: &Path) -> Result<T>
+where
+    T: std::str::FromStr,
+    <T as std::str::FromStr>::Err: std::error::Error + Send + Sync + 'static,
+{
+    let value = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?
+        .trim()
+        .parse()?;
+    Ok(value)
+}
+
+fn load_amd_temperature(device: &Path) -> Result<u32> {
+    let hwmon = device.join("hwmon");
+    let entries = fs::read_dir(&hwmon)
+        .with_context(|| format!("Failed to read directory: {}", hwmon.display()))?;
+    for entry in entries {
+        let path = entry?.path().join("temp1_input");
+        if path.exists() {
+            let millidegrees: u32 = load_value(&path)?;
+            return Ok(millidegrees / 1000);
+        }
+    }
+    Err(anyhow!("No temperature sensor in {}", hwmon.display()))
+}
+
+fn load_amd(device: &Path) -> Result<GpuInfo> {
+    Ok(GpuInfo {
+        utilization: load_value(&device.join("gpu_busy_percent"))?,
+        temperature: load_amd_temperature(device)?,
+        memory_used: load_value(&device.join("mem_info_vram_used"))?,
+        memory_total: load_value(&device.join("mem_info_vram_total"))?,
+    })
+}
+
+#[cfg(feature = "gpu-nvidia")]
+fn load_nvidia(nvml: &Nvml, index: u32) -> Result<GpuInfo> {
+    let device = nvml
+        .device_by_index(index)
+        .with_context(|| format!("Failed to find NVIDIA GPU {index}"))?;
+    let memory = device.memory_info()?;
+    Ok(GpuInfo {
+        utilization: device.utilization_rates()?.gpu,
+        temperature: device.temperature(TemperatureSensor::Gpu)?,
+        memory_used: memory.used,
+        memory_total: memory.total,
+    })
+}
+
+/// Shows the utilization and temperature of a GPU.
+///
+/// By default, this widget reads the state of an AMD GPU from the `amdgpu`
+/// driver's files in [`/sys/class/drm/`], and needs no additional
+/// dependencies. NVIDIA GPUs are supported through [`Gpu::new_nvidia()`],
+/// which uses NVML and is behind the `gpu-nvidia` feature.
+///
+/// [`/sys/class/drm/`]: https://docs.kernel.org/gpu/amdgpu/driver-misc.html
+pub struct Gpu {
+    update_interval: Duration,
+    attr: Attributes,
+    backend: Backend,
+    render: Option<Box<dyn Fn(GpuInfo) -> String>>,
+}
+
+impl Gpu {
+    /// Creates a new [`Gpu`] widget for an AMD GPU.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `card` - The name of the DRM card to show, e.g. `card1`. If
+    /// `None`, the first card reporting its utilization is used.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. [`GpuInfo`] represents the current state of
+    /// the GPU.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::gpu::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Gpu::new(attr, None, None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(
+        attr: Attributes,
+        card: Option<String>,
+        render: Option<Box<dyn Fn(GpuInfo) -> String>>,
+    ) -> Self {
+        let device = card.map(|card| Path::new(DRM_PATH).join(card).join("device"));
+        Self {
+            update_interval: Duration::from_secs(5),
+            attr,
+            backend: Backend::Amd { device },
+            render,
+        }
+    }
+
+    /// Creates a new [`Gpu`] widget for an NVIDIA GPU.
+    ///
+    /// This queries the NVIDIA driver using NVML, so the driver's
+    /// `libnvidia-ml` library must be installed.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `index` - The NVML index of the GPU to show. The first GPU has an
+    /// index of `0`.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. [`GpuInfo`] represents the current state of
+    /// the GPU.
+    #[cfg(feature = "gpu-nvidia")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "gpu-nvidia")))]
+    pub fn new_nvidia(
+        attr: Attributes,
+        index: u32,
+        render: Option<Box<dyn Fn(GpuInfo) -> String>>,
+    ) -> Self {
+        Self {
+            update_interval: Duration::from_secs(5),
+            attr,
+            backend: Backend::Nvidia { index },
+            render,
+        }
+    }
+
+    fn on_change(&self, info: GpuInfo) -> Vec<Text> {
+        let text = match &self.render {
+            Some(render) => (render)(info),
+            None => format!("GPU: {}% {}°C", info.utilization, info.temperature),
+        };
+
+        vec![Text::new(self.attr.clone(), text).with_markup(self.render.is_some())]
+    }
+}
+
+impl Widget for Gpu {
+    fn name(&self) -> &str {
+        "Gpu"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let load: Box<dyn Fn() -> Result<GpuInfo>> = match &self.backend {
+            Backend::Amd { device } => {
+                let device = match device {
+                    Some(device) => device.clone(),
+                    None => find_amd_device().context("Initialising Gpu")?,
+                };
+                Box::new(move || load_amd(&device))
+            }
+            #[cfg(feature = "gpu-nvidia")]
+            Backend::Nvidia { index } => {
+                let index = *index;
+                let nvml = Nvml::init().context("Failed to initialise NVML")?;
+                Box::new(move || load_nvidia(&nvml, index))
+            }
+        };
+
+        let interval = time::interval(self.update_interval);
+        let stream =
+            IntervalStream::new(interval).map(move |_| load().map(|info| self.on_change(info)));
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use cnx::text::{Attributes, Text};
+use cnx::text::{escape_markup, Attributes, Text};
 use cnx::widgets::{Widget, WidgetStream};
 use process_stream::{Process, ProcessExt, StreamExt};
 use serde_derive::Deserialize;
@@ -76,14 +76,14 @@ impl LeftWM {
     /// #
     /// # fn run() -> Result<()> {
     /// let focused = Attributes {
-    ///     font: Font::new("SourceCodePro 14"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: Some(Color::blue()),
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 14".into(),
+    ///     background: Background::Solid(Color::blue()),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let empty = Attributes {
-    ///     bg_color: None,
+    ///     background: Background::None,
     ///     ..focused.clone()
     /// };
     /// let busy = Attributes {
@@ -129,12 +129,7 @@ impl LeftWM {
                     } else {
                         self.attrs.empty.clone()
                     };
-                    Text {
-                        attr,
-                        text: t.name.clone(),
-                        stretch: false,
-                        markup: true,
-                    }
+                    Text::new(attr, escape_markup(&t.name))
                 })
                 .collect();
             Ok(text)
@@ -145,6 +140,10 @@ impl LeftWM {
 }
 
 impl Widget for LeftWM {
+    fn name(&self) -> &str {
+        "LeftWM"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let mut state = Process::new("leftwm-state");
         let s = state
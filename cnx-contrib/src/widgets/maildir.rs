@@ -0,0 +1,145 @@
+use anyhow::{Context, Result};
+use async_stream::try_stream;
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use std::fs;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use tokio::io::unix::AsyncFd;
+
+// Counts the unread messages in a Maildir, i.e. the files in its `new/`
+// directory. Files starting with a `.` aren't messages.
+fn count_new(maildir: &Path) -> Result<usize> {
+    let new = maildir.join("new");
+    let entries =
+        fs::read_dir(&new).with_context(|| format!("Failed to read {}", new.display()))?;
+    let mut count = 0;
+    for entry in entries {
+        if !entry?.file_name().to_string_lossy().starts_with('.') {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+// Owns an inotify instance, closing it when dropped.
+struct InotifyWatcher(Inotify);
+
+impl AsRawFd for InotifyWatcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl Drop for InotifyWatcher {
+    fn drop(&mut self) {
+        let _ = nix::unistd::close(self.0.as_raw_fd());
+    }
+}
+
+/// Shows the number of unread messages in one or more Maildirs.
+///
+/// Unread messages are those in the `new/` directory of each Maildir. This
+/// widget uses inotify to update as soon as mail arrives or is read, rather
+/// than polling. It shows the total, e.g. `✉ 2`, and is hidden when there is
+/// no unread mail.
+pub struct Maildir {
+    attr: Attributes,
+    maildirs: Vec<PathBuf>,
+}
+
+impl Maildir {
+    /// Creates a new [`Maildir`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `maildirs` - The paths of the Maildirs to watch, i.e. the
+    /// directories which contain `cur/`, `new/` and `tmp/`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::maildir::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Maildir::new(attr, vec!["/home/user/Mail/INBOX".into()]));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(attr: Attributes, maildirs: Vec<PathBuf>) -> Maildir {
+        Maildir { attr, maildirs }
+    }
+
+    fn watch(&self) -> Result<InotifyWatcher> {
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)
+            .context("Failed to initialise inotify")?;
+        let watcher = InotifyWatcher(inotify);
+        // New mail is moved into `new/` from `tmp/`, and moved out of it into
+        // `cur/` once it has been read.
+        let flags = AddWatchFlags::IN_CREATE
+            | AddWatchFlags::IN_DELETE
+            | AddWatchFlags::IN_MOVED_FROM
+            | AddWatchFlags::IN_MOVED_TO;
+        for maildir in &self.maildirs {
+            let new = maildir.join("new");
+            watcher
+                .0
+                .add_watch(&new, flags)
+                .with_context(|| format!("Failed to watch {}", new.display()))?;
+        }
+        Ok(watcher)
+    }
+
+    fn on_change(&self) -> Result<Vec<Text>> {
+        let mut unread = 0;
+        for maildir in &self.maildirs {
+            unread += count_new(maildir)?;
+        }
+
+        if unread == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(vec![Text::new(self.attr.clone(), format!("✉ {unread}"))])
+    }
+}
+
+impl Widget for Maildir {
+    fn name(&self) -> &str {
+        "Maildir"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let watcher = AsyncFd::new(self.watch()?)?;
+        let stream = try_stream! {
+            yield self.on_change()?;
+            loop {
+                let mut guard = watcher.readable().await?;
+                // We don't care what the events are, so just drain them and
+                // count the messages again.
+                while guard.get_inner().0.read_events().is_ok() {}
+                guard.clear_ready();
+                yield self.on_change()?;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
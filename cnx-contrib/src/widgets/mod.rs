@@ -1,20 +1,76 @@
 /// Battery widget to shows the current capacity
 pub mod battery;
+/// Bluetooth widget to show connected Bluetooth devices and their battery levels
+#[cfg(feature = "bluetooth")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bluetooth")))]
+pub mod bluetooth;
 /// Command widget to show output of a CLI command
 pub mod command;
+/// Command stream widget to show each line of output from a long-running command
+pub mod command_stream;
 /// CPU widget to show the current CPU consumption
 pub mod cpu;
+/// CPU frequency widget to show the current CPU clock speed
+pub mod cpu_freq;
+/// Disk I/O widget to show how quickly a block device is being read from and written to
+pub mod disk_io;
 /// Disk usage widget to show current usage and remaining free space
 pub mod disk_usage;
+/// Docker widget to show how many Docker containers are running
+#[cfg(feature = "docker")]
+#[cfg_attr(docsrs, doc(cfg(feature = "docker")))]
+pub mod docker;
+/// Fan speed widget to show fan speeds read directly from sysfs
+pub mod fan_speed;
+/// GPU widget to show the utilization and temperature of a GPU
+pub mod gpu;
 /// LeftWM widget that subscribes to leftwm-state and streams the monitors and tags upfate
 #[cfg(feature = "leftwm")]
 #[cfg_attr(docsrs, doc(cfg(feature = "leftwm")))]
 pub mod leftwm;
+/// Maildir widget to show the number of unread messages in one or more Maildirs
+pub mod maildir;
+/// Moon phase widget to show the current phase of the moon
+pub mod moon_phase;
+/// Network status widget to show the active NetworkManager connection
+#[cfg(feature = "networkmanager")]
+#[cfg_attr(docsrs, doc(cfg(feature = "networkmanager")))]
+pub mod network_status;
+/// Notifications widget to show the number of open desktop notifications
+#[cfg(feature = "notifications")]
+#[cfg_attr(docsrs, doc(cfg(feature = "notifications")))]
+pub mod notifications;
+/// Ping widget to show the round-trip latency to a host
+#[cfg(feature = "ping")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ping")))]
+pub mod ping;
+/// Pomodoro widget to show a work/break countdown timer
+pub mod pomodoro;
+/// Pressure widget to show how much time tasks spend stalled waiting for the CPU, memory or IO
+pub mod pressure;
 /// Sensor widget to periodically parses and displays the output of the sensors provided by the system.
 pub mod sensors;
+/// Sun times widget to show the next sunrise or sunset
+#[cfg(feature = "sun-times")]
+#[cfg_attr(docsrs, doc(cfg(feature = "sun-times")))]
+pub mod sun_times;
+/// Systemd unit widget to show whether systemd units are active or have failed
+#[cfg(feature = "systemd")]
+#[cfg_attr(docsrs, doc(cfg(feature = "systemd")))]
+pub mod systemd_unit;
+/// Tasks widget to show the number of Taskwarrior tasks matching a filter
+pub mod tasks;
+/// Thermal zone widget to show temperatures read directly from sysfs
+pub mod thermal_zone;
+/// Ticker widget to show the latest price of a stock or currency
+#[cfg(feature = "ticker")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ticker")))]
+pub mod ticker;
 /// Volume widget to show the current volume/mute status of the default output device.
 pub mod volume;
 /// Weather widget to show temperature of your location
+#[cfg(feature = "weather")]
+#[cfg_attr(docsrs, doc(cfg(feature = "weather")))]
 pub mod weather;
 /// Wireless widget to show wireless strength of your SSID
 #[cfg(feature = "wireless")]
@@ -0,0 +1,148 @@
+use anyhow::Result;
+use chrono::Utc;
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+
+// The average length of a lunar cycle, in days.
+const SYNODIC_MONTH: f64 = 29.530_588_853;
+
+// A known new moon (2000-01-06 18:14 UTC), as a Unix timestamp.
+const REFERENCE_NEW_MOON: i64 = 947_182_440;
+
+const PHASES: [(&str, &str); 8] = [
+    ("🌑", "New Moon"),
+    ("🌒", "Waxing Crescent"),
+    ("🌓", "First Quarter"),
+    ("🌔", "Waxing Gibbous"),
+    ("🌕", "Full Moon"),
+    ("🌖", "Waning Gibbous"),
+    ("🌗", "Last Quarter"),
+    ("🌘", "Waning Crescent"),
+];
+
+// Returns how far through the lunar cycle the moon is at `timestamp`, where
+// 0.0 is a new moon and 0.5 is a full moon.
+fn phase_at(timestamp: i64) -> f64 {
+    let days = (timestamp - REFERENCE_NEW_MOON) as f64 / (24.0 * 60.0 * 60.0);
+    (days / SYNODIC_MONTH).rem_euclid(1.0)
+}
+
+// Returns the glyph and name of the closest of the eight named phases.
+fn phase_name(phase: f64) -> (&'static str, &'static str) {
+    let idx = (phase * PHASES.len() as f64).round() as usize % PHASES.len();
+    PHASES[idx]
+}
+
+/// Shows the current phase of the moon.
+///
+/// The phase is calculated locally, so no network access is needed. By
+/// default, this shows the glyph for the phase, e.g. `🌔`, optionally followed
+/// by its name, e.g. `🌔 Waxing Gibbous`.
+pub struct MoonPhase {
+    attr: Attributes,
+    show_name: bool,
+    render: Option<Box<dyn Fn(f64) -> String>>,
+    update_interval: Duration,
+}
+
+impl MoonPhase {
+    /// Creates a new [`MoonPhase`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `show_name` - Whether to show the name of the phase after its glyph.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It is passed how far through the lunar cycle
+    /// the moon is, from `0.0` (new moon) through `0.5` (full moon) to `1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::moon_phase::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(MoonPhase::new(attr, true, None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(
+        attr: Attributes,
+        show_name: bool,
+        render: Option<Box<dyn Fn(f64) -> String>>,
+    ) -> MoonPhase {
+        MoonPhase {
+            attr,
+            show_name,
+            render,
+            update_interval: Duration::from_secs(60 * 60),
+        }
+    }
+
+    fn tick(&self) -> Vec<Text> {
+        let phase = phase_at(Utc::now().timestamp());
+        let text = match &self.render {
+            Some(render) => (render)(phase),
+            None => {
+                let (glyph, name) = phase_name(phase);
+                if self.show_name {
+                    format!("{glyph} {name}")
+                } else {
+                    glyph.to_owned()
+                }
+            }
+        };
+        vec![Text::new(self.attr.clone(), text)]
+    }
+}
+
+impl Widget for MoonPhase {
+    fn name(&self) -> &str {
+        "MoonPhase"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let interval = time::interval(self.update_interval);
+        let stream = IntervalStream::new(interval).map(move |_| Ok(self.tick()));
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{phase_at, phase_name};
+
+    #[test]
+    fn known_phases() {
+        // 2000-01-21 04:40 UTC was a full moon.
+        let full = phase_at(948_429_600);
+        assert!((full - 0.5).abs() < 0.02, "{full}");
+        assert_eq!(phase_name(full).1, "Full Moon");
+
+        // 2024-01-11 11:57 UTC was a new moon.
+        let new = phase_at(1_704_974_220);
+        assert_eq!(phase_name(new).1, "New Moon");
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::{Context, Result};
+use async_stream::try_stream;
+use cnx::text::{escape_markup, Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use tokio_stream::StreamExt;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{Connection, Proxy};
+
+const NM_DESTINATION: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const NM_INTERFACE: &str = "org.freedesktop.NetworkManager";
+const ACTIVE_INTERFACE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const ACCESS_POINT_INTERFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+
+/// The type of a NetworkManager connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionKind {
+    Wifi,
+    Ethernet,
+    Vpn,
+    /// Any other type of connection, with NetworkManager's name for its
+    /// type, e.g. `bluetooth`.
+    Other(String),
+}
+
+impl ConnectionKind {
+    fn from_nm_type(nm_type: &str) -> ConnectionKind {
+        match nm_type {
+            "802-11-wireless" => ConnectionKind::Wifi,
+            "802-3-ethernet" => ConnectionKind::Ethernet,
+            "vpn" | "wireguard" => ConnectionKind::Vpn,
+            other => ConnectionKind::Other(other.to_owned()),
+        }
+    }
+}
+
+/// Represents an active NetworkManager connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionInfo {
+    /// The name of the connection. For wifi connections, this is the SSID of
+    /// the network.
+    pub name: String,
+    /// The type of the connection.
+    pub kind: ConnectionKind,
+}
+
+/// Represents the state of the network, as reported by NetworkManager.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkInfo {
+    /// The connection NetworkManager considers to be the primary network
+    /// connection, if any.
+    pub primary: Option<ConnectionInfo>,
+    /// An active VPN connection, if any.
+    pub vpn: Option<ConnectionInfo>,
+}
+
+async fn proxy<'a>(conn: &Connection, path: &'a str, interface: &'a str) -> Result<Proxy<'a>> {
+    Proxy::new(conn, NM_DESTINATION, path, interface)
+        .await
+        .with_context(|| format!("Failed to create proxy for {path}"))
+}
+
+async fn load_connection(conn: &Connection, path: &OwnedObjectPath) -> Result<ConnectionInfo> {
+    let active = proxy(conn, path.as_str(), ACTIVE_INTERFACE).await?;
+    let id: String = active.get_property("Id").await?;
+    let nm_type: String = active.get_property("Type").await?;
+    let is_vpn: bool = active.get_property("Vpn").await?;
+
+    let kind = if is_vpn {
+        ConnectionKind::Vpn
+    } else {
+        ConnectionKind::from_nm_type(&nm_type)
+    };
+
+    // The connection's name defaults to the SSID, but can be changed, so
+    // prefer the SSID of the access point.
+    let name = match kind {
+        ConnectionKind::Wifi => load_ssid(conn, &active).await.unwrap_or(id),
+        _ => id,
+    };
+
+    Ok(ConnectionInfo { name, kind })
+}
+
+async fn load_ssid(conn: &Connection, active: &Proxy<'_>) -> Result<String> {
+    let access_point: OwnedObjectPath = active.get_property("SpecificObject").await?;
+    let access_point = proxy(conn, access_point.as_str(), ACCESS_POINT_INTERFACE).await?;
+    let ssid: Vec<u8> = access_point.get_property("Ssid").await?;
+    Ok(String::from_utf8_lossy(&ssid).into_owned())
+}
+
+async fn load_network_info(conn: &Connection, nm: &Proxy<'_>) -> Result<NetworkInfo> {
+    let primary_path: OwnedObjectPath = nm.get_property("PrimaryConnection").await?;
+    // NetworkManager uses `/` when there is no primary connection.
+    let primary = if primary_path.as_str() == "/" {
+        None
+    } else {
+        Some(load_connection(conn, &primary_path).await?)
+    };
+
+    let mut vpn = None;
+    let active_paths: Vec<OwnedObjectPath> = nm.get_property("ActiveConnections").await?;
+    for path in &active_paths {
+        // Connections may disappear while we're looking at them, so skip any
+        // which can't be read.
+        if let Ok(connection) = load_connection(conn, path).await {
+            if connection.kind == ConnectionKind::Vpn {
+                vpn = Some(connection);
+                break;
+            }
+        }
+    }
+
+    Ok(NetworkInfo { primary, vpn })
+}
+
+/// Shows the status of the network connection, as reported by NetworkManager.
+///
+/// This widget talks to NetworkManager over the D-Bus system bus, and updates
+/// whenever NetworkManager's state or list of active connections changes. By
+/// default, it shows e.g. `VPN: work` when a VPN connection is active,
+/// otherwise the SSID of the wifi network or the name of the primary
+/// connection.
+pub struct NetworkStatus {
+    attr: Attributes,
+    render: Option<Box<dyn Fn(NetworkInfo) -> String>>,
+}
+
+impl NetworkStatus {
+    /// Creates a new [`NetworkStatus`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. [`NetworkInfo`] represents the primary and VPN
+    /// connections.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::network_status::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(NetworkStatus::new(attr, None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(attr: Attributes, render: Option<Box<dyn Fn(NetworkInfo) -> String>>) -> Self {
+        Self { attr, render }
+    }
+
+    fn on_change(&self, info: NetworkInfo) -> Vec<Text> {
+        let text = match &self.render {
+            Some(render) => (render)(info),
+            None => match (info.vpn, info.primary) {
+                (Some(vpn), _) => format!("VPN: {}", escape_markup(&vpn.name)),
+                (None, Some(primary)) => escape_markup(&primary.name),
+                (None, None) => "disconnected".to_owned(),
+            },
+        };
+
+        vec![Text::new(self.attr.clone(), text)]
+    }
+}
+
+impl Widget for NetworkStatus {
+    fn name(&self) -> &str {
+        "NetworkStatus"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            let conn = Connection::system()
+                .await
+                .context("Failed to connect to the D-Bus system bus")?;
+            let nm = proxy(&conn, NM_PATH, NM_INTERFACE).await?;
+
+            let state_changed = nm
+                .receive_signal("StateChanged")
+                .await
+                .context("Failed to subscribe to NetworkManager's StateChanged")?
+                .map(|_| ());
+            let connections_changed = nm
+                .receive_property_changed::<Vec<OwnedObjectPath>>("ActiveConnections")
+                .await
+                .map(|_| ());
+            let mut changes = state_changed.merge(connections_changed);
+
+            yield self.on_change(load_network_info(&conn, &nm).await?);
+            while changes.next().await.is_some() {
+                yield self.on_change(load_network_info(&conn, &nm).await?);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
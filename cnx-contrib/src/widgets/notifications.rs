@@ -0,0 +1,153 @@
+use anyhow::{Context, Result};
+use async_stream::try_stream;
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::collections::HashMap;
+use tokio_stream::StreamExt;
+use zbus::zvariant::OwnedValue;
+use zbus::{Connection, Message, MessageStream, MessageType};
+
+// The arguments of `org.freedesktop.Notifications.Notify`.
+type NotifyArgs = (
+    String,
+    u32,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, OwnedValue>,
+    i32,
+);
+
+const MATCH_RULES: &[&str] = &[
+    "type='method_call',interface='org.freedesktop.Notifications',member='Notify'",
+    "type='signal',interface='org.freedesktop.Notifications',member='NotificationClosed'",
+];
+
+enum Event {
+    Notified,
+    Closed,
+}
+
+// Opens a new connection to the session bus and turns it into a monitor for
+// notifications being sent and closed.
+async fn become_monitor() -> Result<MessageStream> {
+    let conn = Connection::session()
+        .await
+        .context("Failed to connect to the D-Bus session bus")?;
+    conn.call_method(
+        Some("org.freedesktop.DBus"),
+        "/org/freedesktop/DBus",
+        Some("org.freedesktop.DBus.Monitoring"),
+        "BecomeMonitor",
+        &(MATCH_RULES, 0u32),
+    )
+    .await
+    .context("Failed to become a D-Bus monitor")?;
+    Ok(MessageStream::from(conn))
+}
+
+fn notification_event(message: &Message) -> Option<Event> {
+    let member = message.member()?;
+    match (message.message_type(), member.as_str()) {
+        (MessageType::MethodCall, "Notify") => {
+            // A notification that replaces an existing one doesn't add to
+            // the count.
+            let (_, replaces_id, ..): NotifyArgs = message.body().ok()?;
+            (replaces_id == 0).then_some(Event::Notified)
+        }
+        (MessageType::Signal, "NotificationClosed") => Some(Event::Closed),
+        _ => None,
+    }
+}
+
+/// Shows the number of open desktop notifications.
+///
+/// This widget passively monitors the D-Bus session bus for notifications
+/// being sent to the notification daemon (`org.freedesktop.Notifications`),
+/// so it works alongside any daemon and doesn't poll. The count goes up for
+/// each `Notify` call and down for each `NotificationClosed` signal. (The
+/// daemon emits `NotificationClosed` whether a notification expires, is
+/// dismissed, or is closed with `CloseNotification`).
+///
+/// As the widget only sees notifications sent while it is running, any
+/// notifications open when the bar starts aren't counted.
+pub struct Notifications {
+    attr: Attributes,
+    render: Option<Box<dyn Fn(usize) -> String>>,
+}
+
+impl Notifications {
+    /// Creates a new [`Notifications`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives the number of open notifications.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::notifications::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Notifications::new(attr, None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(attr: Attributes, render: Option<Box<dyn Fn(usize) -> String>>) -> Self {
+        Self { attr, render }
+    }
+
+    fn on_change(&self, count: usize) -> Vec<Text> {
+        let text = match &self.render {
+            Some(render) => (render)(count),
+            None => format!("🔔 {count}"),
+        };
+
+        vec![Text::new(self.attr.clone(), text).with_markup(self.render.is_some())]
+    }
+}
+
+impl Widget for Notifications {
+    fn name(&self) -> &str {
+        "Notifications"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            let mut messages = become_monitor().await?;
+            let mut count: usize = 0;
+            yield self.on_change(count);
+
+            while let Some(message) = messages.next().await {
+                let message = message.context("Failed to read D-Bus message")?;
+                match notification_event(&message) {
+                    Some(Event::Notified) => count += 1,
+                    Some(Event::Closed) => count = count.saturating_sub(1),
+                    None => continue,
+                }
+                yield self.on_change(count);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
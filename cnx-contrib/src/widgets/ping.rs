@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Context, Result};
+use async_stream::try_stream;
+use cnx::text::{escape_markup, Attributes, Text, Threshold};
+use cnx::widgets::{Widget, WidgetStream};
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::net;
+use tokio::time;
+
+// How long to wait for a reply before giving up on a probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+async fn resolve(host: &str) -> Result<IpAddr> {
+    let mut addrs = net::lookup_host((host, 0))
+        .await
+        .with_context(|| format!("Failed to resolve {host}"))?;
+    addrs
+        .next()
+        .map(|addr| addr.ip())
+        .ok_or_else(|| anyhow!("No addresses found for {host}"))
+}
+
+async fn probe(host: &str) -> Result<Duration> {
+    let addr = resolve(host).await?;
+    let payload = [0; 56];
+    let (_, rtt) = time::timeout(PROBE_TIMEOUT, surge_ping::ping(addr, &payload))
+        .await
+        .context("Timed out waiting for ping reply")?
+        .context("Failed to send ping")?;
+    Ok(rtt)
+}
+
+/// Shows the round-trip latency to a host.
+///
+/// This widget periodically sends an ICMP echo request to a host and shows
+/// how long it took for the reply to arrive, e.g. `8.8.8.8: 23ms`. If no reply
+/// arrives in time (or the request can't be sent), it shows `timeout` instead.
+///
+/// Sending ICMP echo requests needs either the `CAP_NET_RAW` capability or
+/// the user's group to be allowed by the `net.ipv4.ping_group_range` sysctl.
+pub struct Ping {
+    attr: Attributes,
+    host: String,
+    update_interval: Duration,
+    threshold: Option<Threshold>,
+}
+
+impl Ping {
+    /// Creates a new [`Ping`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `host` - The hostname or IP address to ping.
+    ///
+    /// * `interval` - How often to ping the host.
+    ///
+    /// * `threshold` - Represents threshold values (in milliseconds) to
+    /// determine the color of the latency. As lower latencies are better, you probably want to give the
+    /// `low` band a "good" color, unlike [`Threshold::default()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::ping::*;
+    /// # use anyhow::Result;
+    /// # use std::time::Duration;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Ping::new(attr, "8.8.8.8".into(), Duration::from_secs(10), None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(
+        attr: Attributes,
+        host: String,
+        interval: Duration,
+        threshold: Option<Threshold>,
+    ) -> Ping {
+        Ping {
+            attr,
+            host,
+            update_interval: interval,
+            threshold,
+        }
+    }
+
+    fn on_probe(&self, rtt: Option<Duration>) -> Vec<Text> {
+        let host = escape_markup(&self.host);
+        let text = match (rtt, &self.threshold) {
+            (Some(rtt), Some(thold)) => {
+                let ms = rtt.as_millis();
+                let color = thold.color_for(u32::try_from(ms).unwrap_or(u32::MAX));
+                format!(
+                    "{host}: <span foreground=\"{}\">{ms}ms</span>",
+                    color.to_hex()
+                )
+            }
+            (Some(rtt), None) => format!("{host}: {}ms", rtt.as_millis()),
+            (None, _) => format!("{host}: timeout"),
+        };
+        vec![Text::new(self.attr.clone(), text)]
+    }
+}
+
+impl Widget for Ping {
+    fn name(&self) -> &str {
+        "Ping"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            let mut interval = time::interval(self.update_interval);
+            loop {
+                interval.tick().await;
+                // A failed probe is shown in the bar, rather than ending the
+                // widget's stream.
+                let rtt = probe(&self.host).await.ok();
+                yield self.on_probe(rtt);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
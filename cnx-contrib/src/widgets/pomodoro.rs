@@ -0,0 +1,378 @@
+use anyhow::{anyhow, Context, Result};
+use async_stream::try_stream;
+use cnx::text::{Attributes, Color, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use log::warn;
+use nix::fcntl::OFlag;
+use nix::sys::stat::Mode;
+use nix::unistd;
+use std::fs::{File, OpenOptions};
+use std::future;
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc;
+use tokio::time::{self, Instant, MissedTickBehavior};
+
+/// A message which controls a running [`Pomodoro`] widget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PomodoroMsg {
+    /// Starts (or resumes) the timer.
+    Start,
+    /// Pauses the timer.
+    Pause,
+    /// Starts the timer if it is paused, or pauses it if it is running.
+    Toggle,
+    /// Stops the timer and resets it to the start of a work period.
+    Reset,
+}
+
+impl FromStr for PomodoroMsg {
+    type Err = anyhow::Error;
+
+    /// Parses `start`, `pause`, `toggle` or `reset`.
+    fn from_str(s: &str) -> Result<PomodoroMsg> {
+        match s {
+            "start" => Ok(PomodoroMsg::Start),
+            "pause" => Ok(PomodoroMsg::Pause),
+            "toggle" => Ok(PomodoroMsg::Toggle),
+            "reset" => Ok(PomodoroMsg::Reset),
+            _ => Err(anyhow!("Invalid Pomodoro message: {s:?}")),
+        }
+    }
+}
+
+// A named pipe which `PomodoroMsg`s are written to, one per line.
+struct Fifo {
+    file: AsyncFd<File>,
+    buffer: Vec<u8>,
+}
+
+impl Fifo {
+    // Opens the pipe at `path`, creating it if it doesn't exist.
+    fn open(path: &Path) -> Result<Fifo> {
+        if !path.exists() {
+            unistd::mkfifo(path, Mode::S_IRUSR | Mode::S_IWUSR)
+                .with_context(|| format!("Failed to create FIFO: {}", path.display()))?;
+        }
+        // Opening the pipe for writing as well as reading means that opening
+        // it doesn't wait for a writer, and that reads don't see the end of
+        // the file each time a writer closes it.
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(OFlag::O_NONBLOCK.bits())
+            .open(path)
+            .with_context(|| format!("Failed to open FIFO: {}", path.display()))?;
+        Ok(Fifo {
+            file: AsyncFd::new(file)?,
+            buffer: Vec::new(),
+        })
+    }
+
+    // Waits for the next valid message. Invalid lines are logged and skipped.
+    async fn next(&mut self) -> Result<PomodoroMsg> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                match String::from_utf8_lossy(&line).trim().parse() {
+                    Ok(msg) => return Ok(msg),
+                    Err(err) => warn!("{err}"),
+                }
+                continue;
+            }
+
+            let mut guard = self.file.readable().await?;
+            let mut chunk = [0; 64];
+            match guard.try_io(|file| file.get_ref().read(&mut chunk)) {
+                Ok(read) => self.buffer.extend_from_slice(&chunk[..read?]),
+                // Nothing was ready after all.
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+// Waits for the next message from `fifo`, or forever if there is no pipe.
+async fn read_fifo(fifo: &mut Option<Fifo>) -> Result<PomodoroMsg> {
+    match fifo {
+        Some(fifo) => fifo.next().await,
+        None => future::pending().await,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Work,
+    Break,
+}
+
+// The state of the countdown, which is advanced by `Pomodoro`'s stream.
+#[derive(Debug)]
+struct Timer {
+    work: Duration,
+    rest: Duration,
+    phase: Phase,
+    // The time left, as of when the timer was last paused.
+    remaining: Duration,
+    // When the current phase ends, if the timer is running.
+    deadline: Option<Instant>,
+}
+
+impl Timer {
+    fn new(work: Duration, rest: Duration) -> Timer {
+        Timer {
+            work,
+            rest,
+            phase: Phase::Work,
+            remaining: work,
+            deadline: None,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    fn remaining(&self, now: Instant) -> Duration {
+        match self.deadline {
+            Some(deadline) => deadline.saturating_duration_since(now),
+            None => self.remaining,
+        }
+    }
+
+    fn handle(&mut self, msg: PomodoroMsg, now: Instant) {
+        match msg {
+            PomodoroMsg::Start if !self.is_running() => {
+                self.deadline = Some(now + self.remaining);
+            }
+            PomodoroMsg::Pause if self.is_running() => {
+                self.remaining = self.remaining(now);
+                self.deadline = None;
+            }
+            PomodoroMsg::Toggle if self.is_running() => self.handle(PomodoroMsg::Pause, now),
+            PomodoroMsg::Toggle => self.handle(PomodoroMsg::Start, now),
+            PomodoroMsg::Reset => *self = Timer::new(self.work, self.rest),
+            _ => {}
+        }
+    }
+
+    // Moves on to the next phase if the current one has finished. A break
+    // starts as soon as a work period finishes, but the next work period
+    // waits to be started.
+    fn advance(&mut self, now: Instant) {
+        match self.deadline {
+            Some(deadline) if deadline <= now => {}
+            _ => return,
+        }
+        match self.phase {
+            Phase::Work => {
+                self.phase = Phase::Break;
+                self.remaining = self.rest;
+                self.deadline = Some(now + self.rest);
+            }
+            Phase::Break => *self = Timer::new(self.work, self.rest),
+        }
+    }
+}
+
+/// Shows a Pomodoro timer, which counts down a work period and then a break.
+///
+/// The timer is controlled by sending [`PomodoroMsg`]s through the channel
+/// returned by [`Pomodoro::new()`], or by writing them to a named pipe set
+/// with [`Pomodoro::with_fifo()`], e.g. from WM keybindings. It starts paused,
+/// at the beginning of a work period.
+///
+/// By default, this shows `🍅 25:00` during a work period and `☕ 05:00`
+/// during a break. When the work period finishes, the break starts straight
+/// away. When the break finishes, the timer waits to be started again.
+pub struct Pomodoro {
+    attr: Attributes,
+    break_color: Option<Color>,
+    work: Duration,
+    rest: Duration,
+    fifo: Option<PathBuf>,
+    // Keeps the channel open, even once every other sender has been dropped.
+    _sender: mpsc::UnboundedSender<PomodoroMsg>,
+    receiver: mpsc::UnboundedReceiver<PomodoroMsg>,
+}
+
+impl Pomodoro {
+    /// Creates a new [`Pomodoro`] widget, and a channel to control it.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `work` - How long each work period lasts, usually 25 minutes.
+    ///
+    /// * `rest` - How long each break lasts, usually 5 minutes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::pomodoro::*;
+    /// # use anyhow::Result;
+    /// # use std::time::Duration;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let (pomodoro, control) = Pomodoro::new(
+    ///     attr,
+    ///     Duration::from_secs(25 * 60),
+    ///     Duration::from_secs(5 * 60),
+    /// );
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(pomodoro);
+    /// // Later, e.g. when a keybinding is pressed:
+    /// control.send(PomodoroMsg::Toggle).unwrap();
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(
+        attr: Attributes,
+        work: Duration,
+        rest: Duration,
+    ) -> (Pomodoro, mpsc::UnboundedSender<PomodoroMsg>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let pomodoro = Pomodoro {
+            attr,
+            break_color: None,
+            work,
+            rest,
+            fifo: None,
+            _sender: sender.clone(),
+            receiver,
+        };
+        (pomodoro, sender)
+    }
+
+    /// Returns a new [`Pomodoro`] widget which shows breaks in `color`.
+    pub fn with_break_color(self, color: Color) -> Self {
+        Self {
+            break_color: Some(color),
+            ..self
+        }
+    }
+
+    /// Returns a new [`Pomodoro`] widget which can also be controlled by
+    /// writing `start`, `pause`, `toggle` or `reset` lines to the named pipe
+    /// at `path`. The pipe is created if it doesn't exist.
+    ///
+    /// This lets WM keybindings control the timer with e.g.
+    /// `echo toggle > $XDG_RUNTIME_DIR/cnx-pomodoro`.
+    pub fn with_fifo<P: Into<PathBuf>>(self, path: P) -> Self {
+        Self {
+            fifo: Some(path.into()),
+            ..self
+        }
+    }
+
+    fn render(&self, timer: &Timer, now: Instant) -> Vec<Text> {
+        // Round up, so that the timer shows `00:00` only once it has
+        // finished.
+        let remaining = timer.remaining(now);
+        let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        let (minutes, seconds) = (seconds / 60, seconds % 60);
+
+        let mut attr = self.attr.clone();
+        let glyph = match timer.phase {
+            Phase::Work => "🍅",
+            Phase::Break => {
+                if let Some(color) = &self.break_color {
+                    attr.fg_color = color.clone();
+                }
+                "☕"
+            }
+        };
+        vec![Text::new(
+            attr,
+            format!("{glyph} {minutes:02}:{seconds:02}"),
+        )]
+    }
+}
+
+impl Widget for Pomodoro {
+    fn name(&self) -> &str {
+        "Pomodoro"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let mut fifo = self.fifo.as_deref().map(Fifo::open).transpose()?;
+        let stream = try_stream! {
+            let mut this = *self;
+            let mut timer = Timer::new(this.work, this.rest);
+            let mut ticks = time::interval(Duration::from_secs(1));
+            ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                yield this.render(&timer, Instant::now());
+                let msg = tokio::select! {
+                    _ = ticks.tick(), if timer.is_running() => None,
+                    Some(msg) = this.receiver.recv() => Some(msg),
+                    msg = read_fifo(&mut fifo) => Some(msg?),
+                };
+                if let Some(msg) = msg {
+                    timer.handle(msg, Instant::now());
+                    // Count whole seconds from when the timer started.
+                    ticks.reset();
+                }
+                timer.advance(Instant::now());
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Phase, PomodoroMsg, Timer};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[test]
+    fn timer_runs_work_then_break() {
+        let minute = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut timer = Timer::new(25 * minute, 5 * minute);
+        assert!(!timer.is_running());
+
+        timer.handle(PomodoroMsg::Toggle, start);
+        timer.handle(PomodoroMsg::Pause, start + minute);
+        assert_eq!(timer.remaining(start + 10 * minute), 24 * minute);
+
+        timer.handle(PomodoroMsg::Start, start + 2 * minute);
+        timer.advance(start + 26 * minute);
+        assert_eq!(timer.phase, Phase::Break);
+        assert_eq!(timer.remaining(start + 27 * minute), 4 * minute);
+
+        timer.advance(start + 31 * minute);
+        assert_eq!(timer.phase, Phase::Work);
+        assert!(!timer.is_running());
+    }
+
+    #[test]
+    fn parses_messages() {
+        assert_eq!(
+            "toggle".parse::<PomodoroMsg>().unwrap(),
+            PomodoroMsg::Toggle
+        );
+        assert_eq!("reset".parse::<PomodoroMsg>().unwrap(), PomodoroMsg::Reset);
+        assert!("stop".parse::<PomodoroMsg>().is_err());
+    }
+}
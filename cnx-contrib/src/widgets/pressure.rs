@@ -0,0 +1,175 @@
+use anyhow::{anyhow, Result};
+use cnx::text::{Attributes, Text, Threshold};
+use cnx::widgets::{Widget, WidgetStream};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+
+/// The resources whose pressure can be shown by the [`Pressure`] widget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PsiResource {
+    /// Time spent waiting for a CPU.
+    Cpu,
+    /// Time spent waiting for memory, e.g. reclaiming pages or swapping.
+    Memory,
+    /// Time spent waiting for block devices.
+    Io,
+}
+
+impl PsiResource {
+    fn path(self) -> &'static str {
+        match self {
+            PsiResource::Cpu => "/proc/pressure/cpu",
+            PsiResource::Memory => "/proc/pressure/memory",
+            PsiResource::Io => "/proc/pressure/io",
+        }
+    }
+}
+
+impl fmt::Display for PsiResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PsiResource::Cpu => "cpu",
+            PsiResource::Memory => "mem",
+            PsiResource::Io => "io",
+        };
+        f.write_str(name)
+    }
+}
+
+// Parses the `avg10` value of the `some` line of a `/proc/pressure/` file:
+//
+//   some avg10=4.20 avg60=1.03 avg300=0.22 total=1234567
+//   full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+fn parse_some_avg10(contents: &str) -> Result<f64> {
+    let line = contents
+        .lines()
+        .find(|line| line.starts_with("some "))
+        .ok_or_else(|| anyhow!("No 'some' line in pressure file"))?;
+    let value = line
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("avg10="))
+        .ok_or_else(|| anyhow!("No avg10 value in pressure file"))?;
+    Ok(value.parse()?)
+}
+
+/// Shows how much time tasks spend waiting for a resource.
+///
+/// This widget reads the kernel's [Pressure Stall Information] from
+/// `/proc/pressure/`, and shows the percentage of time in the last 10 seconds
+/// that at least one task was stalled waiting for the resource, e.g.
+/// `mem psi: 4.2`. This is often a better sign that the system is struggling
+/// than how much of the resource is in use.
+///
+/// PSI needs Linux 4.20 or later, built with `CONFIG_PSI`. On kernels without
+/// it, `NA` is shown instead.
+///
+/// [Pressure Stall Information]: https://docs.kernel.org/accounting/psi.html
+pub struct Pressure {
+    attr: Attributes,
+    resource: PsiResource,
+    threshold: Option<Threshold>,
+    update_interval: Duration,
+}
+
+impl Pressure {
+    /// Creates a new [`Pressure`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `resource` - Which resource's pressure to show.
+    ///
+    /// * `threshold` - Represents threshold values (as percentages) to
+    /// determine the color of the pressure.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::pressure::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Pressure::new(attr, PsiResource::Memory, None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(attr: Attributes, resource: PsiResource, threshold: Option<Threshold>) -> Pressure {
+        Pressure {
+            attr,
+            resource,
+            threshold,
+            update_interval: Duration::from_secs(5),
+        }
+    }
+
+    fn tick(&self) -> Result<Vec<Text>> {
+        let text = match fs::read_to_string(self.resource.path()) {
+            Ok(contents) => {
+                let avg10 = parse_some_avg10(&contents)?;
+                match &self.threshold {
+                    Some(thold) => {
+                        let color = thold.color_for(avg10.round().clamp(0.0, 100.0) as u32);
+                        format!(
+                            "{} psi: <span foreground=\"{}\">{avg10:.1}</span>",
+                            self.resource,
+                            color.to_hex()
+                        )
+                    }
+                    None => format!("{} psi: {avg10:.1}", self.resource),
+                }
+            }
+            // The kernel doesn't support PSI.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                format!("{} psi: NA", self.resource)
+            }
+            Err(err) => return Err(err.into()),
+        };
+        Ok(vec![Text::new(self.attr.clone(), text)])
+    }
+}
+
+impl Widget for Pressure {
+    fn name(&self) -> &str {
+        "Pressure"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let interval = time::interval(self.update_interval);
+        let stream = IntervalStream::new(interval).map(move |_| self.tick());
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_some_avg10;
+
+    #[test]
+    fn parses_some_avg10() {
+        let contents = "some avg10=4.20 avg60=1.03 avg300=0.22 total=1234567\n\
+                        full avg10=0.50 avg60=0.10 avg300=0.02 total=4567\n";
+        assert_eq!(parse_some_avg10(contents).unwrap(), 4.2);
+        assert!(parse_some_avg10("full avg10=0.50\n").is_err());
+    }
+}
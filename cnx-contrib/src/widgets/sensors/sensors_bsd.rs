@@ -6,9 +6,9 @@ use std::time::Duration;
 
 use regex::Regex;
 
-use crate::cmd::command_output;
 use crate::text::{Attributes, Text};
 use crate::widgets::{Widget, WidgetStream};
+use cnx::cmd::command_output;
 use lazy_static::lazy_static;
 // use regex::Regex;
 use std::str::FromStr;
@@ -115,12 +115,7 @@ impl Sensors {
             .into_iter()
             .map(|Value { value, units }| {
                 let text = format!("{}{}", value, units);
-                Text {
-                    attr: self.attr.clone(),
-                    text,
-                    stretch: false,
-                    markup: false,
-                }
+                Text::new(self.attr.clone(), text).with_markup(false)
             })
             .collect();
 
@@ -129,6 +124,10 @@ impl Sensors {
 }
 
 impl Widget for Sensors {
+    fn name(&self) -> &str {
+        "Sensors"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let interval = time::interval(self.update_interval);
         let stream = IntervalStream::new(interval).map(move |_| self.tick());
@@ -82,10 +82,9 @@ impl Sensors {
     /// #
     /// # fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 21"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
@@ -118,18 +117,17 @@ impl Sensors {
                     .map_or("Invalid".to_owned(), |&Value { temp, units }| {
                         format!("{temp}°{units}")
                     });
-                Ok(Text {
-                    attr: self.attr.clone(),
-                    text,
-                    stretch: false,
-                    markup: false,
-                })
+                Ok(Text::new(self.attr.clone(), text).with_markup(false))
             })
             .collect()
     }
 }
 
 impl Widget for Sensors {
+    fn name(&self) -> &str {
+        "Sensors"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let interval = time::interval(self.update_interval);
         let stream = IntervalStream::new(interval).map(move |_| self.tick());
@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use async_stream::try_stream;
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike};
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::time::Duration;
+use tokio::time;
+
+/// Today's sunrise and sunset, passed to the [`SunTimes`] widget's `render`
+/// closure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SunInfo {
+    pub sunrise: DateTime<Local>,
+    pub sunset: DateTime<Local>,
+    /// Whether the sun is currently up.
+    pub is_day: bool,
+    /// The next sunrise or sunset, which may be tomorrow's sunrise.
+    pub next: DateTime<Local>,
+}
+
+/// Shows the time of the next sunrise or sunset.
+///
+/// The times are calculated locally from a latitude and longitude, so no
+/// network access is needed. By default, this shows a day/night glyph and the
+/// next event, e.g. `☀ 🌇 20:15` during the day or `🌙 🌅 6:42` at night.
+///
+/// The widget only updates at sunrise, sunset and local midnight.
+pub struct SunTimes {
+    attr: Attributes,
+    latitude: f64,
+    longitude: f64,
+    render: Option<Box<dyn Fn(SunInfo) -> String>>,
+}
+
+impl SunTimes {
+    /// Creates a new [`SunTimes`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `latitude` - The latitude of your location, in degrees. North is
+    /// positive.
+    ///
+    /// * `longitude` - The longitude of your location, in degrees. East is
+    /// positive.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. [`SunInfo`] has today's sunrise and sunset.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::sun_times::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(SunTimes::new(attr, 51.5, -0.13, None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(
+        attr: Attributes,
+        latitude: f64,
+        longitude: f64,
+        render: Option<Box<dyn Fn(SunInfo) -> String>>,
+    ) -> SunTimes {
+        SunTimes {
+            attr,
+            latitude,
+            longitude,
+            render,
+        }
+    }
+
+    // Returns the sunrise and sunset on `date`.
+    fn sun_times(&self, date: NaiveDate) -> Result<(DateTime<Local>, DateTime<Local>)> {
+        let (sunrise, sunset) = sunrise::sunrise_sunset(
+            self.latitude,
+            self.longitude,
+            date.year(),
+            date.month(),
+            date.day(),
+        );
+        let local = |timestamp| {
+            Local
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .ok_or_else(|| anyhow!("Invalid sunrise/sunset time: {timestamp}"))
+        };
+        Ok((local(sunrise)?, local(sunset)?))
+    }
+
+    fn sun_info(&self, now: DateTime<Local>) -> Result<SunInfo> {
+        let today = now.naive_local().date();
+        let (sunrise, sunset) = self.sun_times(today)?;
+        let (is_day, next) = if now < sunrise {
+            (false, sunrise)
+        } else if now < sunset {
+            (true, sunset)
+        } else {
+            let tomorrow = today
+                .succ_opt()
+                .ok_or_else(|| anyhow!("No date after {today}"))?;
+            (false, self.sun_times(tomorrow)?.0)
+        };
+        Ok(SunInfo {
+            sunrise,
+            sunset,
+            is_day,
+            next,
+        })
+    }
+
+    fn on_change(&self, info: SunInfo) -> Vec<Text> {
+        let text = match &self.render {
+            Some(render) => (render)(info),
+            None => {
+                let (glyph, event) = if info.is_day {
+                    ("☀", "🌇")
+                } else {
+                    ("🌙", "🌅")
+                };
+                format!("{glyph} {event} {}", info.next.format("%-H:%M"))
+            }
+        };
+        vec![Text::new(self.attr.clone(), text)]
+    }
+}
+
+// Returns how long to wait until the next sunrise/sunset or local midnight,
+// whichever comes first.
+fn until_next_update(now: DateTime<Local>, next: DateTime<Local>) -> Duration {
+    let until_midnight = 24 * 60 * 60 - i64::from(now.num_seconds_from_midnight());
+    let until_next = next.timestamp() - now.timestamp();
+    // During polar day or night the sun doesn't rise or set, and the times
+    // we're given are in the past. Wait until midnight rather than waking up
+    // every second.
+    let seconds = if until_next > 0 {
+        until_midnight.min(until_next)
+    } else {
+        until_midnight
+    };
+    // Wait an extra second, so that we wake up just after the transition
+    // rather than just before it.
+    Duration::from_secs(seconds as u64 + 1)
+}
+
+impl Widget for SunTimes {
+    fn name(&self) -> &str {
+        "SunTimes"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            loop {
+                let now = Local::now();
+                let info = self.sun_info(now)?;
+                let sleep_for = until_next_update(now, info.next);
+                yield self.on_change(info);
+                time::sleep(sleep_for).await;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::until_next_update;
+    use chrono::{DateTime, Local, NaiveDate, TimeZone};
+    use std::time::Duration;
+
+    fn local(hour: u32, minute: u32) -> DateTime<Local> {
+        let time = NaiveDate::from_ymd_opt(2023, 6, 21)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap();
+        Local.from_local_datetime(&time).single().unwrap()
+    }
+
+    #[test]
+    fn waits_until_next_event() {
+        let next = until_next_update(local(12, 0), local(20, 15));
+        assert_eq!(next, Duration::from_secs((8 * 60 + 15) * 60 + 1));
+    }
+
+    #[test]
+    fn waits_until_midnight_during_polar_day() {
+        // The sunrise crate gives the epoch when the sun doesn't rise or set.
+        let epoch = Local.timestamp_opt(0, 0).unwrap();
+        let next = until_next_update(local(12, 0), epoch);
+        assert_eq!(next, Duration::from_secs(12 * 60 * 60 + 1));
+    }
+}
@@ -0,0 +1,224 @@
+use anyhow::{Context, Result};
+use async_stream::try_stream;
+use cnx::text::{escape_markup, Attributes, Color, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::fmt;
+use std::pin::Pin;
+use tokio_stream::{Stream, StreamExt, StreamMap};
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{Connection, Proxy};
+
+const SYSTEMD_DESTINATION: &str = "org.freedesktop.systemd1";
+const SYSTEMD_PATH: &str = "/org/freedesktop/systemd1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.systemd1.Manager";
+const UNIT_INTERFACE: &str = "org.freedesktop.systemd1.Unit";
+
+/// The `ActiveState` of a systemd unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActiveState {
+    Active,
+    Reloading,
+    Inactive,
+    Failed,
+    Activating,
+    Deactivating,
+    /// Any other state, with systemd's name for it.
+    Other(String),
+}
+
+impl ActiveState {
+    fn from_systemd(state: &str) -> ActiveState {
+        match state {
+            "active" => ActiveState::Active,
+            "reloading" => ActiveState::Reloading,
+            "inactive" => ActiveState::Inactive,
+            "failed" => ActiveState::Failed,
+            "activating" => ActiveState::Activating,
+            "deactivating" => ActiveState::Deactivating,
+            other => ActiveState::Other(other.to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for ActiveState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = match self {
+            ActiveState::Active => "active",
+            ActiveState::Reloading => "reloading",
+            ActiveState::Inactive => "inactive",
+            ActiveState::Failed => "failed",
+            ActiveState::Activating => "activating",
+            ActiveState::Deactivating => "deactivating",
+            ActiveState::Other(other) => other,
+        };
+        write!(f, "{state}")
+    }
+}
+
+async fn unit_proxy(conn: &Connection, manager: &Proxy<'_>, unit: &str) -> Result<Proxy<'static>> {
+    // `LoadUnit` (unlike `GetUnit`) also works for units which aren't
+    // currently loaded, e.g. because they're inactive.
+    let path: OwnedObjectPath = manager
+        .call("LoadUnit", &(unit,))
+        .await
+        .with_context(|| format!("Failed to load unit {unit}"))?;
+    Proxy::new(conn, SYSTEMD_DESTINATION, path, UNIT_INTERFACE)
+        .await
+        .with_context(|| format!("Failed to create proxy for unit {unit}"))
+}
+
+/// Shows whether systemd units are active or have failed.
+///
+/// This widget talks to systemd over the D-Bus system bus, and updates
+/// whenever one of the units changes state. By default, it shows each unit
+/// with its state, e.g. `nginx.service active`, showing failed units in the
+/// warning color.
+pub struct SystemdUnit {
+    attr: Attributes,
+    warning_color: Color,
+    units: Vec<String>,
+    render: Option<Box<dyn Fn(Vec<(String, ActiveState)>) -> String>>,
+}
+
+impl SystemdUnit {
+    /// Creates a new [`SystemdUnit`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `warning_color` - The color of units which have failed.
+    ///
+    /// * `units` - The names of the units to show, e.g. `nginx.service` or
+    /// `wg-quick@wg0.service`.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives a `(unit, state)` pair for each of
+    /// the units, in the order they were given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::systemd_unit::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(SystemdUnit::new(attr, Color::red(), vec!["nginx.service"], None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new<S: Into<String>>(
+        attr: Attributes,
+        warning_color: Color,
+        units: Vec<S>,
+        render: Option<Box<dyn Fn(Vec<(String, ActiveState)>) -> String>>,
+    ) -> Self {
+        Self {
+            attr,
+            warning_color,
+            units: units.into_iter().map(Into::into).collect(),
+            render,
+        }
+    }
+
+    fn on_change(&self, states: Vec<(String, ActiveState)>) -> Vec<Text> {
+        if let Some(render) = &self.render {
+            return vec![Text::new(self.attr.clone(), (render)(states))];
+        }
+
+        states
+            .into_iter()
+            .map(|(unit, state)| {
+                let mut attr = self.attr.clone();
+                if state == ActiveState::Failed {
+                    attr.fg_color = self.warning_color.clone();
+                }
+                let text = format!("{} {state}", escape_markup(&unit));
+                Text::new(attr, text)
+            })
+            .collect()
+    }
+}
+
+async fn load_states(
+    units: &[String],
+    proxies: &[Proxy<'_>],
+) -> Result<Vec<(String, ActiveState)>> {
+    let mut states = Vec::with_capacity(units.len());
+    for (unit, proxy) in units.iter().zip(proxies) {
+        let state: String = proxy
+            .get_property("ActiveState")
+            .await
+            .with_context(|| format!("Failed to get state of unit {unit}"))?;
+        states.push((unit.clone(), ActiveState::from_systemd(&state)));
+    }
+    Ok(states)
+}
+
+impl Widget for SystemdUnit {
+    fn name(&self) -> &str {
+        "SystemdUnit"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            let conn = Connection::system()
+                .await
+                .context("Failed to connect to the D-Bus system bus")?;
+            let manager = Proxy::new(&conn, SYSTEMD_DESTINATION, SYSTEMD_PATH, MANAGER_INTERFACE)
+                .await
+                .context("Failed to create proxy for systemd")?;
+            // systemd only emits signals once a client has subscribed.
+            manager
+                .call_method("Subscribe", &())
+                .await
+                .context("Failed to subscribe to systemd signals")?;
+
+            let mut proxies = Vec::with_capacity(self.units.len());
+            for unit in &self.units {
+                proxies.push(unit_proxy(&conn, &manager, unit).await?);
+            }
+
+            // Units being loaded or unloaded can change their state without
+            // a `PropertiesChanged`, so we re-read them on those too.
+            let mut changes: StreamMap<usize, Pin<Box<dyn Stream<Item = ()>>>> = StreamMap::new();
+            let unit_new = manager
+                .receive_signal("UnitNew")
+                .await
+                .context("Failed to subscribe to systemd's UnitNew")?;
+            let unit_removed = manager
+                .receive_signal("UnitRemoved")
+                .await
+                .context("Failed to subscribe to systemd's UnitRemoved")?;
+            changes.insert(0, Box::pin(unit_new.merge(unit_removed).map(|_| ())));
+            for (i, proxy) in proxies.iter().enumerate() {
+                let state_changed = proxy
+                    .receive_property_changed::<String>("ActiveState")
+                    .await
+                    .map(|_| ());
+                changes.insert(i + 1, Box::pin(state_changed));
+            }
+
+            yield self.on_change(load_states(&self.units, &proxies).await?);
+            while changes.next().await.is_some() {
+                yield self.on_change(load_states(&self.units, &proxies).await?);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
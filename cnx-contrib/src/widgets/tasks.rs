@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use async_stream::stream;
+use cnx::cmd::from_command_output;
+use cnx::text::{Attributes, Color, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::time::Duration;
+use tokio::{task, time};
+
+/// Shows the number of [Taskwarrior] tasks matching a filter.
+///
+/// This widget runs `task <filter> count` every minute and shows the result,
+/// e.g. `✓ 7`. The count is shown in red when it is above a threshold, which
+/// can be changed with [`Tasks::with_warning_color()`].
+///
+/// [Taskwarrior]: https://taskwarrior.org/
+pub struct Tasks {
+    attr: Attributes,
+    warning_color: Color,
+    filter: String,
+    threshold: Option<u32>,
+    update_interval: Duration,
+}
+
+impl Tasks {
+    /// Creates a new [`Tasks`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `filter` - The Taskwarrior filter of the tasks to count, e.g.
+    /// `status:pending` or `status:pending +work`.
+    ///
+    /// * `threshold` - If set, the count is shown in red when more tasks
+    /// than this match the filter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::tasks::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Tasks::new(attr, "status:pending".into(), Some(10)));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new(attr: Attributes, filter: String, threshold: Option<u32>) -> Tasks {
+        Tasks {
+            attr,
+            warning_color: Color::red(),
+            filter,
+            threshold,
+            update_interval: Duration::from_secs(60),
+        }
+    }
+
+    /// Returns a new [`Tasks`] widget which shows the count in
+    /// `warning_color`, rather than red, when it is above the threshold.
+    pub fn with_warning_color(self, warning_color: Color) -> Tasks {
+        Tasks {
+            warning_color,
+            ..self
+        }
+    }
+
+    async fn tick(&self) -> Result<Vec<Text>> {
+        let mut args: Vec<String> = self.filter.split_whitespace().map(str::to_owned).collect();
+        args.push("count".to_owned());
+        // `task` can be slow (e.g. if it runs hooks or has a large database),
+        // so run it without blocking the rest of the bar.
+        let count: u32 = task::spawn_blocking(move || {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            from_command_output::<u32>("task", &args)
+        })
+        .await?
+        .context("Counting tasks")?;
+
+        let text = match self.threshold {
+            Some(threshold) if count > threshold => format!(
+                "<span foreground=\"{}\">✓ {count}</span>",
+                self.warning_color.to_hex()
+            ),
+            _ => format!("✓ {count}"),
+        };
+        Ok(vec![Text::new(self.attr.clone(), text)])
+    }
+}
+
+impl Widget for Tasks {
+    fn name(&self) -> &str {
+        "Tasks"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = stream! {
+            let mut interval = time::interval(self.update_interval);
+            loop {
+                interval.tick().await;
+                yield self.tick().await;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::{Context, Result};
+use cnx::text::{Attributes, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+
+const THERMAL_PATH: &str = "/sys/class/thermal";
+
+/// Reads the temperature (in °C) of every thermal zone, keyed by its `type`.
+fn load_thermal_zones() -> Result<HashMap<String, f64>> {
+    let mut zones = HashMap::new();
+    let entries = fs::read_dir(THERMAL_PATH)
+        .with_context(|| format!("Failed to read directory: {THERMAL_PATH}"))?;
+    for entry in entries {
+        let path = entry?.path();
+        let is_zone = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.starts_with("thermal_zone"));
+        if !is_zone {
+            continue;
+        }
+
+        // Some zones can't be read (e.g. when the device is suspended), so
+        // skip those rather than failing the whole widget.
+        if let Ok((kind, celsius)) = load_thermal_zone(&path) {
+            zones.insert(kind, celsius);
+        }
+    }
+    Ok(zones)
+}
+
+fn load_thermal_zone(path: &Path) -> Result<(String, f64)> {
+    let kind = fs::read_to_string(path.join("type"))?.trim().to_owned();
+    let millidegrees: f64 = fs::read_to_string(path.join("temp"))?.trim().parse()?;
+    Ok((kind, millidegrees / 1000.0))
+}
+
+/// Shows the temperature of one or more thermal zones.
+///
+/// This widget reads the temperature of the named thermal zones directly from
+/// [`/sys/class/thermal/`], so unlike the `Sensors` widget it doesn't need
+/// `lm_sensors` to be installed. Zones are identified by the contents of their
+/// `type` file, e.g. `x86_pkg_temp` or `acpitz`.
+///
+/// [`/sys/class/thermal/`]: https://www.kernel.org/doc/Documentation/thermal/sysfs-api.txt
+pub struct ThermalZone {
+    update_interval: Duration,
+    attr: Attributes,
+    zones: Vec<String>,
+    render: Option<Box<dyn Fn(Vec<(String, f64)>) -> String>>,
+}
+
+impl ThermalZone {
+    /// Creates a new [`ThermalZone`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `zones` - The `type`s of the thermal zones to show. You can list
+    /// the available types with `cat /sys/class/thermal/thermal_zone*/type`.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. It receives a `(type, celsius)` pair for each of
+    /// the requested zones that could be read, in the order they were
+    /// requested.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::thermal_zone::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(ThermalZone::new(attr, vec!["x86_pkg_temp"], None));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new<S: Into<String>>(
+        attr: Attributes,
+        zones: Vec<S>,
+        render: Option<Box<dyn Fn(Vec<(String, f64)>) -> String>>,
+    ) -> Self {
+        Self {
+            update_interval: Duration::from_secs(60),
+            attr,
+            zones: zones.into_iter().map(Into::into).collect(),
+            render,
+        }
+    }
+
+    fn tick(&self) -> Result<Vec<Text>> {
+        let mut temperatures = load_thermal_zones()?;
+        let values: Vec<(String, f64)> = self
+            .zones
+            .iter()
+            .filter_map(|zone| {
+                temperatures
+                    .remove(zone)
+                    .map(|celsius| (zone.clone(), celsius))
+            })
+            .collect();
+
+        let text = match &self.render {
+            Some(render) => (render)(values),
+            None => values
+                .iter()
+                .map(|(_, celsius)| format!("{celsius:.0}°C"))
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+
+        Ok(vec![
+            Text::new(self.attr.clone(), text).with_markup(self.render.is_some())
+        ])
+    }
+}
+
+impl Widget for ThermalZone {
+    fn name(&self) -> &str {
+        "ThermalZone"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let interval = time::interval(self.update_interval);
+        let stream = IntervalStream::new(interval).map(move |_| self.tick());
+
+        Ok(Box::pin(stream))
+    }
+}
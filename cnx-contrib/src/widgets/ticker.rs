@@ -0,0 +1,232 @@
+use anyhow::{anyhow, Context, Result};
+use async_stream::try_stream;
+use cnx::text::{escape_markup, Attributes, Color, Text};
+use cnx::widgets::{Widget, WidgetStream};
+use log::warn;
+use std::time::Duration;
+use tokio::time;
+
+/// Represents the latest price of a [`Ticker`]'s symbol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TickerInfo {
+    /// The symbol, as given to [`Ticker::new()`]
+    pub symbol: String,
+    /// The latest price
+    pub price: f64,
+    /// The change in price since the previous fetch, if there was one
+    pub change: Option<f64>,
+}
+
+// Formats a price with thousands separators, e.g. `64,200` or `12.34`.
+fn format_price(price: f64) -> String {
+    let precision = if price.abs() >= 1000.0 { 0 } else { 2 };
+    let formatted = format!("{:.*}", precision, price.abs());
+    let (whole, fraction) = formatted.split_at(formatted.find('.').unwrap_or(formatted.len()));
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if price < 0.0 { "-" } else { "" };
+    format!("{sign}{grouped}{fraction}")
+}
+
+/// Shows the latest price of a stock, currency or other symbol.
+///
+/// This widget periodically fetches JSON from a URL and reads the price from
+/// it using a [JSON pointer], e.g. `/data/amount`. The price may be either a
+/// number or a string containing a number. By default, it shows e.g.
+/// `BTC $64,200 ▲`, colored green or red depending on whether the price went
+/// up or down since the previous fetch. These colors can be changed with
+/// [`Ticker::with_colors()`].
+///
+/// If fetching fails, the last price is kept.
+///
+/// [JSON pointer]: https://datatracker.ietf.org/doc/html/rfc6901
+pub struct Ticker {
+    attr: Attributes,
+    symbol: String,
+    url: String,
+    pointer: String,
+    update_interval: Duration,
+    up_color: Color,
+    down_color: Color,
+    render: Option<Box<dyn Fn(TickerInfo) -> String>>,
+}
+
+impl Ticker {
+    /// Creates a new [`Ticker`] widget.
+    ///
+    /// Arguments
+    ///
+    /// * `attr` - Represents `Attributes` which controls properties like
+    /// `Font`, foreground and background color etc.
+    ///
+    /// * `symbol` - The name of the symbol to show, e.g. `BTC`.
+    ///
+    /// * `url` - The URL to fetch the JSON containing the price from.
+    ///
+    /// * `pointer` - A JSON pointer to the price within the fetched JSON.
+    ///
+    /// * `interval` - How often to fetch the price.
+    ///
+    /// * `render` - We use the closure to control the way output is
+    /// displayed in the bar. [`TickerInfo`] represents the latest price and
+    /// how it has changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::ticker::*;
+    /// # use anyhow::Result;
+    /// # use std::time::Duration;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(Ticker::new(
+    ///     attr,
+    ///     "BTC",
+    ///     "https://api.coinbase.com/v2/prices/BTC-USD/spot",
+    ///     "/data/amount",
+    ///     Duration::from_secs(300),
+    ///     None,
+    /// ));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    pub fn new<S: Into<String>, U: Into<String>, P: Into<String>>(
+        attr: Attributes,
+        symbol: S,
+        url: U,
+        pointer: P,
+        interval: Duration,
+        render: Option<Box<dyn Fn(TickerInfo) -> String>>,
+    ) -> Ticker {
+        Ticker {
+            attr,
+            symbol: symbol.into(),
+            url: url.into(),
+            pointer: pointer.into(),
+            update_interval: interval,
+            up_color: Color::green(),
+            down_color: Color::red(),
+            render,
+        }
+    }
+
+    /// Returns a new [`Ticker`] widget which shows the price in `up_color`
+    /// when it went up, and in `down_color` when it went down, rather than
+    /// in green and red.
+    pub fn with_colors(self, up_color: Color, down_color: Color) -> Ticker {
+        Ticker {
+            up_color,
+            down_color,
+            ..self
+        }
+    }
+
+    async fn fetch_price(&self) -> Result<f64> {
+        let body = reqwest::get(&self.url)
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Failed to fetch price from {}", self.url))?
+            .text()
+            .await?;
+        let response: serde_json::Value = serde_json::from_str(&body)?;
+
+        let value = response
+            .pointer(&self.pointer)
+            .ok_or_else(|| anyhow!("No value at {} in response", self.pointer))?;
+        let price = match value {
+            serde_json::Value::String(price) => price.parse().ok(),
+            value => value.as_f64(),
+        };
+        price.ok_or_else(|| anyhow!("Value at {} is not a number: {value}", self.pointer))
+    }
+
+    fn on_change(&self, info: TickerInfo) -> Vec<Text> {
+        let text = match &self.render {
+            Some(render) => (render)(info),
+            None => {
+                let text = format!(
+                    "{} ${}",
+                    escape_markup(&info.symbol),
+                    format_price(info.price)
+                );
+                match info.change {
+                    Some(change) if change > 0.0 => format!(
+                        "<span foreground=\"{}\">{text} ▲</span>",
+                        self.up_color.to_hex()
+                    ),
+                    Some(change) if change < 0.0 => format!(
+                        "<span foreground=\"{}\">{text} ▼</span>",
+                        self.down_color.to_hex()
+                    ),
+                    _ => text,
+                }
+            }
+        };
+
+        vec![Text::new(self.attr.clone(), text)]
+    }
+}
+
+impl Widget for Ticker {
+    fn name(&self) -> &str {
+        "Ticker"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = try_stream! {
+            let mut interval = time::interval(self.update_interval);
+            let mut last_price: Option<f64> = None;
+            loop {
+                interval.tick().await;
+                match self.fetch_price().await {
+                    Ok(price) => {
+                        let info = TickerInfo {
+                            symbol: self.symbol.clone(),
+                            price,
+                            change: last_price.map(|last| price - last),
+                        };
+                        last_price = Some(price);
+                        yield self.on_change(info);
+                    }
+                    // Keep showing the last price.
+                    Err(err) => warn!("Failed to fetch price of {}: {err:#}", self.symbol),
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_price;
+
+    #[test]
+    fn prices_are_grouped() {
+        assert_eq!(format_price(64200.4), "64,200");
+        assert_eq!(format_price(1234567.0), "1,234,567");
+        assert_eq!(format_price(999.5), "999.50");
+        assert_eq!(format_price(-1500.0), "-1,500");
+    }
+}
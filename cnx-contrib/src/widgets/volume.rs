@@ -4,6 +4,9 @@ mod volume_bsd;
 #[cfg(target_os = "linux")]
 #[cfg(feature = "volume")]
 mod volume_linux;
+#[cfg(target_os = "linux")]
+#[cfg(feature = "pulseaudio")]
+mod volume_pulse;
 #[cfg(target_os = "openbsd")]
 #[cfg(feature = "volume")]
 pub use volume_bsd::Volume;
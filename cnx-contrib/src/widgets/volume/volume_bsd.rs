@@ -94,15 +94,15 @@ impl Volume {
             State::Unmuted { percentage } => format!("{:.0}%", percentage),
         };
 
-        Ok(vec![Text {
-            attr: self.attr.clone(),
-            text,
-            stretch: false,
-        }])
+        Ok(vec![Text::new(self.attr.clone(), text).with_markup(false)])
     }
 }
 
 impl Widget for Volume {
+    fn name(&self) -> &str {
+        "Volume"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let info = VolumeInfo::new();
         let stream = info.stream().map(move |state| self.on_change(state));
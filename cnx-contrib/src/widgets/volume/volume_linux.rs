@@ -2,7 +2,8 @@ use alsa::mixer::{SelemChannelId, SelemId};
 use alsa::{self, Mixer, PollDescriptors};
 use anyhow::{anyhow, Context, Result};
 use cnx::text::{Attributes, Text};
-use cnx::widgets::{Widget, WidgetStream};
+use cnx::widgets::{InputHandler, MouseButton, Widget, WidgetStream};
+use log::error;
 use std::os::unix::io::AsRawFd;
 use std::os::unix::io::RawFd;
 use std::pin::Pin;
@@ -10,17 +11,37 @@ use std::task::Poll;
 use tokio::io::unix::AsyncFd;
 use tokio_stream::{Stream, StreamExt};
 
-/// Shows the current volume of the default ALSA output.
+#[cfg(feature = "pulseaudio")]
+use super::volume_pulse::PulseEventStream;
+
+/// The sound server that the [`Volume`] widget reads its state from.
+enum Backend {
+    /// Talk to the `default` ALSA mixer directly.
+    Alsa,
+    /// Follow the default sink of a PulseAudio (or PipeWire) server.
+    #[cfg(feature = "pulseaudio")]
+    Pulse,
+}
+
+/// Shows the current volume of the default output.
+///
+/// This widget shows the current volume of the default output, or `🔇` if the
+/// output is muted. It has two backends:
+///
+/// * [`Volume::new()`] follows the `default` ALSA mixer, using `alsa-lib` to
+/// receive events when the volume changes, avoiding expensive polling.
 ///
-/// This widget shows the current volume of the default ALSA output, or '`M`' if
-/// the output is muted.
+/// * [`Volume::new_pulse()`] follows the default sink of a PulseAudio (or
+/// PipeWire) server. This is only available with the `pulseaudio` feature on
+/// `cnx-contrib`, which also needs `libpulse` to be installed.
 ///
-/// The widget uses `alsa-lib` to receive events when the volume changes,
-/// avoiding expensive polling. If you do not have `alsa-lib` installed, you
-/// can disable the `volume-widget` feature on the `cnx` crate to avoid
-/// compiling this widget.
+/// When PulseAudio or PipeWire is managing your sound, the `default` ALSA
+/// mixer may not reflect the sink that is actually in use, so use the
+/// PulseAudio backend instead.
 pub struct Volume {
     attr: Attributes,
+    backend: Backend,
+    scroll_step: Option<f64>,
 }
 
 impl Volume {
@@ -52,10 +73,9 @@ impl Volume {
     /// #
     /// # fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 21"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
@@ -65,14 +85,79 @@ impl Volume {
     /// # fn main() { run().unwrap(); }
     /// ```
     pub fn new(attr: Attributes) -> Volume {
-        Volume { attr }
+        Volume {
+            attr,
+            backend: Backend::Alsa,
+            scroll_step: None,
+        }
     }
-}
 
-// https://github.com/mjkillough/cnx/blob/92c24238be541c75d88181208862505739be33fd/src/widgets/volume.rs
+    /// Creates a new Volume widget which follows the PulseAudio default sink.
+    ///
+    /// This works the same way as [`Volume::new()`], but rather than reading
+    /// the `default` ALSA mixer it connects to the PulseAudio server (which
+    /// includes PipeWire's `pipewire-pulse`) and subscribes to sink and server
+    /// change events. The volume and mute state of whichever sink is currently
+    /// the default are shown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use]
+    /// # extern crate cnx;
+    /// #
+    /// # use cnx::*;
+    /// # use cnx::text::*;
+    /// # use cnx_contrib::widgets::*;
+    /// # use anyhow::Result;
+    /// #
+    /// # fn run() -> Result<()> {
+    /// let attr = Attributes {
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let mut cnx = Cnx::new(Position::Top);
+    /// cnx.add_widget(volume::Volume::new_pulse(attr.clone()));
+    /// # Ok(())
+    /// # }
+    /// # fn main() { run().unwrap(); }
+    /// ```
+    #[cfg(feature = "pulseaudio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "pulseaudio")))]
+    pub fn new_pulse(attr: Attributes) -> Volume {
+        Volume {
+            attr,
+            backend: Backend::Pulse,
+            scroll_step: None,
+        }
+    }
 
-impl Widget for Volume {
-    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+    /// Lets the volume be changed by scrolling over the widget.
+    ///
+    /// Scrolling up or down raises or lowers the volume by `step` percent.
+    /// This is only supported by the ALSA backend created with
+    /// [`Volume::new()`]. It does nothing for a widget created with
+    /// [`Volume::new_pulse()`].
+    pub fn with_scroll_step(self, step: f64) -> Volume {
+        Volume {
+            scroll_step: Some(step),
+            ..self
+        }
+    }
+
+    fn on_change(&self, mute: bool, percentage: f64) -> Vec<Text> {
+        let text = if !mute {
+            format!("<span foreground=\"#808080\">[</span>🔈 {percentage:.0}%<span foreground=\"#808080\">]</span>")
+        } else {
+            "🔇".to_owned()
+        };
+
+        vec![Text::new(self.attr.clone(), text)]
+    }
+
+    fn alsa_stream(self: Box<Self>) -> Result<WidgetStream> {
         let mixer_name = "default";
         // We don't attempt to use the same mixer to listen for events and to
         // recompute the mixer state (in the callback below) as the Mixer seems
@@ -86,30 +171,79 @@ impl Widget for Volume {
             let channel = SelemChannelId::FrontLeft;
 
             let mixer = Mixer::new(mixer_name, true)?;
-            let master = mixer.find_selem(&SelemId::new("Master", 0))
-             .ok_or_else(|| anyhow!("Couldn't open Master channel"))?;
+            let master = mixer
+                .find_selem(&SelemId::new("Master", 0))
+                .ok_or_else(|| anyhow!("Couldn't open Master channel"))?;
 
             let mute = master.get_playback_switch(channel)? == 0;
+            let volume = master.get_playback_volume(channel)?;
+            let (min, max) = master.get_playback_volume_range();
+            let percentage = (volume as f64 / (max as f64 - min as f64)) * 100.0;
 
-            let text = if !mute {
-                let volume = master.get_playback_volume(channel)?;
-                let (min, max) = master.get_playback_volume_range();
-                let percentage = (volume as f64 / (max as f64 - min as f64)) * 100.0;
-                format!("<span foreground=\"#808080\">[</span>🔈 {percentage:.0}%<span foreground=\"#808080\">]</span>")
-            } else {
-                "🔇".to_owned()
-            };
-
-            Ok(vec![Text {
-                attr: self.attr.clone(),
-                text,
-                stretch: false,
-                markup: true,
-            }])
+            Ok(self.on_change(mute, percentage))
         });
 
         Ok(Box::pin(stream))
     }
+
+    #[cfg(feature = "pulseaudio")]
+    fn pulse_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let stream = PulseEventStream::new()?
+            .map(move |state| state.map(|state| self.on_change(state.mute, state.percentage)));
+
+        Ok(Box::pin(stream))
+    }
+}
+
+// https://github.com/mjkillough/cnx/blob/92c24238be541c75d88181208862505739be33fd/src/widgets/volume.rs
+
+impl Widget for Volume {
+    fn name(&self) -> &str {
+        "Volume"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        match self.backend {
+            Backend::Alsa => self.alsa_stream(),
+            #[cfg(feature = "pulseaudio")]
+            Backend::Pulse => self.pulse_stream(),
+        }
+    }
+
+    fn input_handler(&mut self) -> Option<InputHandler> {
+        let step = self.scroll_step?;
+        if !matches!(self.backend, Backend::Alsa) {
+            return None;
+        }
+
+        // Changing the mixer generates an ALSA event, so the widget is
+        // re-rendered by `alsa_stream()`.
+        Some(Box::new(move |button| {
+            let result = match button {
+                MouseButton::ScrollUp => change_alsa_volume(step),
+                MouseButton::ScrollDown => change_alsa_volume(-step),
+                _ => Ok(()),
+            };
+            if let Err(err) = result {
+                error!("Failed to change volume: {err}");
+            }
+        }))
+    }
+}
+
+// Changes the volume of the `default` mixer's Master channel by `step`
+// percent of its range.
+fn change_alsa_volume(step: f64) -> Result<()> {
+    let mixer = Mixer::new("default", false)?;
+    let master = mixer
+        .find_selem(&SelemId::new("Master", 0))
+        .ok_or_else(|| anyhow!("Couldn't open Master channel"))?;
+
+    let (min, max) = master.get_playback_volume_range();
+    let volume = master.get_playback_volume(SelemChannelId::FrontLeft)?;
+    let delta = ((max - min) as f64 * step / 100.0).round() as i64;
+    master.set_playback_volume_all((volume + delta).clamp(min, max))?;
+    Ok(())
 }
 
 struct AlsaEvented(Mixer);
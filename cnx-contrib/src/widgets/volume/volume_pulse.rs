@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Context as _, Result};
+use libpulse_binding as pulse;
+use pulse::callbacks::ListResult;
+use pulse::context::subscribe::InterestMaskSet;
+use pulse::context::{Context, FlagSet, State as ContextState};
+use pulse::mainloop::standard::{IterateResult, Mainloop};
+use pulse::volume::Volume;
+use std::cell::Cell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::mpsc as std_mpsc;
+use std::task::Poll;
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::Stream;
+
+// How long to wait for PulseAudio events before checking whether the widget
+// has gone away.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// How long to wait before reconnecting, e.g. while the server restarts.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The volume/mute state of the PulseAudio default sink.
+pub(super) struct PulseState {
+    pub mute: bool,
+    pub percentage: f64,
+}
+
+// A `Stream` of `PulseState` for the default sink of the PulseAudio server.
+//
+// libpulse wants to drive its own mainloop, which doesn't fit nicely into
+// tokio's reactor. Rather than trying to integrate the two, we run a standard
+// libpulse mainloop on its own thread and forward the state of the default sink
+// over a channel each time the server tells us a sink (or the server's choice
+// of default sink) has changed.
+//
+// If the connection to the server is lost, the error is sent over the channel
+// and we keep trying to reconnect. The thread exits once the stream has been
+// dropped.
+pub(super) struct PulseEventStream {
+    receiver: mpsc::UnboundedReceiver<Result<PulseState>>,
+}
+
+impl PulseEventStream {
+    pub fn new() -> Result<PulseEventStream> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        // The libpulse `Context` isn't `Send`, so it has to be created on the
+        // thread that runs the mainloop. Wait for it to connect so that we can
+        // report connection failures when the widget is created.
+        let (ready_sender, ready_receiver) = std_mpsc::sync_channel(1);
+
+        thread::Builder::new()
+            .name("cnx-pulseaudio".to_owned())
+            .spawn(move || match connect() {
+                Ok((mainloop, context)) => {
+                    let _ = ready_sender.send(Ok(()));
+                    run(mainloop, context, sender);
+                }
+                Err(err) => {
+                    let _ = ready_sender.send(Err(err));
+                }
+            })
+            .context("Failed to spawn PulseAudio thread")?;
+
+        ready_receiver
+            .recv()
+            .context("PulseAudio thread exited unexpectedly")??;
+
+        Ok(PulseEventStream { receiver })
+    }
+}
+
+impl Stream for PulseEventStream {
+    type Item = Result<PulseState>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context,
+    ) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+// Watches the default sink until the stream is dropped, reconnecting to the
+// server whenever the connection is lost.
+fn run(
+    mut mainloop: Mainloop,
+    mut context: Context,
+    sender: mpsc::UnboundedSender<Result<PulseState>>,
+) {
+    loop {
+        let err = match watch_default_sink(&mut mainloop, &mut context, &sender) {
+            Ok(()) => return,
+            Err(err) => err,
+        };
+        if sender.send(Err(err)).is_err() {
+            return;
+        }
+
+        // The context has to be dropped before the mainloop it was created
+        // with.
+        drop(context);
+        drop(mainloop);
+        let (new_mainloop, new_context) = match reconnect(&sender) {
+            Some(connected) => connected,
+            None => return,
+        };
+        mainloop = new_mainloop;
+        context = new_context;
+    }
+}
+
+// Keeps trying to connect to the server (and reporting why we can't) until
+// it is back, or returns `None` if the stream is dropped first.
+fn reconnect(sender: &mpsc::UnboundedSender<Result<PulseState>>) -> Option<(Mainloop, Context)> {
+    loop {
+        thread::sleep(RECONNECT_INTERVAL);
+        if sender.is_closed() {
+            return None;
+        }
+        match connect() {
+            Ok(connected) => return Some(connected),
+            Err(err) => sender.send(Err(err)).ok()?,
+        }
+    }
+}
+
+fn connect() -> Result<(Mainloop, Context)> {
+    let mut mainloop =
+        Mainloop::new().ok_or_else(|| anyhow!("Failed to create PulseAudio mainloop"))?;
+    let mut context = Context::new(&mainloop, "cnx")
+        .ok_or_else(|| anyhow!("Failed to create PulseAudio context"))?;
+    context
+        .connect(None, FlagSet::NOFLAGS, None)
+        .map_err(|err| anyhow!("Failed to connect to PulseAudio: {err}"))?;
+
+    loop {
+        iterate(&mut mainloop, true)?;
+        match context.get_state() {
+            ContextState::Ready => break,
+            ContextState::Failed | ContextState::Terminated => {
+                return Err(anyhow!("Failed to connect to PulseAudio"));
+            }
+            _ => (),
+        }
+    }
+
+    Ok((mainloop, context))
+}
+
+// Runs a single iteration of the mainloop, returning how many events were
+// dispatched. If `block` is false, this returns immediately if there are no
+// events.
+fn iterate(mainloop: &mut Mainloop, block: bool) -> Result<u32> {
+    match mainloop.iterate(block) {
+        IterateResult::Success(dispatched) => Ok(dispatched),
+        IterateResult::Quit(_) => Err(anyhow!("PulseAudio mainloop quit")),
+        IterateResult::Err(err) => Err(anyhow!("PulseAudio mainloop error: {err}")),
+    }
+}
+
+// Sends the state of the default sink each time it may have changed, until
+// the stream is dropped (returning `Ok`) or the connection is lost.
+fn watch_default_sink(
+    mainloop: &mut Mainloop,
+    context: &mut Context,
+    sender: &mpsc::UnboundedSender<Result<PulseState>>,
+) -> Result<()> {
+    // We don't bother looking at which sink changed. Each time we're told
+    // about a change we just requery the default sink, which also handles the
+    // server switching to a different default sink.
+    let changed = Rc::new(Cell::new(true));
+    {
+        let changed = changed.clone();
+        context.set_subscribe_callback(Some(Box::new(move |_, _, _| changed.set(true))));
+    }
+    context.subscribe(InterestMaskSet::SINK | InterestMaskSet::SERVER, |_| ());
+
+    let introspector = context.introspect();
+    while !sender.is_closed() {
+        if changed.replace(false) {
+            let sender = sender.clone();
+            introspector.get_sink_info_by_name("@DEFAULT_SINK@", move |result| {
+                if let ListResult::Item(info) = result {
+                    let volume = info.volume.avg();
+                    let percentage = (f64::from(volume.0) / f64::from(Volume::NORMAL.0)) * 100.0;
+                    let _ = sender.send(Ok(PulseState {
+                        mute: info.mute,
+                        percentage,
+                    }));
+                }
+            });
+        }
+
+        // Blocking until the next event would stop us from noticing that
+        // the stream was dropped, so poll instead.
+        if iterate(mainloop, false)? == 0 {
+            thread::sleep(POLL_INTERVAL);
+        }
+        if let ContextState::Failed | ContextState::Terminated = context.get_state() {
+            return Err(anyhow!("Lost connection to PulseAudio"));
+        }
+    }
+
+    Ok(())
+}
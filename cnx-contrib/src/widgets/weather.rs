@@ -1,15 +1,198 @@
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
 use async_stream::try_stream;
+use async_trait::async_trait;
 use cnx::text::{Attributes, Text};
 use cnx::widgets::{Widget, WidgetStream};
+use log::warn;
+use std::rc::Rc;
 use std::time::Duration;
-use weathernoaa::weather::*;
+use tokio::time::{self, MissedTickBehavior};
+
+/// The full weather report from NOAA, as returned by the `weathernoaa`
+/// crate.
+pub use weathernoaa::weather::WeatherInfo as NoaaWeatherInfo;
+
+/// A temperature, in both Celsius and Fahrenheit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Temperature {
+    pub celsius: f64,
+    pub fahrenheit: f64,
+}
+
+impl Temperature {
+    fn from_celsius(celsius: f64) -> Temperature {
+        Temperature {
+            celsius,
+            fahrenheit: celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+}
+
+/// The wind's speed and direction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Wind {
+    /// The wind speed, in km/h
+    pub speed_kmh: f64,
+    /// The direction the wind is blowing from, in degrees clockwise from
+    /// north, if known
+    pub direction: Option<f64>,
+}
+
+/// Represents the current weather, as fetched by a [`WeatherProvider`].
+///
+/// Not every provider reports everything, so all but the sky condition and
+/// temperature are optional.
+#[derive(Clone, Debug)]
+pub struct WeatherInfo {
+    /// A short, lower case description of the sky, e.g. `clear` or `overcast`
+    pub sky_condition: String,
+    /// The current temperature
+    pub temperature: Temperature,
+    /// The dew point
+    pub dew_point: Option<Temperature>,
+    /// The relative humidity, as a percentage
+    pub humidity: Option<f64>,
+    /// The air pressure, in hPa
+    pub pressure: Option<f64>,
+    /// The wind
+    pub wind: Option<Wind>,
+    /// The full report, if the weather was fetched by [`Noaa`]. This has
+    /// details that other providers don't report, such as the visibility.
+    pub noaa: Option<Rc<NoaaWeatherInfo>>,
+}
+
+/// A source of weather information for the [`Weather`] widget.
+///
+/// Implement this to show the weather from a service that isn't supported by
+/// this crate.
+#[async_trait(?Send)]
+pub trait WeatherProvider {
+    /// Fetches the current weather.
+    async fn fetch(&self) -> Result<WeatherInfo>;
+}
+
+/// Fetches the weather from NOAA for a weather station.
+pub struct Noaa {
+    station_code: String,
+}
+
+impl Noaa {
+    /// Creates a new NOAA [`WeatherProvider`].
+    ///
+    /// The `station_code` is a weather station code from the Federal
+    /// Climate Complex ISD. You can find your place's station code by getting
+    /// the information from either [NOAA's
+    /// archive](https://www1.ncdc.noaa.gov/pub/data/noaa/isd-history.txt) or
+    /// [Internet Archive's
+    /// data](https://web.archive.org/web/20210522235412/https://www1.ncdc.noaa.gov/pub/data/noaa/isd-history.txt)
+    /// of the same link.
+    pub fn new<S: Into<String>>(station_code: S) -> Noaa {
+        Noaa {
+            station_code: station_code.into(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl WeatherProvider for Noaa {
+    async fn fetch(&self) -> Result<WeatherInfo> {
+        let weather = weathernoaa::weather::get_weather(self.station_code.clone()).await?;
+        Ok(WeatherInfo {
+            sky_condition: weather.sky_condition.clone(),
+            temperature: Temperature::from_celsius(f64::from(weather.temperature.celsius)),
+            dew_point: Some(Temperature::from_celsius(f64::from(
+                weather.dew_point.celsius,
+            ))),
+            humidity: Some(f64::from(weather.humidity)),
+            pressure: Some(f64::from(weather.pressure)),
+            wind: Some(Wind {
+                speed_kmh: f64::from(weather.wind.kmph),
+                // The azimuth is reported as text, which isn't a number when
+                // the wind's direction is variable.
+                direction: weather.wind.azimuth.trim().parse().ok(),
+            }),
+            noaa: Some(Rc::new(weather)),
+        })
+    }
+}
+
+/// Fetches the weather from [OpenWeatherMap](https://openweathermap.org/)
+/// for a location.
+pub struct OpenWeatherMap {
+    api_key: String,
+    latitude: f64,
+    longitude: f64,
+}
+
+impl OpenWeatherMap {
+    /// Creates a new OpenWeatherMap [`WeatherProvider`].
+    ///
+    /// The `api_key` can be created for free after signing up to
+    /// OpenWeatherMap. The weather is fetched for the location at the given
+    /// `latitude` and `longitude`.
+    pub fn new<S: Into<String>>(api_key: S, latitude: f64, longitude: f64) -> OpenWeatherMap {
+        OpenWeatherMap {
+            api_key: api_key.into(),
+            latitude,
+            longitude,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl WeatherProvider for OpenWeatherMap {
+    async fn fetch(&self) -> Result<WeatherInfo> {
+        let url = format!(
+            "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&units=metric",
+            self.latitude, self.longitude, self.api_key
+        );
+        let body = reqwest::get(&url)
+            .await?
+            .error_for_status()
+            .context("Failed to fetch weather from OpenWeatherMap")?
+            .text()
+            .await?;
+        let response: serde_json::Value = serde_json::from_str(&body)?;
+
+        let celsius = response["main"]["temp"]
+            .as_f64()
+            .ok_or_else(|| anyhow!("OpenWeatherMap response has no temperature"))?;
+        let sky_condition = response["weather"][0]["description"]
+            .as_str()
+            .ok_or_else(|| anyhow!("OpenWeatherMap response has no description"))?
+            .to_lowercase();
+
+        let main = &response["main"];
+        let wind = response["wind"]["speed"].as_f64().map(|speed| Wind {
+            // OpenWeatherMap reports the speed in m/s.
+            speed_kmh: speed * 3.6,
+            direction: response["wind"]["deg"].as_f64(),
+        });
+
+        Ok(WeatherInfo {
+            sky_condition,
+            temperature: Temperature::from_celsius(celsius),
+            dew_point: None,
+            humidity: main["humidity"].as_f64(),
+            pressure: main["pressure"].as_f64(),
+            wind,
+            noaa: None,
+        })
+    }
+}
 
 /// Represents Weather widget used to show current weather information.
+///
+/// The weather is fetched every 30 minutes by default, which can be changed
+/// with [`Weather::with_interval()`]. If fetching fails, the last
+/// weather is kept and fetching is retried with an increasing delay. After
+/// several failures in a row, a `!` is shown after the weather to show that it
+/// is out of date.
 pub struct Weather {
     attr: Attributes,
-    station_code: String,
+    provider: Box<dyn WeatherProvider>,
     render: Option<Box<dyn Fn(WeatherInfo) -> String>>,
+    update_interval: Duration,
 }
 
 impl Weather {
@@ -20,17 +203,12 @@ impl Weather {
     /// * `attr` - Represents `Attributes` which controls properties like
     /// `Font`, foreground and background color etc.
     ///
-    /// * `station_code` - Represents weather station code from the
-    /// Federal Climate Complex ISD. You can find your place's station
-    /// code by getting the information from either [NOAA's
-    /// archive](https://www1.ncdc.noaa.gov/pub/data/noaa/isd-history.txt)
-    /// or [Internet Archive's
-    /// data](https://web.archive.org/web/20210522235412/https://www1.ncdc.noaa.gov/pub/data/noaa/isd-history.txt)
-    /// of the same link.
+    /// * `provider` - Represents the source of the weather information,
+    /// e.g. [`Noaa`] or [`OpenWeatherMap`].
     ///
     /// * `render` - We use the closure to control the way output is
     /// displayed in the bar. [`WeatherInfo`] represents the current
-    /// weather details of the particular station.
+    /// weather details of the particular location.
     ///
     /// # Examples
     ///
@@ -45,48 +223,111 @@ impl Weather {
     /// #
     /// # fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 21"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
-    /// cnx.add_widget(Weather::new(attr, "VOBL".into(),  None));
+    /// cnx.add_widget(Weather::new(attr, Box::new(Noaa::new("VOBL")), None));
     /// # Ok(())
     /// # }
     /// # fn main() { run().unwrap(); }
     /// ```
     pub fn new(
         attr: Attributes,
-        station_code: String,
+        provider: Box<dyn WeatherProvider>,
         render: Option<Box<dyn Fn(WeatherInfo) -> String>>,
     ) -> Weather {
         Weather {
             attr,
-            station_code,
+            provider,
             render,
+            update_interval: UPDATE_INTERVAL,
         }
     }
+
+    /// Returns a new [`Weather`] widget which fetches the weather every
+    /// `interval`, rather than every 30 minutes.
+    ///
+    /// Intervals shorter than a minute are rounded up to a minute, so that
+    /// the weather provider isn't flooded with requests.
+    pub fn with_interval(self, interval: Duration) -> Self {
+        Self {
+            update_interval: interval.max(MIN_UPDATE_INTERVAL),
+            ..self
+        }
+    }
+
+    fn render_weather(&self, weather: &WeatherInfo) -> Vec<Text> {
+        let text = self
+            .render
+            .as_ref()
+            .map_or(format!("Temp: {}°C", weather.temperature.celsius), |x| {
+                (x)(weather.clone())
+            });
+        vec![Text::new(self.attr.clone(), text)]
+    }
+
+    // The last weather we fetched (if any), marked as being out of date.
+    fn render_stale(&self, weather: Option<&WeatherInfo>) -> Vec<Text> {
+        let mut texts = weather.map_or_else(Vec::new, |weather| self.render_weather(weather));
+        texts.push(Text::new(self.attr.clone(), "!".to_owned()).with_markup(false));
+        texts
+    }
 }
 
+// How often to fetch the weather by default.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+// The shortest interval allowed by `Weather::with_interval()`.
+const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(60);
+// How long to wait before retrying after the first failure. This doubles
+// with each consecutive failure, until the next regular update is due.
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+// How many consecutive failures before we show that the weather is stale.
+const MAX_FAILURES: u32 = 3;
+
 impl Widget for Weather {
+    fn name(&self) -> &str {
+        "Weather"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let stream = try_stream! {
+            // Network errors are usually transient, so rather than ending
+            // the stream we keep showing the last weather we fetched and
+            // retry with a backoff.
+            let mut last_weather: Option<WeatherInfo> = None;
+            let mut failures = 0;
+            // The first tick completes immediately, so the weather is
+            // fetched as soon as the bar starts.
+            let mut interval = time::interval(self.update_interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
             loop {
-                let weather = get_weather(self.station_code.clone()).await?;
-                let text = self.render.as_ref().map_or(format!("Temp: {}°C", weather.temperature.celsius), |x| (x)(weather));
-                let texts = vec![Text {
-                    attr: self.attr.clone(),
-                    text,
-                    stretch: false,
-                    markup: true,
-                }];
-                yield texts;
-
-                let thirty_minutes = 30 * 60;
-                let sleep_for = Duration::from_secs(thirty_minutes);
-                tokio::time::sleep(sleep_for).await;
+                interval.tick().await;
+                loop {
+                    match self.provider.fetch().await {
+                        Ok(weather) => {
+                            failures = 0;
+                            yield self.render_weather(&weather);
+                            last_weather = Some(weather);
+                            break;
+                        }
+                        Err(err) => {
+                            failures += 1;
+                            warn!("Failed to fetch weather ({failures} in a row): {err:#}");
+                            if failures == MAX_FAILURES {
+                                yield self.render_stale(last_weather.as_ref());
+                            }
+                            let retry = RETRY_INTERVAL
+                                .saturating_mul(2u32.saturating_pow(failures - 1));
+                            if retry >= self.update_interval {
+                                break;
+                            }
+                            time::sleep(retry).await;
+                        }
+                    }
+                }
             }
         };
         Ok(Box::pin(stream))
@@ -1,18 +1,146 @@
 use anyhow::Result;
-use cnx::text::{Attributes, Text, Threshold};
+use cnx::text::{escape_markup, Attributes, Text, Threshold};
 use cnx::widgets::{Widget, WidgetStream};
-use iwlib::*;
+use iwlib::get_wireless_info;
+use nix::libc;
+use nix::sys::socket::{socket, AddressFamily, SockFlag, SockType};
+use std::fmt;
 use std::time::Duration;
 use tokio::time;
 use tokio_stream::wrappers::IntervalStream;
 use tokio_stream::StreamExt;
 
+// The Wireless Extensions ioctls for the current frequency and bitrate.
+const SIOCGIWFREQ: u32 = 0x8B05;
+const SIOCGIWRATE: u32 = 0x8B21;
+
+// The `iwreq` struct passed to Wireless Extensions ioctls. The data is a
+// union, which is at most 16 bytes.
+#[repr(C)]
+struct IwReq {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    data: [u8; 16],
+}
+
+// Makes a Wireless Extensions `request` for `interface`, returning the data
+// the kernel filled in.
+fn wext_ioctl(interface: &str, request: u32) -> Option<[u8; 16]> {
+    let fd = socket(
+        AddressFamily::Inet,
+        SockType::Datagram,
+        SockFlag::SOCK_CLOEXEC,
+        None,
+    )
+    .ok()?;
+    let mut req = IwReq {
+        name: [0; libc::IFNAMSIZ],
+        data: [0; 16],
+    };
+    // Leave room for the terminating NUL.
+    for (dst, src) in req
+        .name
+        .iter_mut()
+        .zip(interface.bytes().take(libc::IFNAMSIZ - 1))
+    {
+        *dst = src as libc::c_char;
+    }
+    // SAFETY: `req` is a valid `iwreq`, which both requests fill in.
+    let result = unsafe { libc::ioctl(fd, request as _, &mut req) };
+    let _ = nix::unistd::close(fd);
+    (result >= 0).then_some(req.data)
+}
+
+// Returns the bitrate of `interface`, in bits per second. The data is an
+// `iw_param`, whose first field is the value.
+fn bitrate(interface: &str) -> Option<u64> {
+    let data = wext_ioctl(interface, SIOCGIWRATE)?;
+    let value = i32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
+    u64::try_from(value).ok().filter(|&rate| rate > 0)
+}
+
+// Returns the frequency of `interface`, in MHz. The data is an `iw_freq`,
+// holding a mantissa and exponent.
+fn frequency(interface: &str) -> Option<u32> {
+    let data = wext_ioctl(interface, SIOCGIWFREQ)?;
+    let mantissa = i32::from_ne_bytes([data[0], data[1], data[2], data[3]]);
+    let exponent = i16::from_ne_bytes([data[4], data[5]]);
+    frequency_mhz(mantissa, exponent)
+}
+
+fn frequency_mhz(mantissa: i32, exponent: i16) -> Option<u32> {
+    // Small values without an exponent are channel numbers, not frequencies.
+    if exponent == 0 && mantissa < 1000 {
+        return None;
+    }
+    let hz = f64::from(mantissa) * 10f64.powi(i32::from(exponent));
+    let mhz = (hz / 1e6).round();
+    (mhz > 0.0 && mhz <= f64::from(u32::MAX)).then_some(mhz as u32)
+}
+
+/// The frequency band of a wireless connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Band {
+    /// 2.4 GHz
+    Ghz2_4,
+    /// 5 GHz
+    Ghz5,
+    /// 6 GHz
+    Ghz6,
+}
+
+impl Band {
+    fn from_mhz(mhz: u32) -> Option<Band> {
+        match mhz {
+            2400..=2500 => Some(Band::Ghz2_4),
+            5150..=5895 => Some(Band::Ghz5),
+            5925..=7125 => Some(Band::Ghz6),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let band = match self {
+            Band::Ghz2_4 => "2.4 GHz",
+            Band::Ghz5 => "5 GHz",
+            Band::Ghz6 => "6 GHz",
+        };
+        f.write_str(band)
+    }
+}
+
+/// Information about a wireless connection, passed to the [`Wireless`]
+/// widget's `render` closure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WirelessInfo {
+    pub essid: String,
+    /// The link quality, as a percentage.
+    pub quality: u8,
+    /// The negotiated bitrate, in bits per second, if the driver reports it.
+    pub bitrate: Option<u64>,
+    /// The frequency, in MHz, if the driver reports it.
+    pub frequency: Option<u32>,
+}
+
+impl WirelessInfo {
+    /// The frequency band of the connection, if known.
+    pub fn band(&self) -> Option<Band> {
+        self.frequency.and_then(Band::from_mhz)
+    }
+}
+
 /// Wireless widget to show wireless information for a particular ESSID
+///
+/// The bitrate and frequency are read with the Wireless Extensions ioctls
+/// (like `iwconfig`), which most drivers support through `cfg80211`. They're
+/// only shown if a `render` closure is given with [`Wireless::with_render()`].
 pub struct Wireless {
     attr: Attributes,
     interface: String,
     update_interval: Duration,
     threshold: Option<Threshold>,
+    render: Option<Box<dyn Fn(WirelessInfo) -> String>>,
 }
 
 impl Wireless {
@@ -43,10 +171,9 @@ impl Wireless {
     /// #
     /// # fn run() -> Result<()> {
     /// let attr = Attributes {
-    ///     font: Font::new("SourceCodePro 21"),
-    ///     fg_color: Color::white(),
-    ///     bg_color: None,
-    ///     padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+    ///     font: "SourceCodePro 21".into(),
+    ///     padding: Padding::horizontal(8.0),
+    ///     ..Default::default()
     /// };
     ///
     /// let mut cnx = Cnx::new(Position::Top);
@@ -61,25 +188,59 @@ impl Wireless {
             interface,
             attr,
             threshold,
+            render: None,
+        }
+    }
+
+    /// Returns a new [`Wireless`] widget which uses `render` to control the
+    /// way output is displayed in the bar.
+    ///
+    /// `render` receives a [`WirelessInfo`], which includes the bitrate and
+    /// frequency as well as the ESSID and quality. It isn't called when
+    /// there is no wireless connection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cnx::text::Attributes;
+    /// # use cnx_contrib::widgets::wireless::*;
+    /// let wireless = Wireless::new(Attributes::default(), "wlp2s0".into(), None).with_render(
+    ///     Box::new(|info: WirelessInfo| {
+    ///         let bitrate = info.bitrate.map_or(0, |rate| rate / 1_000_000);
+    ///         match info.band() {
+    ///             Some(band) => format!("{} {bitrate} Mb/s {band}", info.essid),
+    ///             None => format!("{} {bitrate} Mb/s", info.essid),
+    ///         }
+    ///     }),
+    /// );
+    /// ```
+    pub fn with_render(self, render: Box<dyn Fn(WirelessInfo) -> String>) -> Self {
+        Self {
+            render: Some(render),
+            ..self
         }
     }
 
     fn tick(&self) -> Vec<Text> {
         let wireless_info = get_wireless_info(self.interface.clone());
 
+        if let (Some(render), Some(info)) = (&self.render, &wireless_info) {
+            let info = WirelessInfo {
+                essid: info.wi_essid.clone(),
+                quality: info.wi_quality,
+                bitrate: bitrate(&self.interface),
+                frequency: frequency(&self.interface),
+            };
+            return vec![Text::new(self.attr.clone(), (render)(info))];
+        }
+
         let text = match wireless_info {
             Some(info) => match &self.threshold {
                 Some(thold) => {
-                    let color = if info.wi_quality <= thold.low.threshold {
-                        &thold.low.color
-                    } else if info.wi_quality <= thold.normal.threshold {
-                        &thold.normal.color
-                    } else {
-                        &thold.high.color
-                    };
+                    let color = thold.color_for(u32::from(info.wi_quality));
                     format!(
                         "<span foreground=\"#808080\">[</span>{} <span foreground=\"{}\">{}%</span><span foreground=\"#808080\">]</span>",
-                        info.wi_essid,
+                        escape_markup(&info.wi_essid),
                         color.to_hex(),
                         info.wi_quality
                     )
@@ -88,16 +249,15 @@ impl Wireless {
             },
             None => "NA".to_owned(),
         };
-        vec![Text {
-            attr: self.attr.clone(),
-            text,
-            stretch: false,
-            markup: self.threshold.is_some(),
-        }]
+        vec![Text::new(self.attr.clone(), text).with_markup(self.threshold.is_some())]
     }
 }
 
 impl Widget for Wireless {
+    fn name(&self) -> &str {
+        "Wireless"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let interval = time::interval(self.update_interval);
         let stream = IntervalStream::new(interval).map(move |_| Ok(self.tick()));
@@ -105,3 +265,22 @@ impl Widget for Wireless {
         Ok(Box::pin(stream))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{frequency_mhz, Band};
+
+    #[test]
+    fn frequency_bands() {
+        // Drivers usually report e.g. 2.412 GHz as 2412 * 10^6.
+        assert_eq!(frequency_mhz(2412, 6), Some(2412));
+        assert_eq!(frequency_mhz(518_000_000, 1), Some(5180));
+        // A channel number, rather than a frequency.
+        assert_eq!(frequency_mhz(36, 0), None);
+
+        assert_eq!(Band::from_mhz(2412), Some(Band::Ghz2_4));
+        assert_eq!(Band::from_mhz(5180), Some(Band::Ghz5));
+        assert_eq!(Band::from_mhz(5955), Some(Band::Ghz6));
+        assert_eq!(Band::from_mhz(60_480), None);
+    }
+}
@@ -1,11 +1,15 @@
+use std::collections::{HashMap, HashSet};
 use std::f64;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
+use log::{debug, trace, warn};
 use ordered_float::OrderedFloat;
 use xcb_util::ewmh;
 
-use crate::text::{ComputedText, Text};
+use crate::text::{create_pango_context, Color, ComputedText, LayoutCache, OnClick, Text};
+use crate::widgets::MouseButton;
 // use crate::widgets::{Widget, WidgetList};
 // use crate::xcb::XcbEventStream;
 
@@ -52,7 +56,8 @@ fn create_surface(
     height: u16,
     width: Option<u16>,
     offset: Offset,
-) -> Result<(u16, cairo::XCBSurface)> {
+    margins: Margins,
+) -> Result<(i16, u16, cairo::XCBSurface)> {
     let screen = conn
         .get_setup()
         .roots()
@@ -60,17 +65,22 @@ fn create_surface(
         .ok_or_else(|| anyhow!("Invalid screen"))?;
     let values = [
         (xcb::CW_BACK_PIXEL, screen.black_pixel()),
-        (xcb::CW_EVENT_MASK, xcb::EVENT_MASK_EXPOSURE),
+        (
+            xcb::CW_EVENT_MASK,
+            xcb::EVENT_MASK_EXPOSURE
+                | xcb::EVENT_MASK_VISIBILITY_CHANGE
+                | xcb::EVENT_MASK_BUTTON_PRESS,
+        ),
     ];
 
-    let width = width.unwrap_or_else(|| screen.width_in_pixels());
+    let (x, width) = horizontal_span(&screen, width, offset, margins);
 
     xcb::create_window(
         conn,
         xcb::COPY_FROM_PARENT as u8,
         window_id,
         screen.root(),
-        offset.x,
+        x,
         offset.y,
         width,
         height,
@@ -88,7 +98,26 @@ fn create_surface(
         i32::from(height),
     )?;
 
-    Ok((width, surface))
+    Ok((x, width, surface))
+}
+
+// Returns the x position and width of the part of the screen given to the
+// bar. A horizontal bar fills this span, and a vertical bar is placed at one
+// side of it.
+fn horizontal_span(
+    screen: &xcb::Screen<'_>,
+    width: Option<u16>,
+    offset: Offset,
+    margins: Margins,
+) -> (i16, u16) {
+    // The margins are taken from either side of the bar, leaving it centered
+    // in the space it would otherwise occupy.
+    let width = width
+        .unwrap_or_else(|| screen.width_in_pixels())
+        .saturating_sub(margins.left.saturating_add(margins.right))
+        .max(1);
+    let x = (i32::from(offset.x) + i32::from(margins.left)).min(i32::from(i16::MAX)) as i16;
+    (x, width)
 }
 
 /// An enum specifying the position of the Cnx bar.
@@ -104,12 +133,29 @@ fn create_surface(
 /// # use cnx::{Cnx, Position};
 /// let mut cnx = Cnx::new(Position::Top);
 /// ```
+///
+/// A bar at the left or right of the screen is vertical: it is as tall as the
+/// screen and as wide as its widest widget, and widgets are stacked from top
+/// to bottom. Text isn't rotated, so short texts work best. For a vertical
+/// bar, [`Cnx::with_height()`] sets the width of the bar instead.
+///
+/// [`Cnx::with_height()`]: struct.Cnx.html#method.with_height
 #[derive(Clone, Debug)]
 pub enum Position {
     /// Position the Cnx bar at the top of the screen.
     Top,
     /// Position the Cnx bar at the bottom of the screen.
     Bottom,
+    /// Position the Cnx bar at the left of the screen.
+    Left,
+    /// Position the Cnx bar at the right of the screen.
+    Right,
+}
+
+impl Position {
+    fn is_vertical(&self) -> bool {
+        matches!(self, Position::Left | Position::Right)
+    }
 }
 
 /// A struct specifying the `x` and `y` offset
@@ -119,6 +165,79 @@ pub struct Offset {
     pub y: i16,
 }
 
+// The gaps left between the sides of the screen and the bar.
+#[derive(Default, Clone, Copy)]
+pub(crate) struct Margins {
+    pub left: u16,
+    pub right: u16,
+}
+
+/// An enum specifying which edge of the Cnx bar a border is drawn along.
+///
+/// This is usually the inner edge of the bar: the bottom edge for a bar at the
+/// top of the screen, or the top edge for a bar at the bottom of the screen.
+/// For a vertical bar, `Top` is its left edge and `Bottom` its right edge.
+///
+/// Passed to [`Cnx::with_border()`].
+///
+/// [`Cnx::with_border()`]: struct.Cnx.html#method.with_border
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    /// Draw the border along the top edge of the bar.
+    Top,
+    /// Draw the border along the bottom edge of the bar.
+    Bottom,
+}
+
+/// An enum specifying the order in which widgets are laid out along the bar.
+///
+/// Passed to [`Cnx::with_order()`].
+///
+/// [`Cnx::with_order()`]: struct.Cnx.html#method.with_order
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Order {
+    /// Lay out widgets from the left edge rightward (or top edge downward,
+    /// for a vertical bar), in the order they were added.
+    #[default]
+    Normal,
+    /// Lay out widgets from the right edge leftward (or bottom edge upward,
+    /// for a vertical bar), in the order they were added. The texts within
+    /// each widget are still drawn left to right (or top to bottom).
+    Reversed,
+}
+
+/// An enum specifying where the bar is stacked relative to other windows.
+///
+/// Passed to [`Cnx::with_stack_mode()`].
+///
+/// [`Cnx::with_stack_mode()`]: struct.Cnx.html#method.with_stack_mode
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StackMode {
+    /// Keep the bar above other windows.
+    #[default]
+    Above,
+    /// Keep the bar below other windows, e.g. so that fullscreen windows
+    /// cover it.
+    Below,
+}
+
+impl StackMode {
+    fn to_xcb(self) -> u32 {
+        match self {
+            StackMode::Above => xcb::STACK_MODE_ABOVE,
+            StackMode::Below => xcb::STACK_MODE_BELOW,
+        }
+    }
+}
+
+/// A solid line drawn along one edge of the bar.
+#[derive(Clone, Debug)]
+pub struct Border {
+    pub color: Color,
+    pub thickness: u16,
+    pub edge: Edge,
+}
+
 pub struct Bar {
     position: Position,
 
@@ -127,15 +246,51 @@ pub struct Bar {
     window_id: u32,
 
     surface: cairo::XCBSurface,
+    // The x position of the window, including any offset and margin.
+    x: i16,
     width: u16,
     height: u16,
+    // The width and margins the bar was created with, which are needed to
+    // recreate its window.
+    requested_width: Option<u16>,
+    margins: Margins,
     offset: Offset,
+    border: Option<Border>,
+    order: Order,
+    fixed_height: Option<u16>,
+    stack_mode: StackMode,
+    // Settings which are applied to the window, and are re-applied if it is
+    // recreated by `reconnect()`.
+    input_passthrough: bool,
+    override_redirect: bool,
+    instance_lock: Option<String>,
 
+    // Fonts which have already been checked by `check_fonts()`.
+    checked_fonts: HashSet<String>,
+    // The sizes of texts which have already been laid out.
+    layout_cache: LayoutCache,
+    // How text is antialiased and hinted, if not cairo's defaults.
+    font_options: Option<cairo::FontOptions>,
     contents: Vec<Vec<ComputedText>>,
+    // The separators added with `add_separator()`, keyed by their index in
+    // `contents`. Each is only shown while it's between non-empty widgets.
+    separators: HashMap<usize, Text>,
+    // When animations started, so that all animated texts blink in time.
+    animation_start: Instant,
 }
 
-impl Bar {
-    pub fn new(position: Position, width: Option<u16>, offset: Offset) -> Result<Bar> {
+// A connection to the X server, and the (unmapped) window for the bar.
+struct BarWindow {
+    conn: Rc<ewmh::Connection>,
+    screen_idx: usize,
+    window_id: u32,
+    surface: cairo::XCBSurface,
+    x: i16,
+    width: u16,
+}
+
+impl BarWindow {
+    fn open(width: Option<u16>, offset: Offset, margins: Margins) -> Result<BarWindow> {
         let (conn, screen_idx) =
             xcb::Connection::connect(None).context("Failed to connect to X server")?;
         let screen_idx = screen_idx as usize;
@@ -145,24 +300,64 @@ impl Bar {
         // our window once we know how big it needs to be. However, it seems to need
         // to be bigger than 0px, or either Xcb/Cairo (or maybe QTile?) gets upset.
         let height = 1;
-        let (width, surface) = create_surface(&conn, screen_idx, window_id, height, width, offset)?;
+        let (x, width, surface) =
+            create_surface(&conn, screen_idx, window_id, height, width, offset, margins)?;
 
         let ewmh_conn = ewmh::Connection::connect(conn)
             .map_err(|(e, _)| e)
             .context("Failed to wrap xcb::Connection in ewmh::Connection")?;
 
-        let bar = Bar {
+        Ok(BarWindow {
             conn: Rc::new(ewmh_conn),
-            window_id,
             screen_idx,
+            window_id,
             surface,
+            x,
             width,
-            height,
+        })
+    }
+}
+
+impl Bar {
+    pub fn new(
+        position: Position,
+        width: Option<u16>,
+        offset: Offset,
+        margins: Margins,
+        border: Option<Border>,
+        order: Order,
+        fixed_height: Option<u16>,
+    ) -> Result<Bar> {
+        let window = BarWindow::open(width, offset, margins)?;
+
+        let bar = Bar {
+            conn: window.conn,
+            window_id: window.window_id,
+            screen_idx: window.screen_idx,
+            surface: window.surface,
+            x: window.x,
+            width: window.width,
+            height: 1,
+            requested_width: width,
+            margins,
             offset,
+            border,
+            order,
+            fixed_height,
+            stack_mode: StackMode::default(),
+            input_passthrough: false,
+            override_redirect: false,
+            instance_lock: None,
             position,
+            checked_fonts: HashSet::new(),
+            layout_cache: LayoutCache::default(),
+            font_options: None,
             contents: Vec::new(),
+            separators: HashMap::new(),
+            animation_start: Instant::now(),
         };
         bar.set_ewmh_properties();
+        bar.watch_window_manager()?;
 
         // XXX We can't map the window until we've updated the window size, or nothing
         // gets rendered. I can't tell if this is something we're doing, something Cairo
@@ -182,6 +377,41 @@ impl Bar {
         xcb::map_window(&self.conn, self.window_id);
     }
 
+    // Connects to the X server again and recreates the bar's window, with the
+    // same settings and content, e.g. after the X server was restarted.
+    pub fn reconnect(&mut self) -> Result<()> {
+        let window = BarWindow::open(self.requested_width, self.offset, self.margins)?;
+        self.conn = window.conn;
+        self.screen_idx = window.screen_idx;
+        self.window_id = window.window_id;
+        self.surface = window.surface;
+        self.x = window.x;
+        self.width = window.width;
+        // The new window isn't mapped until `resize()` sees that the height
+        // has changed.
+        self.height = 0;
+
+        self.set_ewmh_properties();
+        self.watch_window_manager()?;
+        if self.override_redirect {
+            self.set_override_redirect();
+        }
+        if self.input_passthrough {
+            self.set_input_passthrough()?;
+        }
+        if let Some(name) = self.instance_lock.clone() {
+            self.acquire_instance_lock(&name)?;
+        }
+        self.redraw_entire_bar()
+    }
+
+    // Unmaps and destroys the bar's window, once the bar is shutting down.
+    pub fn destroy_window(&self) {
+        xcb::unmap_window(&self.conn, self.window_id);
+        xcb::destroy_window(&self.conn, self.window_id);
+        self.flush();
+    }
+
     fn set_ewmh_properties(&self) {
         ewmh::set_wm_window_type(
             &self.conn,
@@ -189,6 +419,13 @@ impl Bar {
             &[self.conn.WM_WINDOW_TYPE_DOCK()],
         );
 
+        // The window has no size yet (e.g. just after `reconnect()`), so there
+        // is nothing to reserve. `configure_window()` calls us again once it
+        // has been resized.
+        if self.height == 0 || self.width == 0 {
+            return;
+        }
+
         // TODO: Update _WM_STRUT_PARTIAL if the height/position of the bar changes?
         let mut strut_partial = ewmh::StrutPartial {
             left: 0,
@@ -204,13 +441,159 @@ impl Bar {
             bottom_start_x: 0,
             bottom_end_x: 0,
         };
+        // Only reserve the span of the screen that the bar actually occupies.
+        let start_x = self.x.max(0) as u32;
+        let start_y = self.offset.y.max(0) as u32;
         match self.position {
-            Position::Top => strut_partial.top = u32::from(self.height),
-            Position::Bottom => strut_partial.bottom = u32::from(self.height),
+            Position::Top => {
+                strut_partial.top = u32::from(self.height);
+                strut_partial.top_start_x = start_x;
+                strut_partial.top_end_x = (start_x + u32::from(self.width)).saturating_sub(1);
+            }
+            Position::Bottom => {
+                strut_partial.bottom = u32::from(self.height);
+                strut_partial.bottom_start_x = start_x;
+                strut_partial.bottom_end_x = (start_x + u32::from(self.width)).saturating_sub(1);
+            }
+            Position::Left => {
+                strut_partial.left = u32::from(self.width);
+                strut_partial.left_start_y = start_y;
+                strut_partial.left_end_y = (start_y + u32::from(self.height)).saturating_sub(1);
+            }
+            Position::Right => {
+                strut_partial.right = u32::from(self.width);
+                strut_partial.right_start_y = start_y;
+                strut_partial.right_end_y = (start_y + u32::from(self.height)).saturating_sub(1);
+            }
         }
+        // Some WMs only understand the older _NET_WM_STRUT, which reserves
+        // space along the whole edge of the screen.
+        ewmh::set_wm_strut(
+            &self.conn,
+            self.window_id,
+            strut_partial.left,
+            strut_partial.right,
+            strut_partial.top,
+            strut_partial.bottom,
+        );
         ewmh::set_wm_strut_partial(&self.conn, self.window_id, strut_partial);
     }
 
+    // Listens for changes to the root window's properties, so that we notice
+    // when the window manager is restarted or replaced.
+    fn watch_window_manager(&self) -> Result<()> {
+        let root = self.screen()?.root();
+        let attributes = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE)];
+        xcb::change_window_attributes(&self.conn, root, &attributes);
+        Ok(())
+    }
+
+    // Re-applies our window type, struts and stacking order. A newly started
+    // window manager doesn't always pick these up from an existing window,
+    // which leaves the bar covered by other windows.
+    fn reassert_window_state(&self) {
+        self.set_ewmh_properties();
+        let values = [(
+            xcb::CONFIG_WINDOW_STACK_MODE as u16,
+            self.stack_mode.to_xcb(),
+        )];
+        xcb::configure_window(&self.conn, self.window_id, &values);
+        self.flush();
+    }
+
+    // Stops the window manager from managing the bar's window at all. This
+    // only takes effect if it is called before the window is first mapped.
+    pub fn set_override_redirect(&mut self) {
+        self.override_redirect = true;
+        let attributes = [(xcb::CW_OVERRIDE_REDIRECT, 1)];
+        xcb::change_window_attributes(&self.conn, self.window_id, &attributes);
+        self.flush();
+    }
+
+    // Sets where the bar is stacked relative to other windows. This is
+    // applied whenever the bar is resized or moved.
+    pub fn set_stack_mode(&mut self, stack_mode: StackMode) {
+        self.stack_mode = stack_mode;
+    }
+
+    // Gives the bar an empty input region, so that pointer events pass
+    // through it to whatever is beneath.
+    pub fn set_input_passthrough(&mut self) -> Result<()> {
+        let present = self
+            .conn
+            .get_extension_data(xcb::shape::id())
+            .map_or(false, |data| data.present());
+        if !present {
+            return Err(anyhow!("The X SHAPE extension is not available"));
+        }
+
+        xcb::shape::rectangles(
+            &self.conn,
+            xcb::shape::SO_SET as xcb::shape::Op,
+            xcb::shape::SK_INPUT as xcb::shape::Kind,
+            xcb::CLIP_ORDERING_UNSORTED as u8,
+            self.window_id,
+            0,
+            0,
+            &[],
+        );
+        self.flush();
+        self.input_passthrough = true;
+        Ok(())
+    }
+
+    // Sets how text is antialiased and hinted. This should be called before
+    // any content is added, as existing content isn't redrawn.
+    pub fn set_font_options(
+        &mut self,
+        antialias: cairo::Antialias,
+        hint_style: cairo::HintStyle,
+    ) -> Result<()> {
+        let mut font_options = cairo::FontOptions::new()?;
+        font_options.set_antialias(antialias);
+        font_options.set_hint_style(hint_style);
+        self.font_options = Some(font_options);
+        // Texts need to be laid out again, as hinting can change their size.
+        self.layout_cache = LayoutCache::default();
+        Ok(())
+    }
+
+    // Takes ownership of an X selection named after `name`, returning an
+    // error if another bar already owns it. The selection is released when
+    // our window is destroyed.
+    pub fn acquire_instance_lock(&mut self, name: &str) -> Result<()> {
+        let selection_name = format!("_CNX_{name}_S{}", self.screen_idx);
+        let selection = xcb::intern_atom(&self.conn, false, &selection_name)
+            .get_reply()
+            .context("Failed to intern selection atom")?
+            .atom();
+
+        let owner = xcb::get_selection_owner(&self.conn, selection)
+            .get_reply()
+            .context("Failed to get selection owner")?
+            .owner();
+        if owner != xcb::NONE {
+            return Err(anyhow!(
+                "Another instance of cnx named '{name}' is already running"
+            ));
+        }
+
+        xcb::set_selection_owner(&self.conn, self.window_id, selection, xcb::CURRENT_TIME);
+        // Another instance may have taken the selection at the same time, so
+        // check that we won.
+        let owner = xcb::get_selection_owner(&self.conn, selection)
+            .get_reply()
+            .context("Failed to get selection owner")?
+            .owner();
+        if owner != self.window_id {
+            return Err(anyhow!(
+                "Another instance of cnx named '{name}' is already running"
+            ));
+        }
+        self.instance_lock = Some(name.to_owned());
+        Ok(())
+    }
+
     fn screen(&self) -> Result<xcb::Screen<'_>> {
         let screen = self
             .conn
@@ -221,39 +604,90 @@ impl Bar {
         Ok(screen)
     }
 
-    fn update_bar_height(&mut self, height: u16) -> Result<()> {
-        if self.height != height {
+    fn resize(&mut self, width: u16, height: u16) -> Result<()> {
+        if self.width != width || self.height != height {
+            self.width = width;
             self.height = height;
 
-            // If we're at the bottom of the screen, we'll need to update the
-            // position of the window.
-            let y = match self.position {
-                Position::Top => self.offset.y.max(0) as u16,
-                Position::Bottom => {
-                    let h = (self.screen()?.height_in_pixels() - self.height) as i32;
-                    h.checked_add(self.offset.y as i32).unwrap_or(h).max(0) as u16
-                }
-            };
-
-            // Update the height/position of the XCB window and the height of the Cairo surface.
-            let values = [
-                (xcb::CONFIG_WINDOW_Y as u16, u32::from(y)),
-                (xcb::CONFIG_WINDOW_HEIGHT as u16, u32::from(self.height)),
-                (xcb::CONFIG_WINDOW_STACK_MODE as u16, xcb::STACK_MODE_ABOVE),
-            ];
-            xcb::configure_window(&self.conn, self.window_id, &values);
+            // Update the size/position of the XCB window and the size of the
+            // Cairo surface.
+            self.configure_window()?;
             self.map_window();
             self.surface
                 .set_size(i32::from(self.width), i32::from(self.height))
                 .unwrap();
-
-            // Update EWMH properties - we might need to reserve more or less space.
-            self.set_ewmh_properties();
         }
 
         Ok(())
     }
 
+    // Moves and resizes the window to match the bar's position and size, and
+    // reserves the space it occupies.
+    fn configure_window(&mut self) -> Result<()> {
+        let (x, y) = self.window_position()?;
+        self.x = x;
+
+        let values = [
+            (xcb::CONFIG_WINDOW_X as u16, i32::from(x) as u32),
+            (xcb::CONFIG_WINDOW_Y as u16, u32::from(y)),
+            (xcb::CONFIG_WINDOW_WIDTH as u16, u32::from(self.width)),
+            (xcb::CONFIG_WINDOW_HEIGHT as u16, u32::from(self.height)),
+            (
+                xcb::CONFIG_WINDOW_STACK_MODE as u16,
+                self.stack_mode.to_xcb(),
+            ),
+        ];
+        xcb::configure_window(&self.conn, self.window_id, &values);
+
+        // Update EWMH properties - we might need to reserve more or less space.
+        self.set_ewmh_properties();
+        Ok(())
+    }
+
+    // Returns the part of the screen that the bar was given.
+    fn span(&self) -> Result<(i16, u16)> {
+        let screen = self.screen()?;
+        Ok(horizontal_span(
+            &screen,
+            self.requested_width,
+            self.offset,
+            self.margins,
+        ))
+    }
+
+    // Returns the position of the window for the bar's position and size.
+    fn window_position(&self) -> Result<(i16, u16)> {
+        let x = match self.position {
+            Position::Right => {
+                let (x, width) = self.span()?;
+                let right = i32::from(x) + i32::from(width);
+                (right - i32::from(self.width)).clamp(0, i32::from(i16::MAX)) as i16
+            }
+            _ => self.span()?.0,
+        };
+        let y = match self.position {
+            Position::Bottom => {
+                let h = (self.screen()?.height_in_pixels() - self.height) as i32;
+                h.checked_add(self.offset.y as i32).unwrap_or(h).max(0) as u16
+            }
+            _ => self.offset.y.max(0) as u16,
+        };
+        Ok((x, y))
+    }
+
+    // Moves the bar to another edge of the screen, laying it out again in
+    // case it changed between horizontal and vertical.
+    pub fn set_position(&mut self, position: Position) -> Result<()> {
+        self.position = position;
+
+        // Lay the bar out for the new edge first, so that the space we
+        // reserve there is for its new size rather than the old. Then move
+        // it, even if its size didn't change.
+        self.recompute_dimensions()?;
+        self.configure_window()?;
+        self.render_entire_bar()
+    }
+
     // Returns the connection to the X server.
     //
     // The owner of the `Bar` is responsible for polling this for events,
@@ -262,16 +696,79 @@ impl Bar {
         &self.conn
     }
 
+    // Returns the index of the screen the bar is shown on.
+    pub fn screen_idx(&self) -> usize {
+        self.screen_idx
+    }
+
     // Process an X event received from the `Bar::connection()`.
     pub fn process_event(&mut self, event: xcb::GenericEvent) -> Result<()> {
-        let expose = event.response_type() & !0x80 == xcb::EXPOSE;
-        if expose {
-            println!("Redrawing entire bar - expose event.");
-            self.redraw_entire_bar()?;
+        match event.response_type() & !0x80 {
+            xcb::EXPOSE => {
+                trace!("Redrawing entire bar - expose event");
+                self.redraw_entire_bar()?;
+            }
+            xcb::VISIBILITY_NOTIFY => {
+                // We don't raise the bar here, as it may legitimately be
+                // covered (e.g. by a menu). Re-setting our properties prompts
+                // the window manager to re-apply its rules for docks instead.
+                let event: &xcb::VisibilityNotifyEvent = unsafe { xcb::cast_event(&event) };
+                if event.state() != xcb::VISIBILITY_UNOBSCURED as u8 {
+                    self.set_ewmh_properties();
+                    self.flush();
+                }
+            }
+            xcb::PROPERTY_NOTIFY => {
+                // A (new) window manager sets `_NET_SUPPORTING_WM_CHECK` on
+                // the root window when it starts.
+                let event: &xcb::PropertyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                if event.window() == self.screen()?.root()
+                    && event.atom() == self.conn.SUPPORTING_WM_CHECK()
+                {
+                    debug!("Window manager changed - re-asserting bar properties");
+                    self.reassert_window_state();
+                }
+            }
+            _ => (),
         }
         Ok(())
     }
 
+    // If `event` is a mouse button being pressed over some content, returns
+    // the index of that content, the button, and the click handler of the
+    // text it was pressed over (if any).
+    pub fn button_press(
+        &self,
+        event: &xcb::GenericEvent,
+    ) -> Option<(usize, MouseButton, Option<OnClick>)> {
+        if event.response_type() & !0x80 != xcb::BUTTON_PRESS {
+            return None;
+        }
+        let event: &xcb::ButtonPressEvent = unsafe { xcb::cast_event(event) };
+        let button = match event.detail() {
+            1 => MouseButton::Left,
+            2 => MouseButton::Middle,
+            3 => MouseButton::Right,
+            4 => MouseButton::ScrollUp,
+            5 => MouseButton::ScrollDown,
+            _ => return None,
+        };
+
+        let (x, y) = (f64::from(event.event_x()), f64::from(event.event_y()));
+        let vertical = self.position.is_vertical();
+        let contains = |text: &ComputedText| {
+            if vertical {
+                y >= text.y && y < text.y + text.height
+            } else {
+                x >= text.x && x < text.x + text.width
+            }
+        };
+        self.contents.iter().enumerate().find_map(|(idx, content)| {
+            let text = content.iter().find(|&text| contains(text))?;
+            Some((idx, button, text.on_click.clone()))
+        })
+    }
+
     // Add a new widget's content to the `Bar`.
     //
     // Returns the index of the widget within the bar, so that subsequent
@@ -283,123 +780,634 @@ impl Bar {
         Ok(idx)
     }
 
+    // Add a separator between widgets to the `Bar`.
+    //
+    // The separator is hidden unless there is non-empty content on both
+    // sides of it, so that widgets with nothing to show don't leave
+    // separators next to each other or at the edge of the bar.
+    pub fn add_separator(&mut self, separator: Text) -> Result<usize> {
+        let idx = self.add_content(Vec::new())?;
+        self.separators.insert(idx, separator);
+        Ok(idx)
+    }
+
     // Updates an existing widget's content in the `Bar`.
     pub fn update_content(&mut self, idx: usize, content: Vec<Text>) -> Result<()> {
+        self.set_content(idx, content)?;
+        self.update_separators()
+    }
+
+    // Shows or hides separators after a widget's content changed.
+    fn update_separators(&mut self) -> Result<()> {
+        if self.separators.is_empty() {
+            return Ok(());
+        }
+
+        let is_separator: Vec<bool> = (0..self.contents.len())
+            .map(|idx| self.separators.contains_key(&idx))
+            .collect();
+        let shown = shown_separators(&self.contents, &is_separator);
+        for (idx, separator) in self.separators.clone() {
+            let content = if shown[idx] {
+                vec![separator]
+            } else {
+                Vec::new()
+            };
+            self.set_content(idx, content)?;
+        }
+        Ok(())
+    }
+
+    fn set_content(&mut self, idx: usize, content: Vec<Text>) -> Result<()> {
         // If the text is the same, don't bother re-computing the text or
         // redrawing it. This is a spurious wake-up.
-        let old = &self.contents[idx];
-        if &content == old {
+        if content == self.contents[idx] {
             return Ok(());
         }
 
-        let mut new = content
-            .into_iter()
-            .map(|text| text.compute(&self.surface))
-            .collect::<Result<Vec<_>>>()?;
+        self.check_fonts(&content);
+        let old = &self.contents[idx];
+        let mut new = Vec::with_capacity(content.len());
+        for (i, text) in content.into_iter().enumerate() {
+            let computed = match old.get(i) {
+                // Texts which haven't changed don't need to be recomputed.
+                Some(old) if text == *old => old.clone(),
+                _ => text.compute(
+                    &self.surface,
+                    &mut self.layout_cache,
+                    self.font_options.as_ref(),
+                )?,
+            };
+            new.push(computed);
+        }
 
         let error_margin = f64::EPSILON; // Use an epsilon for comparison
 
         // If there are any new texts or any non-stretch texts changed size,
-        // we'll redraw all texts.
+        // we'll redraw all texts. On a vertical bar, a change of width can
+        // change the width of the whole bar.
+        let vertical = self.position.is_vertical();
         let redraw_entire_bar = old.len() != new.len()
-            || old
-                .iter()
-                .zip(&new)
-                .any(|(old, new)| ((old.width - new.width).abs() < error_margin) && !new.stretch);
+            || old.iter().zip(&new).any(|(old, new)| {
+                let resized = (old.width - new.width).abs() >= error_margin
+                    || (vertical && (old.height - new.height).abs() >= error_margin);
+                resized && !new.stretch
+            });
 
         // Steal dimenions from old ComputedText. If we need new dimensions,
         // they'll be recomputed by redraw_entire_bar().
         for (new, old) in new.iter_mut().zip(old.iter()) {
             new.x = old.x;
             new.y = old.y;
-            new.height = old.height;
-            // Only use width for stretch widgets.
-            if new.stretch {
-                new.width = old.width;
+            if vertical {
+                // Only use width and height for stretch widgets.
+                if new.stretch {
+                    new.width = old.width;
+                    new.height = old.height;
+                }
+            } else {
+                new.height = old.height;
+                // Only use width for stretch widgets.
+                if new.stretch {
+                    new.width = old.width;
+                }
+            }
+        }
+
+        // Start any animated texts in the same state as the others.
+        let elapsed = self.animation_start.elapsed();
+        for text in &mut new {
+            if let Some(animation) = text.attr.animation {
+                text.hidden = !animation.is_visible(elapsed);
             }
         }
 
         self.contents[idx] = new;
 
         if !redraw_entire_bar {
-            println!("Redrawing one");
+            trace!("Redrawing widget {idx}");
             self.redraw_content(idx)?;
         } else {
-            println!("Redrawing entire bar - widget update");
+            trace!("Redrawing entire bar - widget update");
             self.redraw_entire_bar()?;
         }
 
         Ok(())
     }
 
-    fn redraw_content(&mut self, idx: usize) -> Result<()> {
-        for text in &mut self.contents[idx] {
-            text.render(&self.surface)?;
+    // Warns (once per font) about any font that Pango had to substitute, as
+    // otherwise a typo in a font name silently results in the wrong font.
+    // Widgets usually yield their first content as soon as the bar starts, so
+    // this is reported at startup.
+    fn check_fonts(&mut self, content: &[Text]) {
+        for text in content {
+            let font = &text.attr.font;
+            if !self.checked_fonts.insert(format!("{font:?}")) {
+                continue;
+            }
+            let resolved = create_pango_context(&self.surface).and_then(|ctx| font.resolve(&ctx));
+            if let Err(err) = resolved {
+                warn!("{err}");
+            }
+        }
+    }
+
+    // Returns when animated texts next need to be redrawn, or `None` if there
+    // are no animated texts.
+    pub fn next_animation_frame(&self) -> Option<Instant> {
+        let elapsed = self.animation_start.elapsed().as_nanos();
+        self.contents
+            .iter()
+            .flatten()
+            .filter_map(|text| text.attr.animation)
+            .map(|animation| {
+                let step = animation.step().as_nanos();
+                let next = (elapsed / step + 1) * step;
+                self.animation_start + Duration::from_nanos(next as u64)
+            })
+            .min()
+    }
+
+    // Moves animated texts on to their current state, redrawing any whose
+    // state changed.
+    pub fn animate(&mut self) -> Result<()> {
+        let elapsed = self.animation_start.elapsed();
+        let mut changed = Vec::new();
+        for (idx, content) in self.contents.iter_mut().enumerate() {
+            let mut content_changed = false;
+            for text in content.iter_mut() {
+                if let Some(animation) = text.attr.animation {
+                    let hidden = !animation.is_visible(elapsed);
+                    content_changed |= text.hidden != hidden;
+                    text.hidden = hidden;
+                }
+            }
+            if content_changed {
+                changed.push(idx);
+            }
         }
 
+        if changed.is_empty() {
+            return Ok(());
+        }
+        for idx in changed {
+            self.render_content(idx)?;
+        }
+        self.draw_border()?;
         self.flush();
+        Ok(())
+    }
 
+    fn render_content(&self, idx: usize) -> Result<()> {
+        for text in &self.contents[idx] {
+            // Fill the background across the bar, as it may be taller (or,
+            // if vertical, wider) than the text.
+            let area = if self.position.is_vertical() {
+                cairo::Rectangle::new(0.0, text.y, f64::from(self.width), text.height)
+            } else {
+                cairo::Rectangle::new(text.x, 0.0, text.width, f64::from(self.height))
+            };
+            text.render(&self.surface, &area, self.font_options.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn redraw_content(&mut self, idx: usize) -> Result<()> {
+        self.render_content(idx)?;
+        // The content's background covers the full height of the bar, so
+        // the border needs to be drawn over it again.
+        self.draw_border()?;
+        self.flush();
         Ok(())
     }
 
     pub fn redraw_entire_bar(&mut self) -> Result<()> {
         self.recompute_dimensions()?;
+        self.render_entire_bar()
+    }
 
+    fn render_entire_bar(&self) -> Result<()> {
+        // Not all of the bar is necessarily covered by content (e.g. if
+        // nothing stretches, or widgets have shrunk), so clear what was
+        // previously drawn.
+        clear_background(&self.surface, f64::from(self.width), f64::from(self.height))?;
         for idx in 0..self.contents.len() {
-            self.redraw_content(idx)?;
+            self.render_content(idx)?;
+        }
+        self.draw_border()?;
+        self.flush();
+        Ok(())
+    }
+
+    fn draw_border(&self) -> Result<()> {
+        let border = match &self.border {
+            Some(border) => border,
+            None => return Ok(()),
+        };
+
+        let thickness = f64::from(border.thickness);
+        let (width, height) = (f64::from(self.width), f64::from(self.height));
+        let context = cairo::Context::new(&self.surface)?;
+        border.color.apply_to_context(&context);
+        match (self.position.is_vertical(), border.edge) {
+            (false, Edge::Top) => context.rectangle(0.0, 0.0, width, thickness),
+            (false, Edge::Bottom) => context.rectangle(0.0, height - thickness, width, thickness),
+            (true, Edge::Top) => context.rectangle(0.0, 0.0, thickness, height),
+            (true, Edge::Bottom) => context.rectangle(width - thickness, 0.0, thickness, height),
         }
+        context.fill()?;
+        self.flush();
+
         Ok(())
     }
 
+    // The height (or width, if vertical) reserved for the border, which
+    // content mustn't draw over.
+    fn border_thickness(&self) -> u16 {
+        self.border.as_ref().map_or(0, |border| border.thickness)
+    }
+
     fn recompute_dimensions(&mut self) -> Result<()> {
+        if self.position.is_vertical() {
+            return self.recompute_vertical_dimensions();
+        }
+
         // Set the height to the max height of any content.
-        let height = self
-            .contents
-            .iter()
-            .flatten()
-            .map(|text| text.height)
-            .max_by_key(|height| OrderedFloat(*height))
-            .unwrap_or(0.0);
+        let height = content_height(&self.contents);
+        let border = self.border_thickness();
+        // X doesn't allow windows with a height of 0, so keep the bar at
+        // least 1px tall even if it has no content.
+        let bar_height = self.fixed_height.unwrap_or(height as u16 + border).max(1);
+        // Leave room for the border, shifting content down if the border
+        // is drawn along the top edge. Then center the content vertically in
+        // the remaining space, in case the bar has a fixed height.
+        let top = match &self.border {
+            Some(border) if border.edge == Edge::Top => f64::from(border.thickness),
+            _ => 0.0,
+        };
+        let available = f64::from(bar_height.saturating_sub(border));
+        let y = top + ((available - height) / 2.0).max(0.0);
         for text in self.contents.iter_mut().flatten() {
+            text.y = y;
             text.height = height;
         }
-        self.update_bar_height(height as u16)?;
+        let (_, width) = self.span()?;
+        self.resize(width, bar_height)?;
+
+        distribute_widths(&mut self.contents, f64::from(self.width), self.order);
+
+        Ok(())
+    }
+
+    fn recompute_vertical_dimensions(&mut self) -> Result<()> {
+        // Set the width to the max width of any content. Stretch texts are
+        // ellipsized to that width, rather than widening the bar.
+        let width = content_width(&self.contents);
+        let border = self.border_thickness();
+        // A fixed "height" is the thickness of the bar, which for a vertical
+        // bar is its width.
+        let bar_width = self.fixed_height.unwrap_or(width as u16 + border).max(1);
+        // Leave room for the border, shifting content right if the border
+        // is drawn along the left edge.
+        let left = match &self.border {
+            Some(border) if border.edge == Edge::Top => f64::from(border.thickness),
+            _ => 0.0,
+        };
+        let available = f64::from(bar_width.saturating_sub(border));
+        for text in self.contents.iter_mut().flatten() {
+            text.x = left;
+            if text.stretch {
+                text.width = available;
+            }
+        }
+        let screen_height = self.screen()?.height_in_pixels();
+        let bar_height = screen_height
+            .saturating_sub(self.offset.y.max(0) as u16)
+            .max(1);
+        self.resize(bar_width, bar_height)?;
+
+        distribute_heights(&mut self.contents, f64::from(self.height), self.order);
+
+        Ok(())
+    }
+}
 
-        // Sum the width of all non-stretch texts. Subtract from the screen
-        // width to get width remaining for stretch texts.
-        let used: f64 = self
-            .contents
+// Fills the whole surface with the bar's default background, which matches
+// the background pixel of its window.
+pub(crate) fn clear_background(surface: &cairo::Surface, width: f64, height: f64) -> Result<()> {
+    let context = cairo::Context::new(surface)?;
+    Color::black().apply_to_context(&context);
+    context.rectangle(0.0, 0.0, width, height);
+    context.fill()?;
+    Ok(())
+}
+
+// Returns whether each content is a separator which should be shown, given
+// which contents are separators. A separator is shown only if there is
+// non-empty content before it and after it. If several separators are
+// between the same two contents (because the widgets between them are
+// empty), only the first is shown.
+pub(crate) fn shown_separators(contents: &[Vec<ComputedText>], is_separator: &[bool]) -> Vec<bool> {
+    let mut shown = vec![false; contents.len()];
+    let mut after_content = false;
+    let mut pending = None;
+    for (idx, content) in contents.iter().enumerate() {
+        if is_separator[idx] {
+            if after_content && pending.is_none() {
+                pending = Some(idx);
+            }
+        } else if !is_empty(content) {
+            if let Some(separator) = pending.take() {
+                shown[separator] = true;
+            }
+            after_content = true;
+        }
+    }
+    shown
+}
+
+// Whether content takes up no space in the bar, e.g. because the widget has
+// nothing to show.
+fn is_empty(content: &[ComputedText]) -> bool {
+    content
+        .iter()
+        .all(|text| !text.stretch && text.width <= 0.0)
+}
+
+// Returns the height of the tallest content, or 0 if there is no content.
+pub(crate) fn content_height(contents: &[Vec<ComputedText>]) -> f64 {
+    contents
+        .iter()
+        .flatten()
+        .map(|text| text.height)
+        .max_by_key(|height| OrderedFloat(*height))
+        .unwrap_or(0.0)
+}
+
+// Returns the width of the widest non-stretch content, or 0 if there is none.
+pub(crate) fn content_width(contents: &[Vec<ComputedText>]) -> f64 {
+    contents
+        .iter()
+        .flatten()
+        .filter(|text| !text.stretch)
+        .map(|text| text.width)
+        .max_by_key(|width| OrderedFloat(*width))
+        .unwrap_or(0.0)
+}
+
+// Returns the position and size of a text along one axis of the bar.
+type Axis = for<'a> fn(&'a mut ComputedText) -> (&'a mut f64, &'a mut f64);
+
+// Sets the width of stretch texts and the x position of all texts.
+pub(crate) fn distribute_widths(contents: &mut [Vec<ComputedText>], width: f64, order: Order) {
+    distribute(contents, width, order, |text| {
+        (&mut text.x, &mut text.width)
+    });
+}
+
+// Sets the height of stretch texts and the y position of all texts, for a
+// vertical bar.
+pub(crate) fn distribute_heights(contents: &mut [Vec<ComputedText>], height: f64, order: Order) {
+    distribute(contents, height, order, |text| {
+        (&mut text.y, &mut text.height)
+    });
+}
+
+fn distribute(contents: &mut [Vec<ComputedText>], length: f64, order: Order, axis: Axis) {
+    // Sum the size of all non-stretch texts. Subtract from the length of the
+    // bar to get the space remaining for stretch texts.
+    let used: f64 = contents
+        .iter_mut()
+        .flatten()
+        .filter(|text| !text.stretch)
+        .map(|text| *axis(text).1)
+        .sum();
+    let remaining = (length - used).max(0.0);
+
+    // Distribute remaining space between stretch texts in proportion to
+    // their weights. If there aren't any, the remaining space is simply left
+    // empty.
+    let total_weight: f64 = contents
+        .iter()
+        .flatten()
+        .filter(|text| text.stretch)
+        .map(|text| text.stretch_weight)
+        .sum();
+    if total_weight > 0.0 {
+        let stretches = contents.iter_mut().flatten().filter(|text| text.stretch);
+        for text in stretches {
+            let weight = text.stretch_weight;
+            *axis(text).1 = remaining * weight / total_weight;
+        }
+    }
+
+    // Set positions based on computed sizes.
+    let place = |text: &mut ComputedText, position: &mut f64| {
+        let (start, size) = axis(text);
+        *start = *position;
+        *position += *size;
+    };
+    match order {
+        Order::Normal => {
+            let mut position = 0.0;
+            for text in contents.iter_mut().flatten() {
+                place(text, &mut position);
+            }
+        }
+        Order::Reversed => {
+            let mut end = length;
+            for content in contents.iter_mut() {
+                let content_length: f64 = content.iter_mut().map(|text| *axis(text).1).sum();
+                let mut position = end - content_length;
+                end = position;
+                for text in content {
+                    place(text, &mut position);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        content_height, content_width, distribute_heights, distribute_widths, shown_separators,
+        Order,
+    };
+    use crate::text::{Alignment, Attributes, ComputedText};
+
+    fn computed_text(width: f64, height: f64, stretch: bool) -> ComputedText {
+        ComputedText {
+            attr: Attributes {
+                font: "Sans 12".into(),
+                ..Default::default()
+            },
+            text: "text".to_owned(),
+            stretch,
+            stretch_weight: 1.0,
+            max_width: None,
+            image: None,
+            alignment: Alignment::Left,
+            rich_text: None,
+            width_chars: None,
+            on_click: None,
+            image_surface: None,
+            background_image: None,
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+            markup: false,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn only_non_stretch_content() {
+        let mut contents = vec![
+            vec![computed_text(10.0, 12.0, false)],
+            vec![
+                computed_text(20.0, 14.0, false),
+                computed_text(30.0, 12.0, false),
+            ],
+        ];
+
+        assert_eq!(content_height(&contents), 14.0);
+
+        distribute_widths(&mut contents, 100.0, Order::Normal);
+        let positions: Vec<_> = contents
             .iter()
             .flatten()
-            .filter(|text| !text.stretch)
-            .map(|text| text.width)
-            .sum();
-        let remaining = f64::from(self.width) - used;
-
-        // Distribute remaining width evenly between stretch texts.
-        let stretches_count = self
-            .contents
+            .map(|text| (text.x, text.width))
+            .collect();
+        assert_eq!(positions, vec![(0.0, 10.0), (10.0, 20.0), (30.0, 30.0)]);
+    }
+
+    #[test]
+    fn no_content() {
+        let mut contents: Vec<Vec<ComputedText>> = vec![Vec::new(), Vec::new()];
+
+        assert_eq!(content_height(&contents), 0.0);
+        distribute_widths(&mut contents, 100.0, Order::Normal);
+    }
+
+    #[test]
+    fn stretch_content_fills_remaining_width() {
+        let mut contents = vec![vec![
+            computed_text(10.0, 12.0, false),
+            computed_text(0.0, 12.0, true),
+            computed_text(0.0, 12.0, true),
+        ]];
+
+        distribute_widths(&mut contents, 110.0, Order::Normal);
+        let widths: Vec<_> = contents.iter().flatten().map(|text| text.width).collect();
+        assert_eq!(widths, vec![10.0, 50.0, 50.0]);
+    }
+
+    #[test]
+    fn stretch_content_is_weighted() {
+        let mut contents = vec![vec![
+            computed_text(10.0, 12.0, false),
+            ComputedText {
+                stretch_weight: 2.0,
+                ..computed_text(0.0, 12.0, true)
+            },
+            computed_text(0.0, 12.0, true),
+        ]];
+
+        distribute_widths(&mut contents, 100.0, Order::Normal);
+        let widths: Vec<_> = contents.iter().flatten().map(|text| text.width).collect();
+        assert_eq!(widths, vec![10.0, 60.0, 30.0]);
+    }
+
+    #[test]
+    fn reversed_content_packs_from_right() {
+        let mut contents = vec![
+            vec![computed_text(10.0, 12.0, false)],
+            vec![
+                computed_text(20.0, 14.0, false),
+                computed_text(30.0, 12.0, false),
+            ],
+        ];
+
+        distribute_widths(&mut contents, 100.0, Order::Reversed);
+        let positions: Vec<_> = contents
             .iter()
             .flatten()
-            .filter(|text| text.stretch)
-            .count();
-        let stretch_width = remaining / (stretches_count as f64);
-        let stretches = self
-            .contents
-            .iter_mut()
+            .map(|text| (text.x, text.width))
+            .collect();
+        assert_eq!(positions, vec![(90.0, 10.0), (40.0, 20.0), (60.0, 30.0)]);
+    }
+
+    #[test]
+    fn vertical_content_is_stacked() {
+        let mut contents = vec![
+            vec![computed_text(10.0, 12.0, false)],
+            vec![
+                computed_text(200.0, 0.0, true),
+                computed_text(30.0, 14.0, false),
+            ],
+        ];
+
+        assert_eq!(content_width(&contents), 30.0);
+
+        distribute_heights(&mut contents, 100.0, Order::Normal);
+        let positions: Vec<_> = contents
+            .iter()
             .flatten()
-            .filter(|text| text.stretch);
-        for text in stretches {
-            text.width = stretch_width;
-        }
+            .map(|text| (text.y, text.height))
+            .collect();
+        assert_eq!(positions, vec![(0.0, 12.0), (12.0, 74.0), (86.0, 14.0)]);
+    }
 
-        // Set x based on computed widths.
-        let mut x = 0.0;
-        for text in self.contents.iter_mut().flatten() {
-            text.x = x;
-            x += text.width;
+    #[test]
+    fn separators_next_to_empty_content_are_hidden() {
+        let separator = || vec![computed_text(5.0, 12.0, false)];
+        let mut contents = vec![
+            vec![computed_text(10.0, 12.0, false)],
+            separator(),
+            // A widget showing a blank text, which takes up no space.
+            vec![computed_text(0.0, 0.0, false)],
+            separator(),
+            vec![computed_text(20.0, 12.0, false)],
+            separator(),
+            // A widget which hasn't shown anything yet.
+            Vec::new(),
+        ];
+        let is_separator = [false, true, false, true, false, true, false];
+
+        let shown = shown_separators(&contents, &is_separator);
+        assert_eq!(shown, vec![false, true, false, false, false, false, false]);
+
+        // The bar clears the content of separators which aren't shown.
+        for (idx, content) in contents.iter_mut().enumerate() {
+            if is_separator[idx] && !shown[idx] {
+                content.clear();
+            }
         }
+        distribute_widths(&mut contents, 100.0, Order::Normal);
+        let positions: Vec<_> = contents
+            .iter()
+            .flatten()
+            .map(|text| (text.x, text.width))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![(0.0, 10.0), (10.0, 5.0), (15.0, 0.0), (15.0, 20.0)]
+        );
+    }
 
-        Ok(())
+    #[test]
+    fn separators_at_edges_are_hidden() {
+        let contents = vec![
+            Vec::new(),
+            vec![computed_text(5.0, 12.0, false)],
+            vec![computed_text(10.0, 12.0, false)],
+            vec![computed_text(5.0, 12.0, false)],
+            Vec::new(),
+        ];
+        let is_separator = [false, true, false, true, false];
+
+        assert_eq!(shown_separators(&contents, &is_separator), vec![false; 5]);
     }
 }
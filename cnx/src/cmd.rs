@@ -0,0 +1,36 @@
+//! Helpers for widgets which show the output of external commands.
+
+use anyhow::{anyhow, Context, Result};
+use std::process::Command;
+use std::str::FromStr;
+
+/// Runs `command` with `args`, returning its standard output.
+///
+/// Returns an error if the command can't be run, or exits unsuccessfully.
+pub fn command_output(command: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(command)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {command}"))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{command} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).with_context(|| format!("Invalid output from {command}"))
+}
+
+/// Runs `command` with `args`, parsing its trimmed standard output as a `T`.
+pub fn from_command_output<T>(command: &str, args: &[&str]) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let output = command_output(command, args)?;
+    output
+        .trim()
+        .parse()
+        .with_context(|| format!("Failed to parse output of {command}: {}", output.trim()))
+}
@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Context, Result};
+use lazy_static::lazy_static;
+use log::warn;
+
+use crate::resources::{find_x_resource, x_resources};
+
+// The DPI that font sizes are designed for.
+pub(crate) const BASE_DPI: f64 = 96.0;
+
+lazy_static! {
+    static ref DPI: f64 = match query_dpi() {
+        Ok(Some(dpi)) => dpi,
+        Ok(None) => BASE_DPI,
+        Err(err) => {
+            warn!("Failed to find the screen's DPI: {err}");
+            BASE_DPI
+        }
+    };
+}
+
+// Returns the DPI of the X screen, which is looked up the first time this is
+// called. Falls back to 96 if the DPI isn't configured.
+pub(crate) fn dpi() -> f64 {
+    *DPI
+}
+
+// Reads the DPI from the XSETTINGS manager (e.g. a desktop environment's
+// settings daemon) or, failing that, the `Xft.dpi` X resource.
+fn query_dpi() -> Result<Option<f64>> {
+    let (conn, screen_idx) =
+        xcb::Connection::connect(None).context("Failed to connect to X server")?;
+    let root = conn
+        .get_setup()
+        .roots()
+        .nth(screen_idx as usize)
+        .ok_or_else(|| anyhow!("Invalid screen"))?
+        .root();
+
+    if let Some(dpi) = xsettings_dpi(&conn, screen_idx)? {
+        return Ok(Some(dpi));
+    }
+
+    Ok(parse_resource_dpi(&x_resources(&conn, root)?))
+}
+
+fn xsettings_dpi(conn: &xcb::Connection, screen_idx: i32) -> Result<Option<f64>> {
+    let intern = |name: &str| -> Result<xcb::Atom> {
+        let reply = xcb::intern_atom(conn, false, name)
+            .get_reply()
+            .with_context(|| format!("Failed to intern {name}"))?;
+        Ok(reply.atom())
+    };
+
+    let selection = intern(&format!("_XSETTINGS_S{screen_idx}"))?;
+    let owner = xcb::get_selection_owner(conn, selection)
+        .get_reply()
+        .context("Failed to find the XSETTINGS manager")?
+        .owner();
+    // There is no XSETTINGS manager running.
+    if owner == xcb::NONE {
+        return Ok(None);
+    }
+
+    let settings = intern("_XSETTINGS_SETTINGS")?;
+    let reply = xcb::get_property(conn, false, owner, settings, settings, 0, u32::MAX)
+        .get_reply()
+        .context("Failed to read XSETTINGS")?;
+    Ok(parse_xsettings_dpi(reply.value::<u8>()))
+}
+
+// Finds `Xft.dpi` in an X resource database string.
+fn parse_resource_dpi(resources: &str) -> Option<f64> {
+    find_x_resource(resources, "Xft.dpi")?
+        .parse()
+        .ok()
+        .filter(|&dpi: &f64| dpi > 0.0)
+}
+
+// Finds `Xft/DPI` in the contents of an `_XSETTINGS_SETTINGS` property. See
+// the XSETTINGS specification for the format:
+// https://specifications.freedesktop.org/xsettings-spec/xsettings-latest.html
+fn parse_xsettings_dpi(data: &[u8]) -> Option<f64> {
+    let big_endian = *data.first()? == 1;
+    let read_u16 = |offset: usize| -> Option<u16> {
+        let bytes = data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let read_u32 = |offset: usize| -> Option<u32> {
+        let bytes = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        })
+    };
+    // Names and strings are padded to a multiple of 4 bytes.
+    let padded = |len: usize| (len + 3) & !3;
+
+    let count = read_u32(8)?;
+    let mut offset = 12;
+    for _ in 0..count {
+        let kind = *data.get(offset)?;
+        let name_len = usize::from(read_u16(offset + 2)?);
+        let name = data.get(offset + 4..offset + 4 + name_len)?;
+        // Skip the header, name and last-change serial.
+        offset += 4 + padded(name_len) + 4;
+        let value_len = match kind {
+            // An integer.
+            0 => {
+                if name == b"Xft/DPI" {
+                    // The DPI is stored multiplied by 1024.
+                    let dpi = read_u32(offset)? as i32;
+                    return (dpi > 0).then_some(f64::from(dpi) / 1024.0);
+                }
+                4
+            }
+            // A string.
+            1 => 4 + padded(read_u32(offset)? as usize),
+            // A color.
+            2 => 8,
+            _ => return None,
+        };
+        offset += value_len;
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_resource_dpi, parse_xsettings_dpi};
+
+    #[test]
+    fn resource_dpi() {
+        let resources = "*.foreground: #ffffff\nXft.dpi:\t144\nXft.antialias: 1\n";
+        assert_eq!(parse_resource_dpi(resources), Some(144.0));
+        assert_eq!(parse_resource_dpi("*.foreground: #ffffff\n"), None);
+    }
+
+    #[test]
+    fn xsettings_dpi() {
+        let mut data = vec![0, 0, 0, 0];
+        data.extend(1u32.to_le_bytes()); // serial
+        data.extend(2u32.to_le_bytes()); // settings
+
+        // Net/ThemeName = "Adwaita"
+        data.extend([1, 0]);
+        data.extend(13u16.to_le_bytes());
+        data.extend(b"Net/ThemeName\0\0\0");
+        data.extend(0u32.to_le_bytes());
+        data.extend(7u32.to_le_bytes());
+        data.extend(b"Adwaita\0");
+
+        // Xft/DPI = 144 * 1024
+        data.extend([0, 0]);
+        data.extend(7u16.to_le_bytes());
+        data.extend(b"Xft/DPI\0");
+        data.extend(0u32.to_le_bytes());
+        data.extend((144 * 1024u32).to_le_bytes());
+
+        assert_eq!(parse_xsettings_dpi(&data), Some(144.0));
+        assert_eq!(parse_xsettings_dpi(&data[..20]), None);
+    }
+}
@@ -26,15 +26,14 @@
 //!
 //! fn main() -> Result<()> {
 //!     let attr = Attributes {
-//!         font: Font::new("Envy Code R 21"),
-//!         fg_color: Color::white(),
-//!         bg_color: None,
-//!         padding: Padding::new(8.0, 8.0, 0.0, 0.0),
+//!         font: "Envy Code R 21".into(),
+//!         padding: Padding::horizontal(8.0),
+//!         ..Default::default()
 //!     };
 //!
 //!     let mut cnx = Cnx::new(Position::Top);
 //!     cnx.add_widget(ActiveWindowTitle::new(attr.clone()));
-//!     cnx.add_widget(Clock::new(attr.clone(), None));
+//!     cnx.add_widget(Clock::new(attr.clone(), None, Precision::Minutes));
 //!     cnx.run()?;
 //!
 //!     Ok(())
@@ -50,6 +49,12 @@
 //! Before running Cnx, you'll need to make sure your system has the required
 //! dependencies, which are described in the [`README`][readme-deps].
 //!
+//! # Logging
+//!
+//! Cnx reports errors (e.g. from widgets) and other diagnostics through the
+//! [`log`] crate, rather than printing them. To see them, initialize a logger
+//! such as [`env_logger`] at the start of `main()`.
+//!
 //! # Built-in widgets
 //!
 //! There are currently these widgets available:
@@ -60,6 +65,12 @@
 //!   currently active. (Uses [`EWMH`]'s `_NET_DESKTOP_NAMES`,
 //!   `_NET_NUMBER_OF_DESKTOPS` and `_NET_CURRENT_DESKTOP`).
 //! - [`crate::widgets::Clock`] — Shows the time.
+//! - [`crate::widgets::Idle`] — Shows how long the user has been idle. (Uses
+//!   the X `MIT-SCREEN-SAVER` extension).
+//! - [`crate::widgets::LockKeys`] — Shows whether Caps Lock and Num Lock are
+//!   active. (Uses the X `XKEYBOARD` extension).
+//! - [`crate::widgets::Combine`] — Shows several widgets together as a single
+//!   block, with one background.
 //!
 //! The cnx-contrib crate contains additional widgets:
 //!
@@ -73,6 +84,25 @@
 //! - **Weather** - Shows the Weather information of your location
 //! - **Disk Usage** - Show the current usage of your monted filesystem
 //! - **LeftWM** - Shows the monitors and tags from LeftWM
+//! - **Thermal Zone** - Shows temperatures read directly from `/sys/class/thermal`
+//! - **Fan Speed** - Shows fan speeds read directly from `/sys/class/hwmon`
+//! - **GPU** - Shows the utilization and temperature of an AMD or NVIDIA GPU
+//! - **Notifications** - Shows the number of open desktop notifications
+//! - **Network Status** - Shows the active VPN, wifi or wired connection from NetworkManager
+//! - **Command Stream** - Shows each line of output from a long-running command
+//! - **Systemd Unit** - Shows whether systemd units are active or have failed
+//! - **Ping** - Shows the round-trip latency to a host
+//! - **Ticker** - Shows the latest price of a stock or currency from a JSON endpoint
+//! - **Pressure** - Shows the pressure stall information for the CPU, memory or IO
+//! - **Sun Times** - Shows the time of the next sunrise or sunset
+//! - **Tasks** - Shows the number of Taskwarrior tasks matching a filter
+//! - **Maildir** - Shows the number of unread messages in one or more Maildirs
+//! - **Moon Phase** - Shows the current phase of the moon
+//! - **Docker** - Shows how many Docker containers are running
+//! - **Pomodoro** - Shows a Pomodoro timer, controlled through a channel
+//! - **Disk IO** - Shows how quickly a block device is being read from and written to
+//! - **Bluetooth** - Shows connected Bluetooth devices and their battery levels
+//! - **CPU frequency** - Shows the current CPU clock speed
 //!
 //! The Sensors, Volume and Battery widgets require platform
 //! support. They currently support Linux (see dependencies below) and OpenBSD.
@@ -85,6 +115,9 @@
 //!
 //!  - `xcb-util`: `xcb-ewmh` / `xcb-icccm` / `xcb-keysyms`
 //!  - `x11-xcb`
+//!  - `xcb-screensaver`
+//!  - `xcb-xkb`
+//!  - `xcb-shape`
 //!  - `pango`
 //!  - `cairo`
 //!  - `pangocairo`
@@ -107,6 +140,8 @@
 //! your work.
 //!
 //! [`tokio`]: https://tokio.rs/
+//! [`log`]: https://docs.rs/log
+//! [`env_logger`]: https://docs.rs/env_logger
 //! [`QTile`]: http://www.qtile.org/
 //! [`dwm`]: http://dwm.suckless.org/
 //! [readme-deps]: https://github.com/mjkillough/cnx/blob/master/README.md#dependencies
@@ -118,21 +153,89 @@
 #![recursion_limit = "256"]
 
 mod bar;
+pub mod cmd;
+mod dpi;
+mod resources;
+mod screens;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod text;
 pub mod widgets;
 mod xcb;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use log::{debug, error, info, warn};
+use std::collections::HashMap;
+use std::time::Duration;
 use tokio::runtime::Runtime;
+use tokio::signal;
+use tokio::sync::{broadcast, mpsc};
 use tokio::task;
-use tokio_stream::{StreamExt, StreamMap};
+use tokio::time;
+use tokio_stream::{self as stream, StreamExt, StreamMap};
 
-use crate::bar::Bar;
-use crate::widgets::Widget;
-use crate::xcb::XcbEventStream;
+use crate::bar::{Bar, Border, Margins};
+use crate::text::{Attributes, Color, Text};
+use crate::widgets::{debounce, Cleanup, FnWidget, InputHandler, Widget, WidgetStream};
+use crate::xcb::{dispatch_event, share_connection, XcbEventStream};
 
+pub use bar::Edge;
 pub use bar::Offset;
+pub use bar::Order;
 pub use bar::Position;
+pub use bar::StackMode;
+pub use cairo::{Antialias, HintStyle};
+pub use screens::{screens, MonitorInfo};
+
+/// Controls how errors returned by a widget's stream are shown on the bar.
+///
+/// Errors are always logged. The default, [`ErrorDisplay::Silent`], leaves
+/// the widget showing whatever it last displayed.
+///
+/// [`ErrorDisplay::Silent`]: #variant.Silent
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum ErrorDisplay {
+    /// The widget keeps showing its previous content.
+    #[default]
+    Silent,
+    /// The widget's content is replaced with a small error indicator (`!`)
+    /// drawn with `attr`, until the widget next yields new content.
+    Inline { attr: Attributes },
+}
+
+impl ErrorDisplay {
+    /// Returns the content to show in place of a widget that has errored, if
+    /// any.
+    fn error_content(&self) -> Option<Vec<Text>> {
+        match self {
+            ErrorDisplay::Silent => None,
+            ErrorDisplay::Inline { attr } => Some(vec![
+                Text::new(attr.clone(), "!".to_owned()).with_markup(false)
+            ]),
+        }
+    }
+}
+
+/// A message sent to a running [`Cnx`] instance, through the channel
+/// returned by [`Cnx::control_channel()`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ControlMsg {
+    /// Moves the bar to the given edge of the screen.
+    SetPosition(Position),
+}
+
+/// An error returned by a widget's stream, published through the channel
+/// returned by [`Cnx::error_channel()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WidgetError {
+    /// The name of the widget, as returned by [`Widget::name()`].
+    ///
+    /// [`Widget::name()`]: widgets/trait.Widget.html#tymethod.name
+    pub name: String,
+    /// The error, formatted as a string including its causes.
+    pub error: String,
+}
 
 /// The main object, used to instantiate an instance of Cnx.
 ///
@@ -153,6 +256,37 @@ pub struct Cnx {
     /// The (optional) width of the bar
     /// It can be used in order to run multiple bars in a multi-monitor setup
     width: Option<u16>,
+    /// The gaps left on either side of the bar
+    margins: Margins,
+    /// The (optional) separator inserted between adjacent widgets
+    separator: Option<Text>,
+    /// The (optional) border drawn along one edge of the bar
+    border: Option<Border>,
+    /// How errors from widgets are shown on the bar
+    error_display: ErrorDisplay,
+    /// The order in which widgets are laid out along the bar
+    order: Order,
+    /// The (optional) fixed height of the bar
+    height: Option<u16>,
+    /// Whether pointer events pass through the bar to the windows below it
+    input_passthrough: bool,
+    /// Whether the bar's window is hidden from the window manager
+    override_redirect: bool,
+    /// Where the bar is stacked relative to other windows
+    stack_mode: StackMode,
+    /// How text on the bar is antialiased
+    antialias: Antialias,
+    /// How text on the bar is hinted
+    hint_style: HintStyle,
+    /// The (optional) name used to stop more than one bar running at once
+    single_instance: Option<String>,
+    /// How many times to try to reconnect if the X connection is lost
+    reconnect_attempts: u32,
+    /// The channel through which the running bar is controlled
+    control_sender: mpsc::UnboundedSender<ControlMsg>,
+    control_receiver: mpsc::UnboundedReceiver<ControlMsg>,
+    /// The channel to which errors from widgets are published
+    error_sender: broadcast::Sender<WidgetError>,
 }
 
 impl Cnx {
@@ -164,11 +298,29 @@ impl Cnx {
     /// [`Position`]: enum.Position.html
     pub fn new(position: Position) -> Self {
         let widgets = Vec::new();
+        let (control_sender, control_receiver) = mpsc::unbounded_channel();
+        let (error_sender, _) = broadcast::channel(16);
         Self {
             position,
             widgets,
             offset: Offset::default(),
             width: None,
+            margins: Margins::default(),
+            separator: None,
+            border: None,
+            error_display: ErrorDisplay::default(),
+            order: Order::default(),
+            height: None,
+            input_passthrough: false,
+            override_redirect: false,
+            stack_mode: StackMode::default(),
+            antialias: Antialias::Default,
+            hint_style: HintStyle::Default,
+            single_instance: None,
+            reconnect_attempts: 0,
+            control_sender,
+            control_receiver,
+            error_sender,
         }
     }
 
@@ -183,6 +335,39 @@ impl Cnx {
         Self { width, ..self }
     }
 
+    /// Returns a new instance of `Cnx` with gaps on either side of the bar.
+    ///
+    /// The bar is shrunk by `left` and `right` pixels, leaving a gap between
+    /// it and the sides of the screen (or of the width given to
+    /// [`with_width()`]). Only the part of the screen that the bar occupies
+    /// is reserved from other windows.
+    ///
+    /// [`with_width()`]: #method.with_width
+    pub fn with_margins(self, left: u16, right: u16) -> Self {
+        Self {
+            margins: Margins { left, right },
+            ..self
+        }
+    }
+
+    /// Returns a new instance of `Cnx` with the specified height.
+    ///
+    /// By default, the bar is as tall as its tallest widget. With a fixed
+    /// height, widgets are vertically centered within the bar instead. The
+    /// height includes any border added with [`with_border()`].
+    ///
+    /// For a bar at the [`Position::Left`] or [`Position::Right`] of the
+    /// screen, which always fills the screen's height, this is used as the
+    /// bar's width instead, and widgets are laid out from its left edge.
+    ///
+    /// [`with_border()`]: #method.with_border
+    pub fn with_height(self, height: u16) -> Self {
+        Self {
+            height: Some(height),
+            ..self
+        }
+    }
+
     /// Returns a new instance of `Cnx` with the specified offset.
     ///
     /// This allows to specify the x and y offset of the `Cnx` bar,
@@ -197,6 +382,171 @@ impl Cnx {
         }
     }
 
+    /// Returns a new instance of `Cnx` which separates widgets with `text`.
+    ///
+    /// A copy of `text` is inserted between each pair of adjacent widgets,
+    /// but not before the first widget or after the last. Widgets with
+    /// nothing to show are skipped, so there's never more than one separator
+    /// in a row, or one at the edge of the bar. The separator is
+    /// drawn with its own [`Attributes`] and never stretches, regardless of
+    /// the value of [`Text::stretch`].
+    ///
+    /// [`Attributes`]: text/struct.Attributes.html
+    /// [`Text::stretch`]: text/struct.Text.html#structfield.stretch
+    pub fn with_separator(self, text: Text) -> Self {
+        let separator = text.with_stretch(false);
+        Self {
+            separator: Some(separator),
+            ..self
+        }
+    }
+
+    /// Returns a new instance of `Cnx` with a border along one edge.
+    ///
+    /// A solid line of the given `color` and `thickness` (in pixels) is
+    /// drawn along the given [`Edge`] of the bar, after the widgets have been
+    /// drawn. The bar is made `thickness` pixels taller, so that the border
+    /// doesn't overlap any widget's text.
+    ///
+    /// The border is usually drawn along the inner edge of the bar, i.e.
+    /// [`Edge::Bottom`] for a [`Position::Top`] bar, and [`Edge::Top`] for a
+    /// [`Position::Bottom`] bar.
+    ///
+    /// [`Edge`]: enum.Edge.html
+    /// [`Edge::Bottom`]: enum.Edge.html#variant.Bottom
+    /// [`Edge::Top`]: enum.Edge.html#variant.Top
+    /// [`Position::Top`]: enum.Position.html#variant.Top
+    /// [`Position::Bottom`]: enum.Position.html#variant.Bottom
+    pub fn with_border(self, color: Color, thickness: u16, edge: Edge) -> Self {
+        let border = Border {
+            color,
+            thickness,
+            edge,
+        };
+        Self {
+            border: Some(border),
+            ..self
+        }
+    }
+
+    /// Returns a new instance of `Cnx` which shows widget errors as specified.
+    ///
+    /// By default, errors from widgets are only logged and the widget keeps
+    /// showing its previous content. See [`ErrorDisplay`] for alternatives.
+    ///
+    /// [`ErrorDisplay`]: enum.ErrorDisplay.html
+    pub fn with_error_display(self, error_display: ErrorDisplay) -> Self {
+        Self {
+            error_display,
+            ..self
+        }
+    }
+
+    /// Returns a new instance of `Cnx` which lays out widgets in the given
+    /// [`Order`].
+    ///
+    /// With [`Order::Reversed`], the first widget added is drawn at the right
+    /// edge of the bar, with each subsequent widget to its left. This can be
+    /// used to mirror another bar without changing the order in which widgets
+    /// are added.
+    ///
+    /// [`Order`]: enum.Order.html
+    /// [`Order::Reversed`]: enum.Order.html#variant.Reversed
+    pub fn with_order(self, order: Order) -> Self {
+        Self { order, ..self }
+    }
+
+    /// Returns a new instance of `Cnx` whose bar lets pointer events through.
+    ///
+    /// When `input_passthrough` is `true`, the bar is purely decorative:
+    /// clicks and scrolling over it reach whatever is beneath it (usually the
+    /// desktop) instead. This uses the X `SHAPE` extension to give the bar's
+    /// window an empty input region.
+    ///
+    /// As the bar no longer receives any pointer events, widgets can't react
+    /// to clicks or scrolling while this is enabled.
+    pub fn with_input_passthrough(self, input_passthrough: bool) -> Self {
+        Self {
+            input_passthrough,
+            ..self
+        }
+    }
+
+    /// Returns a new instance of `Cnx` whose bar isn't managed by the window
+    /// manager.
+    ///
+    /// When `override_redirect` is `true`, the bar's window is created with
+    /// the X `override-redirect` attribute, so the window manager leaves it
+    /// alone: it isn't treated as a dock, and no space is reserved for it.
+    /// This can help with window managers that mishandle dock windows.
+    pub fn with_override_redirect(self, override_redirect: bool) -> Self {
+        Self {
+            override_redirect,
+            ..self
+        }
+    }
+
+    /// Returns a new instance of `Cnx` whose bar is stacked as specified.
+    ///
+    /// By default, the bar is kept above other windows. With
+    /// [`StackMode::Below`], it is kept below them instead, so that e.g.
+    /// fullscreen windows cover it.
+    ///
+    /// [`StackMode::Below`]: enum.StackMode.html#variant.Below
+    pub fn with_stack_mode(self, stack_mode: StackMode) -> Self {
+        Self { stack_mode, ..self }
+    }
+
+    /// Returns a new instance of `Cnx` which tries to reconnect to the X
+    /// server if its connection is lost, e.g. because the X server was
+    /// restarted.
+    ///
+    /// Up to `reconnect_attempts` attempts are made, waiting a little longer
+    /// before each one, after which [`run()`] returns an error. The bar's
+    /// window is recreated with its current content, and widgets which talk
+    /// to the X server (such as [`Pager`]) switch to the new connection. By
+    /// default, no attempts are made.
+    ///
+    /// [`run()`]: #method.run
+    /// [`Pager`]: widgets/struct.Pager.html
+    pub fn with_reconnect_attempts(self, reconnect_attempts: u32) -> Self {
+        Self {
+            reconnect_attempts,
+            ..self
+        }
+    }
+
+    /// Returns a new instance of `Cnx` which antialiases text as specified.
+    ///
+    /// By default, text is antialiased according to the system's font
+    /// configuration. On low-DPI screens, small fonts can look blurry:
+    /// [`Antialias::None`] draws sharper text, while
+    /// [`Antialias::Subpixel`] suits most LCD screens.
+    pub fn with_antialias(self, antialias: Antialias) -> Self {
+        Self { antialias, ..self }
+    }
+
+    /// Returns a new instance of `Cnx` which hints text as specified.
+    ///
+    /// Hinting fits the outlines of glyphs to the pixel grid. Stronger
+    /// hinting (e.g. [`HintStyle::Full`]) gives sharper text, at the cost of
+    /// changing the shapes of the glyphs.
+    pub fn with_hint_style(self, hint_style: HintStyle) -> Self {
+        Self { hint_style, ..self }
+    }
+
+    /// Returns the DPI of the X screen.
+    ///
+    /// This is read from the XSETTINGS manager (e.g. a desktop environment's
+    /// settings daemon) or, failing that, the `Xft.dpi` X resource. If
+    /// neither is set, this returns 96. It can be used to scale sizes other
+    /// than fonts, such as padding, in the same way as [`Font::scaled()`].
+    ///
+    /// [`Font::scaled()`]: text/struct.Font.html#method.scaled
+    pub fn dpi(&self) -> f64 {
+        dpi::dpi()
+    }
+
     /// Adds a widget to the `Cnx` instance.
     ///
     /// Takes ownership of the [`Widget`] and adds it to the Cnx instance to
@@ -210,58 +560,357 @@ impl Cnx {
         self.widgets.push(Box::new(widget));
     }
 
+    /// Adds a widget's stream to the `Cnx` instance.
+    ///
+    /// This is a shorthand for adding a one-off widget without implementing
+    /// [`Widget`], e.g. for a stream built inline from `tokio_stream`. Like
+    /// [`add_widget()`], the stream's content is shown to the right of any
+    /// existing widgets.
+    ///
+    /// Widgets that use the bar's X connection must be created once the bar
+    /// is running, so should use [`FnWidget`] instead.
+    ///
+    /// [`Widget`]: widgets/trait.Widget.html
+    /// [`add_widget()`]: #method.add_widget
+    /// [`FnWidget`]: widgets/struct.FnWidget.html
+    pub fn add_stream(&mut self, stream: WidgetStream) {
+        self.add_widget(FnWidget::new(move || Ok(stream)));
+    }
+
+    /// Returns a channel for controlling the bar while it is running.
+    ///
+    /// The returned sender can be cloned and moved elsewhere (e.g. into a
+    /// task which listens for a signal sent by a keybinding) before calling
+    /// [`run()`]. Each [`ControlMsg`] sent is applied by the running bar.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cnx::{Cnx, ControlMsg, Position};
+    /// let cnx = Cnx::new(Position::Top);
+    /// let control = cnx.control_channel();
+    /// // Later, once the bar is running:
+    /// control.send(ControlMsg::SetPosition(Position::Bottom)).unwrap();
+    /// ```
+    ///
+    /// [`run()`]: #method.run
+    pub fn control_channel(&self) -> mpsc::UnboundedSender<ControlMsg> {
+        self.control_sender.clone()
+    }
+
+    /// Returns a channel to which errors from widgets are published while the
+    /// bar is running.
+    ///
+    /// Each error returned by a widget's stream is logged (and shown on the
+    /// bar, depending on [`with_error_display()`]) and also sent as a
+    /// [`WidgetError`] to every receiver subscribed to the returned sender.
+    /// This lets an application embedding Cnx react to failing widgets, e.g.
+    /// by alerting someone. Errors are dropped if there are no receivers, and
+    /// a receiver which falls too far behind misses the oldest errors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cnx::{Cnx, Position};
+    /// let cnx = Cnx::new(Position::Top);
+    /// let mut errors = cnx.error_channel().subscribe();
+    /// // Later, e.g. in a task running alongside the bar:
+    /// // while let Ok(error) = errors.recv().await { ... }
+    /// ```
+    ///
+    /// [`with_error_display()`]: #method.with_error_display
+    pub fn error_channel(&self) -> broadcast::Sender<WidgetError> {
+        self.error_sender.clone()
+    }
+
+    /// Returns a new instance of `Cnx` which refuses to run alongside another
+    /// instance with the same `name`.
+    ///
+    /// If another bar with the same `name` is already running on the X
+    /// screen, [`run()`] returns an error rather than showing a second bar.
+    /// Bars with different names (e.g. one per monitor) are unaffected.
+    ///
+    /// [`run()`]: #method.run
+    pub fn with_single_instance<S: Into<String>>(self, name: S) -> Self {
+        Self {
+            single_instance: Some(name.into()),
+            ..self
+        }
+    }
+
     /// Runs the Cnx instance.
     ///
     /// This method takes ownership of the Cnx instance and runs it until either
-    /// the process is terminated, or an internal error is returned.
+    /// the process receives Ctrl-C (or `SIGTERM` on unix), or an internal
+    /// error is returned. On Ctrl-C or `SIGTERM`, the widgets' streams are
+    /// dropped, any hooks returned by [`Widget::cleanup()`] are called and the
+    /// bar's window is destroyed before returning `Ok(())`.
+    ///
+    /// This creates its own tokio [`Runtime`]. Use [`run_on()`] to run on an
+    /// existing runtime instead.
+    ///
+    /// [`Runtime`]: https://docs.rs/tokio/latest/tokio/runtime/struct.Runtime.html
+    /// [`run_on()`]: #method.run_on
     pub fn run(self) -> Result<()> {
-        // Use a single-threaded event loop. We aren't interested in
-        // performance too much, so don't mind if we block the loop
+        let rt = Runtime::new()?;
+        self.run_on(&rt)
+    }
+
+    /// Runs the Cnx instance on an existing tokio [`Runtime`].
+    ///
+    /// This behaves like [`run()`], but lets an application which already has
+    /// a runtime share it with Cnx, e.g. to run its own tasks alongside the
+    /// bar.
+    ///
+    /// Widgets aren't `Send`, so Cnx runs inside a [`LocalSet`] on the calling
+    /// thread, which this blocks until Cnx finishes. It must not be called
+    /// from within an asynchronous context, such as a task running on
+    /// `runtime`. Tasks spawned by the application on `runtime` continue to
+    /// run on its other threads (if any).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use cnx::{Cnx, Position};
+    /// # use tokio::runtime::Runtime;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let runtime = Runtime::new()?;
+    /// runtime.spawn(async {
+    ///     // Some other work, running alongside the bar.
+    /// });
+    /// Cnx::new(Position::Top).run_on(&runtime)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Runtime`]: https://docs.rs/tokio/latest/tokio/runtime/struct.Runtime.html
+    /// [`LocalSet`]: https://docs.rs/tokio/latest/tokio/task/struct.LocalSet.html
+    /// [`run()`]: #method.run
+    pub fn run_on(self, runtime: &Runtime) -> Result<()> {
+        // Widgets run on a single-threaded event loop. We aren't interested
+        // in performance too much, so don't mind if we block the loop
         // occasionally. We are using events to get woken up as
         // infrequently as possible (to save battery).
-        let rt = Runtime::new()?;
         let local = task::LocalSet::new();
-        local.block_on(&rt, self.run_inner())?;
+        local.block_on(runtime, self.run_inner())?;
         Ok(())
     }
 
     async fn run_inner(self) -> Result<()> {
-        let mut bar = Bar::new(self.position, self.width, self.offset)?;
+        let mut bar = Bar::new(
+            self.position,
+            self.width,
+            self.offset,
+            self.margins,
+            self.border,
+            self.order,
+            self.height,
+        )?;
+
+        if let Some(name) = &self.single_instance {
+            bar.acquire_instance_lock(name)?;
+        }
+
+        if self.input_passthrough {
+            bar.set_input_passthrough()?;
+        }
+
+        // The window hasn't been mapped yet, so the window manager will
+        // never see it.
+        if self.override_redirect {
+            bar.set_override_redirect();
+        }
+        bar.set_stack_mode(self.stack_mode);
+
+        bar.set_font_options(self.antialias, self.hint_style)?;
+
+        // Widgets that watch X properties share the bar's connection, rather
+        // than each opening their own.
+        share_connection(bar.connection().clone(), bar.screen_idx() as i32);
 
         let mut widgets = StreamMap::with_capacity(self.widgets.len());
-        for widget in self.widgets {
+        let mut input_handlers: HashMap<usize, InputHandler> = HashMap::new();
+        // The names of the widgets, so that errors can say which widget
+        // they came from.
+        let mut names: HashMap<usize, String> = HashMap::new();
+        let mut cleanups: HashMap<usize, Cleanup> = HashMap::new();
+        for (i, mut widget) in self.widgets.into_iter().enumerate() {
+            if let Some(separator) = &self.separator {
+                if i > 0 {
+                    bar.add_separator(separator.clone())?;
+                }
+            }
             let idx = bar.add_content(Vec::new())?;
-            widgets.insert(idx, widget.into_stream()?);
+            names.insert(idx, widget.name().to_owned());
+            if let Some(handler) = widget.input_handler() {
+                input_handlers.insert(idx, handler);
+            }
+            if let Some(cleanup) = widget.cleanup() {
+                cleanups.insert(idx, cleanup);
+            }
+            let min_interval = widget.min_interval();
+            let mut widget_stream = widget.into_stream()?;
+            if let Some(min_interval) = min_interval {
+                widget_stream = debounce(widget_stream, min_interval);
+            }
+            // Mark the end of each widget's stream, so that we notice when
+            // it finishes rather than it silently disappearing from the map.
+            let stream = widget_stream.map(Some).chain(stream::once(None));
+            widgets.insert(idx, stream);
         }
 
+        let error_display = self.error_display;
+        let reconnect_attempts = self.reconnect_attempts;
+        let error_sender = self.error_sender;
+        // Only the senders handed out by `control_channel()` keep the channel
+        // open, so drop our own.
+        drop(self.control_sender);
+        let mut control_receiver = self.control_receiver;
         let mut event_stream = XcbEventStream::new(bar.connection().clone())?;
         task::spawn_local(async move {
+            let shutdown = shutdown_signal();
+            tokio::pin!(shutdown);
             loop {
+                let next_frame = bar.next_animation_frame();
+                let animation = time::sleep_until(
+                    next_frame.map_or_else(time::Instant::now, time::Instant::from_std),
+                );
                 tokio::select! {
-                    // Pass each XCB event to the Bar.
+                    // Stop the bar when asked to.
+                    result = &mut shutdown => {
+                        result?;
+                        break;
+                    }
+
+                    // Pass each XCB event to the Bar, and any mouse buttons
+                    // pressed over a widget to its input handler.
                     Some(event) = event_stream.next() => {
+                        let event = match event {
+                            Ok(event) => event,
+                            Err(err) => {
+                                error!("{err}");
+                                reconnect(&mut bar, reconnect_attempts).await?;
+                                share_connection(bar.connection().clone(), bar.screen_idx() as i32);
+                                event_stream = XcbEventStream::new(bar.connection().clone())?;
+                                continue;
+                            }
+                        };
+                        dispatch_event(&event);
+                        if let Some((idx, button, on_click)) = bar.button_press(&event) {
+                            if let Some(on_click) = on_click {
+                                on_click.call(button);
+                            } else if let Some(handler) = input_handlers.get(&idx) {
+                                (handler)(button);
+                            }
+                        }
                         if let Err(err) = bar.process_event(event) {
-                            println!("Error processing XCB event: {err}");
+                            error!("Error processing XCB event: {err}");
                         }
                     },
 
+                    // Redraw any animated texts, e.g. to blink them.
+                    _ = animation, if next_frame.is_some() => {
+                        if let Err(err) = bar.animate() {
+                            error!("Error animating bar: {err}");
+                        }
+                    }
+
+                    // Apply any messages sent through `control_channel()`.
+                    Some(msg) = control_receiver.recv() => {
+                        let result = match msg {
+                            ControlMsg::SetPosition(position) => bar.set_position(position),
+                        };
+                        if let Err(err) = result {
+                            error!("Error handling control message: {err}");
+                        }
+                    }
+
                     // Each time a widget yields new values, pass to the bar.
-                    // Ignore (but log) any errors from widgets.
+                    // Log any errors from widgets, and show them on the bar
+                    // if configured to do so.
+                    //
+                    // Once a widget's stream has ended, its `Widget::cleanup()`
+                    // hook is called. Its content is cleared, rather than
+                    // leaving stale content on the bar forever.
                     Some((idx, result)) = widgets.next() => {
+                        let name = names.get(&idx).map_or("unnamed", String::as_str);
                         match result {
-                            Err(err) => println!("Error from widget {idx}: {err}"),
-                            Ok(texts) => {
+                            None => {
+                                debug!("Widget '{name}' stream ended");
+                                if let Some(cleanup) = cleanups.remove(&idx) {
+                                    cleanup();
+                                }
+                                if let Err(err) = bar.update_content(idx, Vec::new()) {
+                                    error!("Error clearing widget '{name}': {err}");
+                                }
+                            }
+                            Some(Err(err)) => {
+                                error!("Error from widget '{name}': {err}");
+                                // It's fine if nobody is listening.
+                                let _ = error_sender.send(WidgetError {
+                                    name: name.to_owned(),
+                                    error: format!("{err:#}"),
+                                });
+                                if let Some(texts) = error_display.error_content() {
+                                    if let Err(err) = bar.update_content(idx, texts) {
+                                        error!("Error updating widget '{name}': {err}");
+                                    }
+                                }
+                            }
+                            Some(Ok(texts)) => {
                                 if let Err(err) = bar.update_content(idx, texts) {
-                                    println!("Error updating widget {idx}: {err}");
+                                    error!("Error updating widget '{name}': {err}");
                                 }
                             }
                         }
                     }
                 }
             }
+
+            // Drop the widgets' streams and call their `Widget::cleanup()`
+            // hooks while the X connection is still open.
+            drop(widgets);
+            for cleanup in cleanups.into_values() {
+                cleanup();
+            }
+            bar.destroy_window();
+            Ok::<_, anyhow::Error>(())
         })
-        .await?;
+        .await??;
 
         Ok(())
     }
 }
+
+// Tries to reconnect the bar to the X server up to `attempts` times, waiting
+// longer before each attempt so that a restarting X server has time to start.
+async fn reconnect(bar: &mut Bar, attempts: u32) -> Result<()> {
+    let mut delay = Duration::from_secs(1);
+    for attempt in 1..=attempts {
+        time::sleep(delay).await;
+        match bar.reconnect() {
+            Ok(()) => {
+                info!("Reconnected to the X server");
+                return Ok(());
+            }
+            Err(err) => warn!("Failed to reconnect to the X server ({attempt}/{attempts}): {err}"),
+        }
+        delay = (delay * 2).min(Duration::from_secs(30));
+    }
+    Err(anyhow!("Lost connection to the X server"))
+}
+
+// Waits until the process is asked to stop, i.e. on Ctrl-C or `SIGTERM`.
+async fn shutdown_signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            result = signal::ctrl_c() => result?,
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    signal::ctrl_c().await?;
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+
+// Reads the X resource database, i.e. the `RESOURCE_MANAGER` property of the
+// root window, as loaded by `xrdb` from `~/.Xresources`.
+pub(crate) fn x_resources(conn: &xcb::Connection, root: xcb::Window) -> Result<String> {
+    let reply = xcb::get_property(
+        conn,
+        false,
+        root,
+        xcb::ATOM_RESOURCE_MANAGER,
+        xcb::ATOM_STRING,
+        0,
+        u32::MAX,
+    )
+    .get_reply()
+    .context("Failed to read the X resource database")?;
+    Ok(String::from_utf8_lossy(reply.value::<u8>()).into_owned())
+}
+
+// Finds the value of the `name` entry in the `key: value` lines of an X
+// resource database string. Wildcard entries such as `*name` or `*.name` are
+// matched too.
+pub(crate) fn find_x_resource<'a>(resources: &'a str, name: &str) -> Option<&'a str> {
+    resources.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        let key = key.trim();
+        let key = key
+            .strip_prefix("*.")
+            .or_else(|| key.strip_prefix('*'))
+            .unwrap_or(key);
+        if key == name {
+            Some(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::find_x_resource;
+
+    #[test]
+    fn finds_x_resources() {
+        let resources = "*.foreground: #ffffff\n*color4:\t#0000ff\nXft.dpi: 144\n";
+        assert_eq!(find_x_resource(resources, "foreground"), Some("#ffffff"));
+        assert_eq!(find_x_resource(resources, "color4"), Some("#0000ff"));
+        assert_eq!(find_x_resource(resources, "Xft.dpi"), Some("144"));
+        assert_eq!(find_x_resource(resources, "background"), None);
+    }
+}
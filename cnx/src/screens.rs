@@ -0,0 +1,97 @@
+use anyhow::{anyhow, Context, Result};
+use xcb::randr;
+
+/// Describes a monitor connected to the X server.
+///
+/// Returned by [`screens()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MonitorInfo {
+    /// The name of the RandR output, e.g. `eDP-1` or `HDMI-1`.
+    pub name: String,
+    /// The x position of the monitor's top-left corner.
+    pub x: i16,
+    /// The y position of the monitor's top-left corner.
+    pub y: i16,
+    /// The width of the monitor, in pixels.
+    pub width: u16,
+    /// The height of the monitor, in pixels.
+    pub height: u16,
+    /// Whether this is the primary monitor.
+    pub primary: bool,
+}
+
+/// Returns the monitors that are currently active, as reported by the X
+/// RandR extension.
+///
+/// This can be used to run a bar on each monitor, by passing the position
+/// and size of each monitor to [`Cnx::with_offset()`] and
+/// [`Cnx::with_width()`].
+///
+/// [`Cnx::with_offset()`]: struct.Cnx.html#method.with_offset
+/// [`Cnx::with_width()`]: struct.Cnx.html#method.with_width
+///
+/// # Examples
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # fn run() -> Result<()> {
+/// for monitor in cnx::screens()? {
+///     println!(
+///         "{}: {}x{}+{}+{}",
+///         monitor.name, monitor.width, monitor.height, monitor.x, monitor.y
+///     );
+/// }
+/// # Ok(())
+/// # }
+/// # fn main() { run().unwrap(); }
+/// ```
+pub fn screens() -> Result<Vec<MonitorInfo>> {
+    let (conn, screen_idx) =
+        xcb::Connection::connect(None).context("Failed to connect to X server")?;
+    let root = conn
+        .get_setup()
+        .roots()
+        .nth(screen_idx as usize)
+        .ok_or_else(|| anyhow!("Invalid screen"))?
+        .root();
+
+    let present = conn
+        .get_extension_data(randr::id())
+        .map_or(false, |data| data.present());
+    if !present {
+        return Err(anyhow!("The X RandR extension is not available"));
+    }
+
+    let resources = randr::get_screen_resources_current(&conn, root)
+        .get_reply()
+        .context("Failed to get RandR screen resources")?;
+    let timestamp = resources.config_timestamp();
+    let primary = randr::get_output_primary(&conn, root)
+        .get_reply()
+        .map_or(0, |reply| reply.output());
+
+    let mut monitors = Vec::new();
+    for &output in resources.outputs() {
+        let info = randr::get_output_info(&conn, output, timestamp)
+            .get_reply()
+            .context("Failed to get RandR output info")?;
+        // Outputs without a CRTC are connected but turned off.
+        if u32::from(info.connection()) != randr::CONNECTION_CONNECTED || info.crtc() == 0 {
+            continue;
+        }
+
+        let crtc = randr::get_crtc_info(&conn, info.crtc(), timestamp)
+            .get_reply()
+            .context("Failed to get RandR CRTC info")?;
+        monitors.push(MonitorInfo {
+            name: String::from_utf8_lossy(info.name()).into_owned(),
+            x: crtc.x(),
+            y: crtc.y(),
+            width: crtc.width(),
+            height: crtc.height(),
+            primary: output == primary,
+        });
+    }
+
+    Ok(monitors)
+}
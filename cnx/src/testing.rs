@@ -0,0 +1,143 @@
+//! Helpers for testing widgets without an X server.
+//!
+//! These are only available with the `testing` feature, which is intended to
+//! be enabled from `[dev-dependencies]`:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! cnx = { version = "0.3", features = ["testing"] }
+//! ```
+
+use cairo::{Format, ImageSurface, Rectangle};
+use tokio::runtime;
+use tokio::task::LocalSet;
+use tokio_stream::StreamExt;
+
+use crate::bar::{clear_background, content_height, distribute_widths, Order};
+use crate::text::{LayoutCache, Text};
+use crate::widgets::Widget;
+
+/// Returns the first `n` updates of a widget's stream.
+///
+/// The stream is driven on a new single-threaded tokio runtime. Fewer than
+/// `n` updates are returned if the stream ends early.
+///
+/// # Panics
+///
+/// Panics if the widget fails to create its stream, or if the stream yields
+/// an error.
+///
+/// # Examples
+///
+/// ```
+/// # use cnx::testing::collect_n;
+/// # use cnx::text::*;
+/// # use cnx::widgets::{Clock, Precision};
+/// let attr = Attributes {
+///     font: "SourceCodePro 21".into(),
+///     padding: Padding::horizontal(8.0),
+///     ..Default::default()
+/// };
+///
+/// let updates = collect_n(Clock::new(attr, None, Precision::Seconds), 1);
+/// assert_eq!(updates.len(), 1);
+/// ```
+pub fn collect_n<W: Widget + 'static>(widget: W, n: usize) -> Vec<Vec<Text>> {
+    let rt = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime");
+
+    // Widget streams aren't `Send`, so drive them on a `LocalSet`.
+    LocalSet::new().block_on(&rt, async move {
+        let stream = Box::new(widget)
+            .into_stream()
+            .expect("Failed to create widget stream");
+        stream
+            .take(n)
+            .map(|update| update.expect("Widget stream yielded an error"))
+            .collect()
+            .await
+    })
+}
+
+/// Renders `texts` as they would appear on a bar of the given size.
+///
+/// The texts are laid out from the left of an in-memory image, in the same
+/// way as the content of a single widget on the bar. Any space they don't
+/// cover is left with the bar's black background. The image's pixels are
+/// returned in cairo's `ARGB32` format, i.e. four bytes per pixel, with each
+/// row being `ImageSurface::stride_for_width()` bytes long.
+///
+/// # Panics
+///
+/// Panics if the texts can't be laid out or rendered.
+pub fn render_to_image(texts: Vec<Text>, width: u16, height: u16) -> Vec<u8> {
+    let mut surface = ImageSurface::create(Format::ARgb32, i32::from(width), i32::from(height))
+        .expect("Failed to create image surface");
+
+    let mut cache = LayoutCache::default();
+    let computed = texts
+        .into_iter()
+        .map(|text| text.compute(&surface, &mut cache, None))
+        .collect::<anyhow::Result<Vec<_>>>()
+        .expect("Failed to lay out text");
+    let mut contents = vec![computed];
+
+    let bar_height = f64::from(height);
+    let text_height = content_height(&contents);
+    let y = ((bar_height - text_height) / 2.0).max(0.0);
+    for text in contents.iter_mut().flatten() {
+        text.y = y;
+        text.height = text_height;
+    }
+    distribute_widths(&mut contents, f64::from(width), Order::Normal);
+
+    clear_background(&surface, f64::from(width), bar_height).expect("Failed to clear background");
+    for text in contents.iter().flatten() {
+        let area = Rectangle::new(text.x, 0.0, text.width, bar_height);
+        text.render(&surface, &area, None)
+            .expect("Failed to render text");
+    }
+    surface.flush();
+
+    let data = surface.data().expect("Failed to read image surface");
+    data.to_vec()
+}
+
+#[cfg(test)]
+mod test {
+    use super::render_to_image;
+    use crate::text::{Attributes, Background, Color, Text};
+
+    #[test]
+    fn renders_background() {
+        let attr = Attributes {
+            font: "Sans 10".into(),
+            background: Background::Solid(Color::red()),
+            min_width: Some(10.0),
+            ..Default::default()
+        };
+        let data = render_to_image(vec![Text::new(attr, " ".to_owned())], 20, 10);
+
+        // Pixels are stored as native-endian ARGB32.
+        let pixel = |x: usize| u32::from_ne_bytes(data[x * 4..x * 4 + 4].try_into().unwrap());
+        assert_eq!(pixel(0), 0xffff0000);
+    }
+
+    #[test]
+    fn clears_background_not_covered_by_content() {
+        let attr = Attributes {
+            font: "Sans 10".into(),
+            background: Background::Solid(Color::red()),
+            min_width: Some(10.0),
+            ..Default::default()
+        };
+        let data = render_to_image(vec![Text::new(attr, " ".to_owned())], 20, 10);
+
+        // Nothing stretches, so the right half of the bar has no content and
+        // is left with the bar's default (opaque black) background.
+        let pixel = |x: usize| u32::from_ne_bytes(data[x * 4..x * 4 + 4].try_into().unwrap());
+        assert_eq!(pixel(15), 0xff000000);
+    }
+}
@@ -3,17 +3,29 @@
 //! This module is light on documentation. See the existing widget
 //! implementations for inspiration.
 
-use anyhow::Result;
-use cairo::{Context, Surface};
-use colors_transform::{Color as ColorTransform, Rgb};
+use anyhow::{anyhow, Context as _AnyhowContext, Result};
+use cairo::{Context, Extend, FontOptions, ImageSurface, LinearGradient, Rectangle, Surface};
+use log::warn;
+use pango::prelude::FontExt;
 use pango::{EllipsizeMode, FontDescription};
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_2, PI};
 use std::fmt;
+use std::fs::File;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
 
-#[derive(Clone, Debug, PartialEq)]
+use crate::resources::{find_x_resource, x_resources};
+use crate::widgets::MouseButton;
+
+#[derive(Clone, PartialEq)]
 pub struct Color {
     red: f64,
     green: f64,
     blue: f64,
+    alpha: f64,
 }
 
 macro_rules! color {
@@ -24,6 +36,7 @@ macro_rules! color {
                 red: $r,
                 green: $g,
                 blue: $b,
+                alpha: 1.0,
             }
         }
     };
@@ -38,7 +51,7 @@ impl Color {
     color!(yellow, (1.0, 1.0, 0.0));
 
     pub fn apply_to_context(&self, cr: &Context) {
-        cr.set_source_rgb(self.red, self.green, self.blue);
+        cr.set_source_rgba(self.red, self.green, self.blue, self.alpha);
     }
 
     pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
@@ -46,10 +59,21 @@ impl Color {
             red: r as f64 / 255.0,
             green: g as f64 / 255.0,
             blue: b as f64 / 255.0,
+            alpha: 1.0,
         }
     }
 
-    /// Parse string as hex color
+    pub fn from_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self {
+            alpha: a as f64 / 255.0,
+            ..Self::from_rgb(r, g, b)
+        }
+    }
+
+    /// Parse string as hex color, falling back to black if it isn't valid
+    ///
+    /// See [`Color::try_from_hex()`] for the supported formats.
+    ///
     /// # Example
     /// ```
     /// use cnx::text::Color;
@@ -58,47 +82,186 @@ impl Color {
     /// assert_eq!(Color::from_hex("not hex"), Color::from_rgb(0, 0, 0));
     /// ```
     pub fn from_hex(hex: &str) -> Self {
-        let rgb = match Rgb::from_hex_str(hex) {
-            Ok(rgb) => rgb,
-            Err(_) => Rgb::from(0.0, 0.0, 0.0),
+        Self::try_from_hex(hex).unwrap_or_else(|_| Color::black())
+    }
+
+    /// Parse string as hex color
+    ///
+    /// Accepts `rgb`, `rrggbb` and `rrggbbaa` hex digits, optionally
+    /// prefixed with `#`.
+    ///
+    /// # Example
+    /// ```
+    /// use cnx::text::Color;
+    ///
+    /// assert_eq!(Color::try_from_hex("#fa0").unwrap(), Color::from_rgb(255, 170, 0));
+    /// assert!(Color::try_from_hex("#1e1e2").is_err());
+    /// ```
+    pub fn try_from_hex(hex: &str) -> Result<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!("Invalid hex color: {:?}", hex));
+        }
+
+        // Each component is either one digit (which is repeated) or two.
+        let component = |i: usize, len: usize| -> u8 {
+            let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16)
+                .expect("Checked that all characters are hex digits");
+            if len == 1 {
+                value * 0x11
+            } else {
+                value
+            }
         };
 
-        Self {
-            red: rgb.get_red() as f64 / 255.0,
-            green: rgb.get_green() as f64 / 255.0,
-            blue: rgb.get_blue() as f64 / 255.0,
+        match digits.len() {
+            3 => Ok(Self::from_rgb(
+                component(0, 1),
+                component(1, 1),
+                component(2, 1),
+            )),
+            6 => Ok(Self::from_rgb(
+                component(0, 2),
+                component(1, 2),
+                component(2, 2),
+            )),
+            8 => Ok(Self::from_rgba(
+                component(0, 2),
+                component(1, 2),
+                component(2, 2),
+                component(3, 2),
+            )),
+            _ => Err(anyhow!(
+                "Invalid hex color: {:?} (expected 3, 6 or 8 digits)",
+                hex
+            )),
         }
     }
 
+    /// Resolve a color from the X resource database
+    ///
+    /// Looks up `name` (e.g. `color4` or `foreground`) in the
+    /// `RESOURCE_MANAGER` property of the root window, as loaded by `xrdb` from
+    /// `~/.Xresources`. Wildcard entries such as `*color4` or `*.color4` are
+    /// matched. If no matching entry is found, `name` is parsed with
+    /// [`Color::from_hex()`], so hex colors may be passed through unchanged.
+    pub fn from_x_resource(conn: &xcb::Connection, name: &str) -> Self {
+        let resources = conn
+            .get_setup()
+            .roots()
+            .next()
+            .and_then(|screen| x_resources(conn, screen.root()).ok());
+
+        let value = resources
+            .as_deref()
+            .and_then(|resources| find_x_resource(resources, name));
+        Self::from_hex(value.unwrap_or(name))
+    }
+
+    /// The red component of the color, from 0 to 255.
+    pub fn r(&self) -> u8 {
+        to_u8(self.red)
+    }
+
+    /// The green component of the color, from 0 to 255.
+    pub fn g(&self) -> u8 {
+        to_u8(self.green)
+    }
+
+    /// The blue component of the color, from 0 to 255.
+    pub fn b(&self) -> u8 {
+        to_u8(self.blue)
+    }
+
+    /// The alpha (opacity) of the color, from 0 (transparent) to 255.
+    pub fn a(&self) -> u8 {
+        to_u8(self.alpha)
+    }
+
+    /// Format the color as `#rrggbb` hex, or `#rrggbbaa` if it isn't opaque.
     pub fn to_hex(&self) -> String {
-        let r = if self.red >= 1.0 {
-            255
-        } else {
-            (self.red * 255.0) as i32
-        };
-        let g = if self.green >= 1.0 {
-            255
-        } else {
-            (self.green * 255.0) as i32
-        };
-        let b = if self.blue >= 1.0 {
-            255
-        } else {
-            (self.blue * 255.0) as i32
-        };
-        format!("#{:0width$X}{:0width$X}{:0width$X}", r, g, b, width = 2)
+        let rgb = format!("#{:02X}{:02X}{:02X}", self.r(), self.g(), self.b());
+        match self.a() {
+            255 => rgb,
+            a => format!("{rgb}{a:02X}"),
+        }
+    }
+
+    /// Interpolate between this color and `other`
+    ///
+    /// `t` is clamped between `0.0`, which gives this color, and `1.0`, which
+    /// gives `other`.
+    ///
+    /// # Example
+    /// ```
+    /// use cnx::text::Color;
+    ///
+    /// let grey = Color::black().blend(&Color::white(), 0.5);
+    /// assert_eq!(grey.to_hex(), "#808080");
+    /// ```
+    pub fn blend(&self, other: &Color, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        Self {
+            red: mix(self.red, other.red),
+            green: mix(self.green, other.green),
+            blue: mix(self.blue, other.blue),
+            alpha: mix(self.alpha, other.alpha),
+        }
+    }
+
+    /// Darken the color by blending it with black
+    ///
+    /// A `factor` of `0.0` leaves the color unchanged, and `1.0` gives black.
+    pub fn darken(&self, factor: f64) -> Self {
+        self.blend(&Color::black(), factor)
+    }
+
+    /// Lighten the color by blending it with white
+    ///
+    /// A `factor` of `0.0` leaves the color unchanged, and `1.0` gives white.
+    pub fn lighten(&self, factor: f64) -> Self {
+        self.blend(&Color::white(), factor)
+    }
+}
+
+impl fmt::Debug for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Color({})", self.to_hex())
     }
 }
 
+fn to_u8(component: f64) -> u8 {
+    (component.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// The space (in pixels) around a [`Text`].
+///
+/// [`Padding::new()`] takes each side in the order `left`, `right`, `top`,
+/// `bottom`. The other constructors and `with_*()` methods can be clearer at
+/// call sites.
+///
+/// # Example
+/// ```
+/// use cnx::text::Padding;
+///
+/// assert_eq!(Padding::horizontal(8.0), Padding::new(8.0, 8.0, 0.0, 0.0));
+/// assert_eq!(
+///     Padding::all(4.0).with_left(8.0),
+///     Padding::new(8.0, 4.0, 4.0, 4.0)
+/// );
+/// ```
 #[derive(Clone, Debug, PartialEq)]
 pub struct Padding {
-    left: f64,
-    right: f64,
-    top: f64,
-    bottom: f64,
+    pub(crate) left: f64,
+    pub(crate) right: f64,
+    pub(crate) top: f64,
+    pub(crate) bottom: f64,
 }
 
 impl Padding {
+    /// Creates padding with the given `left`, `right`, `top` and `bottom`
+    /// sides, in that order.
     pub fn new(left: f64, right: f64, top: f64, bottom: f64) -> Padding {
         Padding {
             left,
@@ -107,15 +270,139 @@ impl Padding {
             bottom,
         }
     }
+
+    /// Creates padding of `padding` on the left and right, and none on the
+    /// top and bottom.
+    pub fn horizontal(padding: f64) -> Padding {
+        Padding::new(padding, padding, 0.0, 0.0)
+    }
+
+    /// Creates padding of `padding` on the top and bottom, and none on the
+    /// left and right.
+    pub fn vertical(padding: f64) -> Padding {
+        Padding::new(0.0, 0.0, padding, padding)
+    }
+
+    /// Creates padding of `padding` on all sides.
+    pub fn all(padding: f64) -> Padding {
+        Padding::new(padding, padding, padding, padding)
+    }
+
+    /// Returns this padding with the left side set to `left`.
+    pub fn with_left(self, left: f64) -> Padding {
+        Padding { left, ..self }
+    }
+
+    /// Returns this padding with the right side set to `right`.
+    pub fn with_right(self, right: f64) -> Padding {
+        Padding { right, ..self }
+    }
+
+    /// Returns this padding with the top side set to `top`.
+    pub fn with_top(self, top: f64) -> Padding {
+        Padding { top, ..self }
+    }
+
+    /// Returns this padding with the bottom side set to `bottom`.
+    pub fn with_bottom(self, bottom: f64) -> Padding {
+        Padding { bottom, ..self }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct Font(FontDescription);
 
+// Generic family names, which fontconfig always substitutes with a real
+// family, so aren't worth warning about.
+const GENERIC_FAMILIES: &[&str] = &[
+    "sans",
+    "sans-serif",
+    "serif",
+    "monospace",
+    "mono",
+    "system-ui",
+    "emoji",
+    "cursive",
+    "fantasy",
+];
+
 impl Font {
     pub fn new(name: &str) -> Font {
         Font(FontDescription::from_string(name))
     }
+
+    /// Creates a font whose size is scaled for the screen's DPI.
+    ///
+    /// `base_points` is the size the font should be on a 96 DPI screen. It
+    /// is multiplied by DPI/96, where the DPI is read from the XSETTINGS
+    /// manager or the `Xft.dpi` X resource (see [`crate::Cnx::dpi()`]). This
+    /// keeps text the same physical size on screens with different DPIs.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use cnx::text::Font;
+    /// let font = Font::scaled("SourceCodePro", 12.0);
+    /// ```
+    pub fn scaled(name: &str, base_points: f64) -> Font {
+        let points = base_points * crate::dpi::dpi() / crate::dpi::BASE_DPI;
+        Font::new(&format!("{name} {points}"))
+    }
+
+    /// Finds the font that Pango will actually use for this `Font`.
+    ///
+    /// Pango silently substitutes a default font when the requested family
+    /// isn't installed. This returns an error if that happened, so that a
+    /// misconfigured font can be reported rather than the bar quietly using
+    /// the wrong font. Generic families such as `Sans` or `Monospace` are
+    /// always considered to match.
+    pub fn resolve(&self, ctx: &pango::Context) -> Result<ResolvedFont> {
+        let requested = self
+            .0
+            .family()
+            .map(|family| family.to_string())
+            .unwrap_or_default();
+        let font = ctx
+            .load_font(&self.0)
+            .ok_or_else(|| anyhow!("Failed to load font: {}", self.0))?;
+        let family = font
+            .describe()
+            .family()
+            .map(|family| family.to_string())
+            .unwrap_or_default();
+
+        // The family may be a comma-separated list of fallbacks.
+        let matched = requested.is_empty()
+            || requested.split(',').map(str::trim).any(|requested| {
+                requested.eq_ignore_ascii_case(&family)
+                    || GENERIC_FAMILIES
+                        .iter()
+                        .any(|generic| requested.eq_ignore_ascii_case(generic))
+            });
+        if !matched {
+            return Err(anyhow!(
+                "Font family '{requested}' is not available, using '{family}' instead"
+            ));
+        }
+
+        Ok(ResolvedFont { requested, family })
+    }
+}
+
+/// The family that Pango resolved a [`Font`] to, returned by
+/// [`Font::resolve()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedFont {
+    /// The family (or comma-separated list of families) that was requested
+    pub requested: String,
+    /// The family that Pango will draw the font with
+    pub family: String,
+}
+
+impl From<&str> for Font {
+    fn from(name: &str) -> Font {
+        Font::new(name)
+    }
 }
 
 impl fmt::Debug for Font {
@@ -124,12 +411,175 @@ impl fmt::Debug for Font {
     }
 }
 
+/// What is drawn behind a widget's text.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum Background {
+    /// The bar's default (black) background.
+    #[default]
+    None,
+    /// A solid color.
+    Solid(Color),
+    /// A PNG image, repeated to fill the widget.
+    Image(PathBuf),
+    /// A gradient from the left of the widget to the right.
+    LinearGradient { from: Color, to: Color },
+}
+
+impl Background {
+    // Sets the context's source to the background, for a widget `width`
+    // pixels wide whose top is at `y`. An image background is drawn from
+    // `image`, which was loaded from its path when the text was computed.
+    fn apply_to_context(
+        &self,
+        context: &Context,
+        y: f64,
+        width: f64,
+        image: Option<&LoadedImage>,
+    ) -> Result<()> {
+        match self {
+            Background::None => Color::black().apply_to_context(context),
+            Background::Solid(color) => color.apply_to_context(context),
+            Background::Image(_) => match image {
+                Some(image) => {
+                    context.set_source_surface(&*image.0, 0.0, y)?;
+                    context.source().set_extend(Extend::Repeat);
+                }
+                None => Color::black().apply_to_context(context),
+            },
+            Background::LinearGradient { from, to } => {
+                let gradient = LinearGradient::new(0.0, 0.0, width, 0.0);
+                for (offset, color) in [(0.0, from), (1.0, to)] {
+                    gradient.add_color_stop_rgba(
+                        offset,
+                        color.red,
+                        color.green,
+                        color.blue,
+                        color.alpha,
+                    );
+                }
+                context.set_source(&gradient)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An animation which the bar applies to a widget's text.
+///
+/// Widgets can use this to draw attention to a critical state, by setting it
+/// in the [`Attributes`] of the text they show in that state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Animation {
+    /// Repeatedly hides and shows the text (but not its background). The
+    /// text is shown for the first half of each `period` and hidden for the
+    /// second half.
+    Blink { period: Duration },
+}
+
+impl Animation {
+    // How long the animation stays in each state.
+    pub(crate) fn step(self) -> Duration {
+        match self {
+            // Don't let a tiny period redraw the bar in a busy loop.
+            Animation::Blink { period } => (period / 2).max(Duration::from_millis(50)),
+        }
+    }
+
+    // Whether the text is visible `elapsed` after the animations started.
+    pub(crate) fn is_visible(self, elapsed: Duration) -> bool {
+        elapsed.as_nanos() / self.step().as_nanos() % 2 == 0
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Attributes {
     pub font: Font,
     pub fg_color: Color,
-    pub bg_color: Option<Color>,
+    pub background: Background,
     pub padding: Padding,
+    /// The minimum width (in pixels, including padding) of text drawn with
+    /// these attributes. Narrower text is left-aligned within this width,
+    /// which stops the bar from shifting as e.g. a percentage changes from
+    /// `9%` to `10%`.
+    pub min_width: Option<f64>,
+    /// Whether text which is too wide for its space ends with an ellipsis
+    /// (`…`). If `false`, the text is clipped instead, which can look better
+    /// for e.g. CJK text.
+    pub ellipsize: bool,
+    /// The radius (in pixels) of the corners of the background. This only
+    /// has an effect if `background` is set.
+    pub corner_radius: f64,
+    /// An animation to draw attention to the text, e.g. for a critical
+    /// battery level.
+    pub animation: Option<Animation>,
+}
+
+/// The default attributes draw white `Monospace 12` text on the bar's
+/// background, with no padding.
+///
+/// This can be used to only specify the attributes that differ from the
+/// default:
+///
+/// ```
+/// # use cnx::text::{Attributes, Padding};
+/// let attr = Attributes {
+///     font: "SourceCodePro 21".into(),
+///     padding: Padding::horizontal(8.0),
+///     ..Default::default()
+/// };
+/// ```
+impl Default for Attributes {
+    fn default() -> Attributes {
+        Attributes {
+            font: Font::new("Monospace 12"),
+            fg_color: Color::white(),
+            background: Background::None,
+            padding: Padding::new(0.0, 0.0, 0.0, 0.0),
+            min_width: None,
+            ellipsize: true,
+            corner_radius: 0.0,
+            animation: None,
+        }
+    }
+}
+
+impl Attributes {
+    /// Returns a copy of these attributes using a different foreground
+    /// [`Color`]
+    pub fn with_fg_color(self, fg_color: Color) -> Attributes {
+        Attributes { fg_color, ..self }
+    }
+
+    /// Returns a copy of these attributes with a solid background [`Color`],
+    /// or no background if `bg_color` is `None`
+    pub fn with_bg_color(self, bg_color: Option<Color>) -> Attributes {
+        let background = bg_color.map_or(Background::None, Background::Solid);
+        Attributes { background, ..self }
+    }
+
+    /// Returns a copy of these attributes using different [`Padding`]
+    pub fn with_padding(self, padding: Padding) -> Attributes {
+        Attributes { padding, ..self }
+    }
+
+    /// Returns a copy of these attributes using a different [`Font`]
+    ///
+    /// This is useful for widgets which change their font depending on their
+    /// state, e.g. to switch to a bold font when a warning threshold is hit.
+    pub fn with_font(self, font: Font) -> Attributes {
+        Attributes { font, ..self }
+    }
+
+    /// Returns a copy of these attributes with an [`Animation`]
+    ///
+    /// This is useful for widgets which want to draw attention to a critical
+    /// state, e.g. to blink when the battery is almost empty.
+    pub fn with_animation(self, animation: Animation) -> Attributes {
+        Attributes {
+            animation: Some(animation),
+            ..self
+        }
+    }
 }
 
 pub struct PagerAttributes {
@@ -141,59 +591,635 @@ pub struct PagerAttributes {
     pub non_empty_attr: Attributes,
 }
 
+// Creates a cairo context for drawing to `surface`, using the bar's font
+// options (if any) rather than cairo's defaults.
+fn create_context(surface: &Surface, font_options: Option<&FontOptions>) -> Result<Context> {
+    let context = Context::new(surface)?;
+    if let Some(font_options) = font_options {
+        context.set_font_options(font_options);
+    }
+    Ok(context)
+}
+
 fn create_pango_layout(cairo_context: &cairo::Context) -> pango::Layout {
     pangocairo::functions::create_layout(cairo_context)
 }
 
+// Returns a Pango context for drawing to `surface`.
+pub(crate) fn create_pango_context(surface: &Surface) -> Result<pango::Context> {
+    let context = Context::new(surface)?;
+    Ok(create_pango_layout(&context).context())
+}
+
 fn show_pango_layout(cairo_context: &cairo::Context, layout: &pango::Layout) {
     pangocairo::functions::show_layout(cairo_context, layout);
 }
 
+/// Escape a string so that it can be safely interpolated into Pango markup
+///
+/// Replaces `&`, `<`, `>`, `'` and `"` with their entities. Use this for any
+/// untrusted text (window titles, desktop names, command output, etc.) which is
+/// shown in a [`Text`] with `markup` enabled, otherwise a stray `<` or `&` will
+/// stop Pango from parsing the markup.
+///
+/// # Example
+/// ```
+/// use cnx::text::escape_markup;
+///
+/// assert_eq!(escape_markup("<b>Tom & Jerry</b>"), "&lt;b&gt;Tom &amp; Jerry&lt;/b&gt;");
+/// assert_eq!(escape_markup("\"it's\""), "&quot;it&#39;s&quot;");
+/// ```
+pub fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '\'' => escaped.push_str("&#39;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A piece of text shown by a widget.
+///
+/// Create one with [`Text::new()`] and customise it with the `with_*()`
+/// methods. New fields may be added in future, so `Text` can't be constructed
+/// directly outside of this crate.
+///
+/// # Example
+/// ```
+/// use cnx::text::{Attributes, Padding, Text};
+///
+/// let attr = Attributes {
+///     font: "SourceCodePro 21".into(),
+///     padding: Padding::horizontal(8.0),
+///     ..Default::default()
+/// };
+/// let text = Text::new(attr, "some <b>bold</b> text").with_stretch(true);
+/// ```
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub struct Text {
     pub attr: Attributes,
     pub text: String,
+    /// Whether the text stretches to fill the space left by other texts.
     pub stretch: bool,
+    /// The share of the space left by other texts that this text gets, if
+    /// it stretches, relative to other stretching texts.
+    pub stretch_weight: f64,
+    /// Whether the text is parsed as [Pango markup]. If the text isn't valid
+    /// markup, a warning is logged and it is shown as plain text instead.
+    ///
+    /// [Pango markup]: https://docs.gtk.org/Pango/pango_markup.html
     pub markup: bool,
+    /// The maximum width (in pixels, including padding) of the text. Longer
+    /// text is ellipsized to fit.
+    pub max_width: Option<f64>,
+    /// An image to draw instead of the text.
+    pub image: Option<ImageContent>,
+    /// How the text is aligned, if it has more space than it needs.
+    pub alignment: Alignment,
+    /// Styles applied to parts of the text, if it was created from a
+    /// [`RichText`].
+    pub rich_text: Option<RichText>,
+    /// A fixed width for the text, in digits of its font. If this is set,
+    /// the text's width doesn't change with its content.
+    pub width_chars: Option<usize>,
+    /// Called when a mouse button is pressed over the text.
+    pub on_click: Option<OnClick>,
+}
+
+/// A handler for mouse buttons pressed over a [`Text`], set with
+/// [`Text::with_click()`].
+///
+/// Two handlers are only equal if they are the same closure, so widgets
+/// which yield the same handler with each update should clone it rather than
+/// creating a new closure each time.
+#[derive(Clone)]
+pub struct OnClick(Rc<dyn Fn(MouseButton)>);
+
+impl OnClick {
+    /// Creates a new handler, which calls `on_click`.
+    pub fn new<F>(on_click: F) -> OnClick
+    where
+        F: Fn(MouseButton) + 'static,
+    {
+        OnClick(Rc::new(on_click))
+    }
+
+    pub(crate) fn call(&self, button: MouseButton) {
+        (self.0)(button)
+    }
+}
+
+impl PartialEq for OnClick {
+    fn eq(&self, other: &OnClick) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for OnClick {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OnClick")
+    }
+}
+
+/// How a [`Text`] is aligned within its width.
+///
+/// This only makes a difference if the text is given more space than it
+/// needs, e.g. because it stretches or has a `min_width`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    fn to_pango(self) -> pango::Alignment {
+        match self {
+            Alignment::Left => pango::Alignment::Left,
+            Alignment::Center => pango::Alignment::Center,
+            Alignment::Right => pango::Alignment::Right,
+        }
+    }
+
+    // How far to move content `content_width` pixels wide, so that it is
+    // aligned within `width` pixels.
+    fn offset(self, content_width: f64, width: f64) -> f64 {
+        let space = (width - content_width).max(0.0);
+        match self {
+            Alignment::Left => 0.0,
+            Alignment::Center => space / 2.0,
+            Alignment::Right => space,
+        }
+    }
 }
 
 impl Text {
-    pub(crate) fn compute(self, surface: &Surface) -> Result<ComputedText> {
-        let (width, height) = {
-            let context = Context::new(surface)?;
-            let layout = create_pango_layout(&context);
-            if self.markup {
-                layout.set_markup(&self.text);
-            } else {
-                layout.set_text(&self.text);
-            }
-            layout.set_font_description(Some(&self.attr.font.0));
+    /// Creates a new `Text` with the given attributes.
+    ///
+    /// The text doesn't stretch, has no maximum width, and is parsed as Pango
+    /// markup. Use [`escape_markup()`] on any untrusted parts of `text`, or
+    /// disable markup with [`with_markup()`].
+    ///
+    /// An empty text without a background takes up no space in the bar, not
+    /// even for its padding, so widgets can hide themselves by showing one.
+    ///
+    /// [`with_markup()`]: #method.with_markup
+    pub fn new<S: Into<String>>(attr: Attributes, text: S) -> Text {
+        Text {
+            attr,
+            text: text.into(),
+            stretch: false,
+            stretch_weight: 1.0,
+            markup: true,
+            max_width: None,
+            image: None,
+            alignment: Alignment::Left,
+            rich_text: None,
+            width_chars: None,
+            on_click: None,
+        }
+    }
+
+    /// Creates a new `Text` which shows `rich_text`, with its styles.
+    ///
+    /// The text isn't parsed as markup, so it doesn't need escaping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cnx::text::{Attributes, Color, RichText, Text, TextStyle};
+    /// let rich_text = RichText::new()
+    ///     .push("CPU: ")
+    ///     .push_styled("95%", &[TextStyle::Foreground(Color::red()), TextStyle::Bold]);
+    /// let text = Text::rich(Attributes::default(), rich_text);
+    /// assert_eq!(text.text, "CPU: 95%");
+    /// ```
+    pub fn rich(attr: Attributes, rich_text: RichText) -> Text {
+        let text = rich_text.text.clone();
+        Text {
+            rich_text: Some(rich_text),
+            ..Text::new(attr, text).with_markup(false)
+        }
+    }
+
+    /// Creates a new `Text` which draws an image rather than text.
+    ///
+    /// The image is drawn within the padding of `attr`, on top of its
+    /// background color.
+    pub fn image(attr: Attributes, image: ImageContent) -> Text {
+        Text {
+            image: Some(image),
+            ..Text::new(attr, "").with_markup(false)
+        }
+    }
+
+    /// Returns this text, stretching to fill the available space if `stretch`
+    /// is true.
+    pub fn with_stretch(self, stretch: bool) -> Text {
+        Text { stretch, ..self }
+    }
+
+    /// Returns this text, with the given weight when stretching.
+    ///
+    /// The space left by texts which don't stretch is divided between
+    /// stretching texts in proportion to their weights, e.g. a text with a
+    /// weight of `2.0` gets twice as much space as one with the default weight
+    /// of `1.0`. A text with a weight of `0.0` doesn't stretch at all.
+    pub fn with_stretch_weight(self, stretch_weight: f64) -> Text {
+        Text {
+            stretch_weight,
+            ..self
+        }
+    }
+
+    // Whether the text actually stretches, taking its weight into account.
+    fn stretches(&self) -> bool {
+        self.stretch && self.stretch_weight > 0.0
+    }
+
+    // Whether this text has nothing to show, so shouldn't take up any space
+    // in the bar, not even for its padding. This lets widgets disappear when
+    // they have nothing to say by yielding an empty text. Texts which ask
+    // for space, by stretching or with a minimum width, still get it.
+    fn is_blank(&self) -> bool {
+        self.text.is_empty()
+            && self.image.is_none()
+            && self.attr.background == Background::None
+            && self.attr.min_width.is_none()
+            && self.width_chars.is_none()
+            && !self.stretches()
+    }
+
+    /// Returns this text, parsed as Pango markup if `markup` is true.
+    pub fn with_markup(self, markup: bool) -> Text {
+        Text { markup, ..self }
+    }
+
+    /// Returns this text, aligned within its width as specified.
+    ///
+    /// For example, a clock which stretches to fill the middle of the bar
+    /// can be centered within that space with [`Alignment::Center`].
+    pub fn with_alignment(self, alignment: Alignment) -> Text {
+        Text { alignment, ..self }
+    }
+
+    /// Returns this text, always `width_chars` digits of its font wide.
+    ///
+    /// This suits texts whose width is known in advance, such as a clock or
+    /// a percentage. They aren't laid out each time they change to find
+    /// their size, and don't make the bar shift as they change.
+    pub fn with_width_chars(self, width_chars: usize) -> Text {
+        Text {
+            width_chars: Some(width_chars),
+            ..self
+        }
+    }
+
+    /// Returns this text, calling `on_click` when a mouse button is pressed
+    /// over it.
+    ///
+    /// This takes priority over the widget's [`Widget::input_handler()`], so
+    /// that widgets which show several texts can tell which was clicked. Like
+    /// an input handler, `on_click` is called on the bar's event loop, so it
+    /// shouldn't block for long.
+    ///
+    /// [`Widget::input_handler()`]: crate::widgets::Widget::input_handler
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cnx::text::{Attributes, Text};
+    /// # use cnx::widgets::MouseButton;
+    /// let text = Text::new(Attributes::default(), "menu").with_click(|button| {
+    ///     if button == MouseButton::Left {
+    ///         // Open a menu, etc.
+    ///     }
+    /// });
+    /// ```
+    pub fn with_click<F>(self, on_click: F) -> Text
+    where
+        F: Fn(MouseButton) + 'static,
+    {
+        Text {
+            on_click: Some(OnClick::new(on_click)),
+            ..self
+        }
+    }
+
+    /// Returns this text, ellipsized if it is wider than `max_width` pixels.
+    pub fn with_max_width(self, max_width: f64) -> Text {
+        Text {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    pub(crate) fn compute(
+        self,
+        surface: &Surface,
+        cache: &mut LayoutCache,
+        font_options: Option<&FontOptions>,
+    ) -> Result<ComputedText> {
+        // Decode any images once, rather than each time the text is drawn.
+        let image_surface = match &self.image {
+            Some(image) => Some(LoadedImage::load(&image.path)?),
+            None => None,
+        };
+        let background_image = match &self.attr.background {
+            Background::Image(path) => Some(LoadedImage::load(path)?),
+            _ => None,
+        };
+
+        let (width, height, markup) = if self.is_blank() {
+            (0.0, 0.0, false)
+        } else {
+            let (content_width, content_height, markup) = match (&self.image, &image_surface) {
+                (Some(image), Some(surface)) => {
+                    let (width, height) = image.size(surface);
+                    (width, height, false)
+                }
+                _ => match self.width_chars {
+                    Some(width_chars) => {
+                        // The size only depends on the font, so is shared by
+                        // every text with the same font and width.
+                        let key = LayoutKey {
+                            text: String::new(),
+                            font: self.attr.font.0.to_string(),
+                            markup: false,
+                            styles: None,
+                            width_chars: Some(width_chars),
+                        };
+                        let (width, height, _) = cache.get_or_insert_with(key, || {
+                            self.fixed_layout_size(width_chars, surface, font_options)
+                        })?;
+                        (width, height, self.markup && is_valid_markup(&self.text))
+                    }
+                    None => {
+                        let key = LayoutKey {
+                            text: self.text.clone(),
+                            font: self.attr.font.0.to_string(),
+                            markup: self.markup,
+                            styles: self
+                                .rich_text
+                                .as_ref()
+                                .map(|rich_text| format!("{:?}", rich_text.spans)),
+                            width_chars: None,
+                        };
+                        cache.get_or_insert_with(key, || self.layout_size(surface, font_options))?
+                    }
+                },
+            };
 
             let padding = &self.attr.padding;
-            let (text_width, text_height) = layout.pixel_size();
-            let width = f64::from(text_width) + padding.left + padding.right;
-            let height = f64::from(text_height) + padding.top + padding.bottom;
-            (width, height)
+            let mut width = content_width + padding.left + padding.right;
+            if let Some(min_width) = self.attr.min_width {
+                width = width.max(min_width);
+            }
+            if let Some(max_width) = self.max_width {
+                // `render()` ellipsizes the layout to the computed width.
+                width = width.min(max_width);
+            }
+            let height = content_height + padding.top + padding.bottom;
+            (width, height, markup)
         };
 
         Ok(ComputedText {
             attr: self.attr,
             text: self.text,
-            stretch: self.stretch,
+            stretch: self.stretches(),
+            stretch_weight: self.stretch_weight,
+            max_width: self.max_width,
+            image: self.image,
+            alignment: self.alignment,
+            rich_text: self.rich_text,
+            width_chars: self.width_chars,
+            on_click: self.on_click,
+            image_surface,
+            background_image,
             x: 0.0,
             y: 0.0,
             width,
             height,
-            markup: self.markup,
+            markup,
+            hidden: false,
         })
     }
 }
 
+/// A style applied to part of a [`RichText`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextStyle {
+    /// Draws the text in a different color.
+    Foreground(Color),
+    /// Fills the background behind the text.
+    Background(Color),
+    /// Draws the text in bold.
+    Bold,
+    /// Draws the text in italics.
+    Italic,
+    /// Underlines the text.
+    Underline,
+}
+
+impl TextStyle {
+    fn to_pango(&self) -> pango::Attribute {
+        // Pango's colors have 16 bits per channel.
+        let channels = |color: &Color| {
+            let channel = |value: u8| u16::from(value) * 257;
+            (channel(color.r()), channel(color.g()), channel(color.b()))
+        };
+        match self {
+            TextStyle::Foreground(color) => {
+                let (r, g, b) = channels(color);
+                pango::AttrColor::new_foreground(r, g, b).into()
+            }
+            TextStyle::Background(color) => {
+                let (r, g, b) = channels(color);
+                pango::AttrColor::new_background(r, g, b).into()
+            }
+            TextStyle::Bold => pango::AttrInt::new_weight(pango::Weight::Bold).into(),
+            TextStyle::Italic => pango::AttrInt::new_style(pango::Style::Italic).into(),
+            TextStyle::Underline => pango::AttrInt::new_underline(pango::Underline::Single).into(),
+        }
+    }
+}
+
+/// Text with styles applied to parts of it, shown with [`Text::rich()`].
+///
+/// This is an alternative to building strings of Pango markup, which avoids
+/// having to escape any of the text.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RichText {
+    text: String,
+    // The byte ranges of `text` that each style applies to.
+    spans: Vec<(Range<usize>, TextStyle)>,
+}
+
+impl RichText {
+    /// Creates a new, empty `RichText`.
+    pub fn new() -> RichText {
+        RichText::default()
+    }
+
+    /// Returns this text, followed by `text` without any styles.
+    pub fn push<S: AsRef<str>>(self, text: S) -> RichText {
+        self.push_styled(text, &[])
+    }
+
+    /// Returns this text, followed by `text` with the given styles.
+    pub fn push_styled<S: AsRef<str>>(mut self, text: S, styles: &[TextStyle]) -> RichText {
+        let start = self.text.len();
+        self.text.push_str(text.as_ref());
+        let range = start..self.text.len();
+        self.spans
+            .extend(styles.iter().map(|style| (range.clone(), style.clone())));
+        self
+    }
+
+    /// The text, without any styles.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the styles as a Pango attribute list, to apply to a layout of
+    /// [`text()`].
+    ///
+    /// [`text()`]: #method.text
+    pub fn attr_list(&self) -> pango::AttrList {
+        let attr_list = pango::AttrList::new();
+        for (range, style) in &self.spans {
+            let mut attr = style.to_pango();
+            attr.set_start_index(range.start as u32);
+            attr.set_end_index(range.end as u32);
+            attr_list.insert(attr);
+        }
+        attr_list
+    }
+}
+
+impl Text {
+    // Lays out the text, returning its size and whether it was laid out as
+    // markup.
+    fn layout_size(
+        &self,
+        surface: &Surface,
+        font_options: Option<&FontOptions>,
+    ) -> Result<LayoutSize> {
+        let markup = self.markup && is_valid_markup(&self.text);
+        let context = create_context(surface, font_options)?;
+        let layout = create_pango_layout(&context);
+        if markup {
+            layout.set_markup(&self.text);
+        } else {
+            layout.set_text(&self.text);
+        }
+        if let Some(rich_text) = &self.rich_text {
+            layout.set_attributes(Some(&rich_text.attr_list()));
+        }
+        layout.set_font_description(Some(&self.attr.font.0));
+
+        let (text_width, text_height) = layout.pixel_size();
+        Ok((f64::from(text_width), f64::from(text_height), markup))
+    }
+
+    // Returns the size of `width_chars` digits in the text's font, without
+    // laying out the text itself.
+    fn fixed_layout_size(
+        &self,
+        width_chars: usize,
+        surface: &Surface,
+        font_options: Option<&FontOptions>,
+    ) -> Result<LayoutSize> {
+        let context = create_context(surface, font_options)?;
+        let layout = create_pango_layout(&context);
+        layout.set_font_description(Some(&self.attr.font.0));
+        // The height of a single line only depends on the font.
+        layout.set_text("0");
+        let (_, text_height) = layout.pixel_size();
+
+        let metrics = layout.context().metrics(Some(&self.attr.font.0), None);
+        let digit_width = f64::from(metrics.approximate_digit_width()) / f64::from(pango::SCALE);
+        let text_width = (digit_width * width_chars as f64).ceil();
+        Ok((text_width, f64::from(text_height), false))
+    }
+}
+
+// The width, height and whether the text is valid markup.
+type LayoutSize = (f64, f64, bool);
+
+#[derive(PartialEq, Eq, Hash)]
+pub(crate) struct LayoutKey {
+    text: String,
+    font: String,
+    markup: bool,
+    // The styles of a `RichText`, as some (e.g. bold) change its size.
+    styles: Option<String>,
+    width_chars: Option<usize>,
+}
+
+// The maximum number of sizes kept by a `LayoutCache`, so that widgets whose
+// text is always changing (e.g. a clock) can't grow it forever.
+const LAYOUT_CACHE_CAPACITY: usize = 512;
+
+// Caches the size of laid out text, keyed by everything that affects it, so
+// that text which has already been seen doesn't need to be laid out again.
+#[derive(Default)]
+pub(crate) struct LayoutCache {
+    sizes: HashMap<LayoutKey, LayoutSize>,
+}
+
+impl LayoutCache {
+    fn get_or_insert_with<F>(&mut self, key: LayoutKey, f: F) -> Result<LayoutSize>
+    where
+        F: FnOnce() -> Result<LayoutSize>,
+    {
+        if let Some(size) = self.sizes.get(&key) {
+            return Ok(*size);
+        }
+        let size = f()?;
+        if self.sizes.len() >= LAYOUT_CACHE_CAPACITY {
+            self.sizes.clear();
+        }
+        self.sizes.insert(key, size);
+        Ok(size)
+    }
+}
+
+// Checks whether `text` is valid Pango markup, warning if it isn't. Pango
+// draws nothing at all for invalid markup, so the caller should fall back to
+// showing `text` as it is.
+fn is_valid_markup(text: &str) -> bool {
+    match pango::parse_markup(text, '\0') {
+        Ok(_) => true,
+        Err(err) => {
+            warn!("Showing invalid Pango markup as plain text ({err}): {text:?}");
+            false
+        }
+    }
+}
+
 // This impl allows us to see whether a widget's text has changed without
 // having to call the (relatively) expensive .compute().
 impl PartialEq<ComputedText> for Text {
     fn eq(&self, other: &ComputedText) -> bool {
-        self.attr == other.attr && self.text == other.text && self.stretch == other.stretch
+        self.attr == other.attr
+            && self.text == other.text
+            && self.stretches() == other.stretch
+            && self.stretch_weight == other.stretch_weight
+            && self.max_width == other.max_width
+            && self.image == other.image
+            && self.alignment == other.alignment
+            && self.rich_text == other.rich_text
+            && self.width_chars == other.width_chars
+            && self.on_click == other.on_click
     }
 }
 
@@ -202,55 +1228,194 @@ pub(crate) struct ComputedText {
     pub attr: Attributes,
     pub text: String,
     pub stretch: bool,
+    pub stretch_weight: f64,
+    pub max_width: Option<f64>,
+    pub image: Option<ImageContent>,
+    pub alignment: Alignment,
+    pub rich_text: Option<RichText>,
+    pub width_chars: Option<usize>,
+    pub on_click: Option<OnClick>,
+    // The decoded `image` and background image, if there are any.
+    pub image_surface: Option<LoadedImage>,
+    pub background_image: Option<LoadedImage>,
 
     pub x: f64,
     pub y: f64,
     pub width: f64,
     pub height: f64,
     pub markup: bool,
+    // Whether the text is currently hidden by its animation.
+    pub hidden: bool,
 }
 
 impl ComputedText {
-    pub fn render(&self, surface: &Surface) -> Result<()> {
-        let context = Context::new(surface)?;
+    // Draws the text, filling `area` (in the bar's coordinates) with its
+    // background.
+    pub fn render(
+        &self,
+        surface: &Surface,
+        area: &Rectangle,
+        font_options: Option<&FontOptions>,
+    ) -> Result<()> {
+        // Blank texts take up no space, so have nothing to draw.
+        if self.width <= 0.0 {
+            return Ok(());
+        }
+
+        let context = create_context(surface, font_options)?;
+        context.translate(self.x, self.y);
+
+        // Fill the background for the whole area, not just the text, as the
+        // bar may be taller than its content.
+        let (x, y) = (area.x() - self.x, area.y() - self.y);
+        let (width, height) = (area.width(), area.height());
+        let background = &self.attr.background;
+        let radius = self.attr.corner_radius;
+        if *background != Background::None {
+            // Clear the area first. Otherwise, translucent backgrounds would
+            // be drawn over whatever was previously drawn there, becoming
+            // more opaque with each redraw, and the corners of rounded ones
+            // would keep it.
+            Color::black().apply_to_context(&context);
+            context.rectangle(x, y, width, height);
+            context.fill()?;
+        }
+        if *background != Background::None && radius > 0.0 {
+            rounded_rectangle(&context, x, y, width, height, radius);
+        } else {
+            context.rectangle(x, y, width, height);
+        }
+        background.apply_to_context(&context, y, width, self.background_image.as_ref())?;
+        context.fill()?;
+
+        let padding = &self.attr.padding;
+        context.translate(padding.left, padding.top);
+
+        if self.hidden {
+            return Ok(());
+        }
+
+        if let (Some(image), Some(surface)) = (&self.image, &self.image_surface) {
+            return image.render(&context, surface);
+        }
+
         let layout = create_pango_layout(&context);
         if self.markup {
             layout.set_markup(&self.text);
         } else {
             layout.set_text(&self.text);
         }
+        if let Some(rich_text) = &self.rich_text {
+            layout.set_attributes(Some(&rich_text.attr_list()));
+        }
         layout.set_font_description(Some(&self.attr.font.0));
 
-        context.translate(self.x, self.y);
-
-        // Set the width/height on the Pango layout so that it word-wraps/ellipises.
-        let padding = &self.attr.padding;
         let text_width = self.width - padding.left - padding.right;
         let text_height = self.height - padding.top - padding.bottom;
-        layout.set_ellipsize(EllipsizeMode::End);
-        layout.set_width(text_width as i32 * pango::SCALE);
-        layout.set_height(text_height as i32 * pango::SCALE);
-
-        let bg_color = &self.attr.bg_color.clone().unwrap_or_else(Color::black);
-        bg_color.apply_to_context(&context);
-        // FIXME: The use of `height` isnt' right here: we want to do the
-        // full height of the bar, not the full height of the text. It
-        // would be useful if we could do Surface.get_height(), but that
-        // doesn't seem to be available in cairo-rs for some reason?
-        context.rectangle(0.0, 0.0, self.width, self.height);
-        context.fill()?;
+        if self.attr.ellipsize {
+            // Set the width/height on the Pango layout so that it word-wraps/ellipises.
+            layout.set_ellipsize(EllipsizeMode::End);
+            layout.set_width(text_width as i32 * pango::SCALE);
+            layout.set_height(text_height as i32 * pango::SCALE);
+            layout.set_alignment(self.alignment.to_pango());
+        } else {
+            context.rectangle(0.0, 0.0, text_width, text_height);
+            context.clip();
+            // Without a width, Pango can't align the layout itself. Text
+            // which is wider than its space isn't moved, so its end is
+            // clipped.
+            let (layout_width, _) = layout.pixel_size();
+            let offset = self.alignment.offset(f64::from(layout_width), text_width);
+            context.translate(offset, 0.0);
+        }
 
         self.attr.fg_color.apply_to_context(&context);
-        context.translate(padding.left, padding.top);
         show_pango_layout(&context, &layout);
 
         Ok(())
     }
 }
 
+// Adds a rectangle with rounded corners to the current path.
+fn rounded_rectangle(context: &Context, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+    // The corners can't be bigger than half of the rectangle.
+    let radius = radius.min(width / 2.0).min(height / 2.0);
+    context.new_sub_path();
+    context.arc(x + width - radius, y + radius, radius, -FRAC_PI_2, 0.0);
+    context.arc(
+        x + width - radius,
+        y + height - radius,
+        radius,
+        0.0,
+        FRAC_PI_2,
+    );
+    context.arc(x + radius, y + height - radius, radius, FRAC_PI_2, PI);
+    context.arc(x + radius, y + radius, radius, PI, 3.0 * FRAC_PI_2);
+    context.close_path();
+}
+
+/// A PNG image, drawn by a [`Text`] created with [`Text::image()`].
+///
+/// The image is scaled to the given height, preserving its aspect ratio. This
+/// allows widgets to show crisp icons rather than relying on glyphs from the
+/// font.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageContent {
+    /// The path of the PNG file
+    pub path: PathBuf,
+    /// The height (in pixels) to draw the image at
+    pub height: f64,
+}
+
+impl ImageContent {
+    /// Creates a new `ImageContent` for the PNG at `path`, drawn `height`
+    /// pixels tall.
+    pub fn new<P: Into<PathBuf>>(path: P, height: f64) -> ImageContent {
+        ImageContent {
+            path: path.into(),
+            height,
+        }
+    }
+
+    // Returns the width and height of the image once scaled.
+    fn size(&self, image: &LoadedImage) -> (f64, f64) {
+        let scale = self.height / f64::from(image.0.height().max(1));
+        (f64::from(image.0.width()) * scale, self.height)
+    }
+
+    fn render(&self, context: &Context, image: &LoadedImage) -> Result<()> {
+        let scale = self.height / f64::from(image.0.height().max(1));
+        context.scale(scale, scale);
+        context.set_source_surface(&*image.0, 0.0, 0.0)?;
+        context.paint()?;
+        Ok(())
+    }
+}
+
+// A decoded PNG. Texts are computed far less often than they are drawn, so
+// images are decoded when the text is computed and kept until it changes.
+#[derive(Clone, Debug)]
+pub(crate) struct LoadedImage(Rc<ImageSurface>);
+
+impl LoadedImage {
+    fn load(path: &Path) -> Result<LoadedImage> {
+        let mut file = File::open(path)
+            .with_context(|| format!("Failed to open image: {}", path.display()))?;
+        let surface = ImageSurface::create_from_png(&mut file)
+            .with_context(|| format!("Failed to load PNG: {}", path.display()))?;
+        Ok(LoadedImage(Rc::new(surface)))
+    }
+}
+
+impl PartialEq for LoadedImage {
+    fn eq(&self, other: &LoadedImage) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ThresholdValue {
-    pub threshold: u8,
+    pub threshold: u32,
     pub color: Color,
 }
 
@@ -261,6 +1426,48 @@ pub struct Threshold {
     pub high: ThresholdValue,
 }
 
+impl Threshold {
+    /// Returns the color of the band that `value` falls into
+    ///
+    /// Values up to and including `low.threshold` are `low`, values up to and
+    /// including `normal.threshold` are `normal` and anything else is `high`.
+    pub fn color_for(&self, value: u32) -> &Color {
+        if value <= self.low.threshold {
+            &self.low.color
+        } else if value <= self.normal.threshold {
+            &self.normal.color
+        } else {
+            &self.high.color
+        }
+    }
+
+    /// Returns a color linearly interpolated between the band colors
+    ///
+    /// Unlike [`Threshold::color_for()`], this gives a smooth gradient: the
+    /// color moves from `low.color` at `low.threshold` to `normal.color` at
+    /// `normal.threshold`, and on to `high.color` at `high.threshold`. Values
+    /// outside of that range are clamped to the nearest band's color.
+    pub fn lerp(&self, value: u32) -> Color {
+        let bands = [&self.low, &self.normal, &self.high];
+        if value <= self.low.threshold {
+            return self.low.color.clone();
+        }
+        for pair in bands.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            if value <= to.threshold {
+                let range = f64::from(to.threshold) - f64::from(from.threshold);
+                let t = if range > 0.0 {
+                    (f64::from(value) - f64::from(from.threshold)) / range
+                } else {
+                    1.0
+                };
+                return from.color.blend(&to.color, t);
+            }
+        }
+        self.high.color.clone()
+    }
+}
+
 impl Default for Threshold {
     fn default() -> Self {
         Threshold {
@@ -279,3 +1486,166 @@ impl Default for Threshold {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Alignment, Animation, Attributes, Color, LayoutCache, LayoutKey, RichText, Text, TextStyle,
+        Threshold, ThresholdValue,
+    };
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn layout_cache_skips_identical_text() {
+        let mut cache = LayoutCache::default();
+        let layouts = Cell::new(0);
+        let key = || LayoutKey {
+            text: "text".to_owned(),
+            font: "Sans 12".to_owned(),
+            markup: true,
+            styles: None,
+            width_chars: None,
+        };
+        let layout = || {
+            layouts.set(layouts.get() + 1);
+            Ok((10.0, 12.0, true))
+        };
+
+        assert_eq!(
+            cache.get_or_insert_with(key(), layout).unwrap(),
+            (10.0, 12.0, true)
+        );
+        assert_eq!(
+            cache.get_or_insert_with(key(), layout).unwrap(),
+            (10.0, 12.0, true)
+        );
+        assert_eq!(layouts.get(), 1);
+
+        let other = LayoutKey {
+            markup: false,
+            ..key()
+        };
+        cache.get_or_insert_with(other, layout).unwrap();
+        assert_eq!(layouts.get(), 2);
+    }
+
+    #[test]
+    fn rich_text_spans() {
+        let rich_text = RichText::new()
+            .push("CPU: ")
+            .push_styled("95°", &[TextStyle::Bold, TextStyle::Italic]);
+        assert_eq!(rich_text.text(), "CPU: 95°");
+        assert_eq!(
+            rich_text.spans,
+            vec![(5..9, TextStyle::Bold), (5..9, TextStyle::Italic)]
+        );
+    }
+
+    #[test]
+    fn empty_texts_are_blank() {
+        let attr = Attributes::default();
+        assert!(Text::new(attr.clone(), "").is_blank());
+        assert!(!Text::new(attr.clone(), " ").is_blank());
+        assert!(!Text::new(attr.clone(), "").with_stretch(true).is_blank());
+        assert!(!Text::new(attr.clone(), "").with_width_chars(3).is_blank());
+        let attr = attr.with_bg_color(Some(Color::red()));
+        assert!(!Text::new(attr, "").is_blank());
+    }
+
+    #[test]
+    fn alignment_offsets() {
+        assert_eq!(Alignment::Left.offset(20.0, 100.0), 0.0);
+        assert_eq!(Alignment::Center.offset(20.0, 100.0), 40.0);
+        assert_eq!(Alignment::Right.offset(20.0, 100.0), 80.0);
+        // Content wider than its space isn't moved.
+        assert_eq!(Alignment::Right.offset(120.0, 100.0), 0.0);
+    }
+
+    #[test]
+    fn blink_alternates_each_half_period() {
+        let blink = Animation::Blink {
+            period: Duration::from_secs(1),
+        };
+        assert_eq!(blink.step(), Duration::from_millis(500));
+        assert!(blink.is_visible(Duration::from_millis(0)));
+        assert!(blink.is_visible(Duration::from_millis(499)));
+        assert!(!blink.is_visible(Duration::from_millis(500)));
+        assert!(blink.is_visible(Duration::from_millis(1000)));
+    }
+
+    #[test]
+    fn hex_colors() {
+        assert_eq!(
+            Color::try_from_hex("#fa0").unwrap(),
+            Color::from_rgb(0xff, 0xaa, 0x00)
+        );
+        assert_eq!(
+            Color::try_from_hex("1e1e2e").unwrap(),
+            Color::from_rgb(0x1e, 0x1e, 0x2e)
+        );
+        assert_eq!(
+            Color::try_from_hex("#1E1E2E80").unwrap(),
+            Color::from_rgba(0x1e, 0x1e, 0x2e, 0x80)
+        );
+    }
+
+    #[test]
+    fn colors_to_hex() {
+        assert_eq!(Color::from_rgb(0x1e, 0x1e, 0x2e).to_hex(), "#1E1E2E");
+        assert_eq!(
+            Color::from_rgba(0x1e, 0x1e, 0x2e, 0x80).to_hex(),
+            "#1E1E2E80"
+        );
+        assert_eq!(
+            format!("{:?}", Color::from_rgba(0x1e, 0x1e, 0x2e, 0x80)),
+            "Color(#1E1E2E80)"
+        );
+    }
+
+    #[test]
+    fn invalid_hex_colors() {
+        for hex in [
+            "",
+            "#",
+            "#12",
+            "#1234",
+            "#1e1e2",
+            "#1e1e2e8",
+            "#ggg",
+            "#1e1e2e+0",
+        ] {
+            assert!(
+                Color::try_from_hex(hex).is_err(),
+                "{hex:?} should be invalid"
+            );
+        }
+        assert_eq!(Color::from_hex("#ggg"), Color::black());
+    }
+
+    #[test]
+    fn thresholds_beyond_u8() {
+        // e.g. ping latencies, in milliseconds.
+        let threshold = Threshold {
+            low: ThresholdValue {
+                threshold: 100,
+                color: Color::green(),
+            },
+            normal: ThresholdValue {
+                threshold: 500,
+                color: Color::yellow(),
+            },
+            high: ThresholdValue {
+                threshold: 2000,
+                color: Color::red(),
+            },
+        };
+
+        assert_eq!(threshold.color_for(300), &Color::yellow());
+        assert_eq!(threshold.color_for(2000), &Color::red());
+        assert_eq!(
+            threshold.lerp(1250),
+            Color::yellow().blend(&Color::red(), 0.5)
+        );
+    }
+}
@@ -1,35 +1,85 @@
 use anyhow::{Context, Result};
 use futures::stream::StreamExt;
-use xcb_util::ewmh;
+use std::collections::HashMap;
+use xcb_util::{ewmh, icccm};
 
 use crate::text::{Attributes, Text};
 use crate::widgets::{Widget, WidgetStream};
-use crate::xcb::xcb_properties_stream;
+use crate::xcb::{shared_connection, xcb_properties_stream};
 
 /// Shows the title of the currently focused window.
 ///
-/// This widget shows the title (`_NET_WM_NAME` [`EWMH`] property) of the
-/// currently focused window. It uses the `_NET_ACTIVE_WINDOW` [`EWMH`] property
-/// of the root window to determine which window is currently focused.
+/// This widget shows the title of the currently focused window. It uses the
+/// first non-empty value of the `_NET_WM_VISIBLE_NAME` and `_NET_WM_NAME`
+/// [`EWMH`] properties, and the ICCCM `WM_NAME` property. It uses the
+/// `_NET_ACTIVE_WINDOW` [`EWMH`] property of the root window to determine which
+/// window is currently focused.
 ///
-/// The widgets content stretches to fill all available space. If the title is
-/// too large for the available space, it will be truncated.
+/// By default, the widgets content stretches to fill all available space. If
+/// the title is too large for the available space, it will be truncated. Use
+/// [`with_max_width()`] to instead give the title a fixed maximum width.
+///
+/// Use [`with_class_glyphs()`] to prefix the title with a glyph chosen by the
+/// window's class.
+///
+/// [`with_max_width()`]: #method.with_max_width
+/// [`with_class_glyphs()`]: #method.with_class_glyphs
 ///
 /// [`EWMH`]: https://specifications.freedesktop.org/wm-spec/wm-spec-latest.html
 pub struct ActiveWindowTitle {
     attr: Attributes,
+    max_width: Option<f64>,
+    class_glyphs: Option<HashMap<String, String>>,
 }
 
 impl ActiveWindowTitle {
     /// Creates a new Active Window Title widget.
     pub fn new(attr: Attributes) -> ActiveWindowTitle {
-        ActiveWindowTitle { attr }
+        ActiveWindowTitle {
+            attr,
+            max_width: None,
+            class_glyphs: None,
+        }
+    }
+
+    /// Returns a new Active Window Title widget that is at most `max_width`
+    /// pixels wide.
+    ///
+    /// The title no longer stretches to fill the available space, so that it
+    /// doesn't push other widgets around. Titles that are too wide are
+    /// ellipsized.
+    pub fn with_max_width(self, max_width: f64) -> ActiveWindowTitle {
+        ActiveWindowTitle {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    /// Returns a new Active Window Title widget that prefixes the title with
+    /// a glyph based on the window's class.
+    ///
+    /// `class_glyphs` maps the class of a window (as given by the ICCCM
+    /// `WM_CLASS` property, e.g. `Alacritty` or `firefox`) to the glyph to
+    /// show before its title. Windows whose class isn't in the map are shown
+    /// without a glyph.
+    pub fn with_class_glyphs(self, class_glyphs: HashMap<String, String>) -> ActiveWindowTitle {
+        ActiveWindowTitle {
+            class_glyphs: Some(class_glyphs),
+            ..self
+        }
+    }
+
+    // Returns the glyph for `window`'s class, if it has one.
+    fn class_glyph(&self, conn: &ewmh::Connection, window: xcb::Window) -> Option<&str> {
+        let class_glyphs = self.class_glyphs.as_ref()?;
+        let class = icccm::get_wm_class(conn, window).get_reply().ok()?;
+        class_glyphs.get(class.class()).map(String::as_str)
     }
 
     fn on_change(&self, conn: &ewmh::Connection, screen_idx: i32) -> Vec<Text> {
         let title = ewmh::get_active_window(conn, screen_idx)
             .get_reply()
-            .and_then(|active_window| {
+            .map(|active_window| {
                 // xcb_properties_stream() will only register for notifications on the
                 // root window, so will only receive notifications when the active window
                 // changes. So, for each active window we see, register for property
@@ -40,28 +90,71 @@ impl ActiveWindowTitle {
                 xcb::change_window_attributes(conn, active_window, &attributes);
                 conn.flush();
 
-                ewmh::get_wm_name(conn, active_window).get_reply()
+                let title = window_title(conn, active_window);
+                match self.class_glyph(conn, active_window) {
+                    Some(glyph) => format!("{glyph} {title}"),
+                    None => title,
+                }
             })
-            .map(|reply| reply.string().to_owned())
             .unwrap_or_else(|_| "".to_owned());
 
-        vec![Text {
-            attr: self.attr.clone(),
-            text: title,
-            stretch: true,
-            markup: false,
-        }]
+        let text = Text::new(self.attr.clone(), title).with_markup(false);
+        let text = match self.max_width {
+            Some(max_width) => text.with_max_width(max_width),
+            None => text.with_stretch(true),
+        };
+        vec![text]
     }
 }
 
+// Returns the first non-empty title of `window`, trying the title as decorated
+// by the WM, then the title set by the client, then the legacy ICCCM title.
+fn window_title(conn: &ewmh::Connection, window: xcb::Window) -> String {
+    let visible_name = ewmh::get_wm_visible_name(conn, window)
+        .get_reply()
+        .map(|reply| reply.string().to_owned());
+    let name = || {
+        ewmh::get_wm_name(conn, window)
+            .get_reply()
+            .map(|reply| reply.string().to_owned())
+    };
+    let icccm_name = || {
+        icccm::get_wm_name(conn, window)
+            .get_reply()
+            .map(|reply| reply.name().to_owned())
+    };
+
+    visible_name
+        .ok()
+        .filter(|title| !title.is_empty())
+        .or_else(|| name().ok().filter(|title| !title.is_empty()))
+        .or_else(|| icccm_name().ok().filter(|title| !title.is_empty()))
+        .unwrap_or_default()
+}
+
 impl Widget for ActiveWindowTitle {
+    fn name(&self) -> &str {
+        "ActiveWindowTitle"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
-        let properties = &["_NET_ACTIVE_WINDOW", "_NET_WM_NAME"];
-        let screen_idx = 0; // XXX assume
-        let (conn, stream) =
-            xcb_properties_stream(properties).context("Initialising ActiveWindowtitle")?;
+        let properties = &[
+            "_NET_ACTIVE_WINDOW",
+            "_NET_WM_VISIBLE_NAME",
+            "_NET_WM_NAME",
+            "WM_NAME",
+            "WM_CLASS",
+        ];
+        let (_, screen_idx) = shared_connection().context("Initialising ActiveWindowTitle")?;
+        let stream = xcb_properties_stream(screen_idx, properties)
+            .context("Initialising ActiveWindowTitle")?;
 
-        let stream = stream.map(move |()| Ok(self.on_change(&conn, screen_idx)));
+        // Fetch the connection each time, as it changes if the bar
+        // reconnects to the X server.
+        let stream = stream.map(move |()| {
+            let (conn, screen_idx) = shared_connection()?;
+            Ok(self.on_change(&conn, screen_idx))
+        });
 
         Ok(Box::pin(stream))
     }
@@ -1,51 +1,147 @@
 use anyhow::Result;
+use chrono::Timelike;
 use std::time::Duration;
-use tokio::time;
+use tokio::time::{self, Instant};
 use tokio_stream::wrappers::IntervalStream;
-use tokio_stream::StreamExt;
+use tokio_stream::{self as stream, StreamExt};
 
 use crate::text::{Attributes, Text};
 use crate::widgets::{Widget, WidgetStream};
 
+/// How often the [`Clock`] widget updates.
+///
+/// Choose `Seconds` if the format string shows seconds (e.g. contains `%S`),
+/// otherwise `Minutes` avoids waking up every second for no reason.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Precision {
+    /// Update once a minute, on the minute.
+    Minutes,
+    /// Update once a second, on the second.
+    Seconds,
+}
+
+impl Precision {
+    fn period(self) -> Duration {
+        match self {
+            Precision::Minutes => Duration::from_secs(60),
+            Precision::Seconds => Duration::from_secs(1),
+        }
+    }
+
+    // Returns how long it is until the start of the next whole second/minute,
+    // so that the displayed time is never stale by nearly a full unit.
+    fn until_next_boundary(self) -> Duration {
+        let now = chrono::Local::now();
+        // nanosecond() may exceed one second during a leap second.
+        let nanos = now.nanosecond().min(999_999_999);
+        let until_next_second = Duration::from_secs(1) - Duration::from_nanos(u64::from(nanos));
+        match self {
+            Precision::Seconds => until_next_second,
+            Precision::Minutes => {
+                let whole_seconds = 59 - u64::from(now.second().min(59));
+                until_next_second + Duration::from_secs(whole_seconds)
+            }
+        }
+    }
+}
+
+// The timezone a clock shows the time in.
+enum Zone {
+    Local,
+    #[cfg(feature = "timezones")]
+    Tz(chrono_tz::Tz),
+}
+
 /// Shows the current time and date.
 ///
 /// This widget shows the current time and date, in the form `%Y-%m-%d %a %I:%M
 /// %p`, e.g. `2017-09-01 Fri 12:51 PM`.
+///
+/// With the `timezones` feature, [`Clock::with_timezones()`] shows several
+/// clocks at once, e.g. the local time alongside UTC.
 pub struct Clock {
     attr: Attributes,
-    format_str: Option<String>,
+    clocks: Vec<(Zone, String)>,
+    precision: Precision,
 }
 
 impl Clock {
     // Creates a new Clock widget.
-    pub fn new(attr: Attributes, format_str: Option<String>) -> Self {
-        Self { attr, format_str }
+    pub fn new(attr: Attributes, format_str: Option<String>, precision: Precision) -> Self {
+        let format_str = format_str.unwrap_or_else(|| "%Y-%m-%d %a %I:%M %p".to_owned());
+        Self {
+            attr,
+            clocks: vec![(Zone::Local, format_str)],
+            precision,
+        }
+    }
+
+    /// Creates a new Clock widget which shows the time in several timezones.
+    ///
+    /// Each `(timezone, format)` pair is shown as a separate text, in order.
+    /// A timezone of `None` shows the local time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cnx::text::Attributes;
+    /// # use cnx::widgets::{Clock, Precision};
+    /// let clock = Clock::with_timezones(
+    ///     Attributes::default(),
+    ///     vec![
+    ///         (None, "%H:%M".to_owned()),
+    ///         (Some(chrono_tz::UTC), "UTC %H:%M".to_owned()),
+    ///         (Some(chrono_tz::America::New_York), "NYC %H:%M".to_owned()),
+    ///     ],
+    ///     Precision::Minutes,
+    /// );
+    /// ```
+    #[cfg(feature = "timezones")]
+    pub fn with_timezones(
+        attr: Attributes,
+        clocks: Vec<(Option<chrono_tz::Tz>, String)>,
+        precision: Precision,
+    ) -> Self {
+        let clocks = clocks
+            .into_iter()
+            .map(|(tz, format_str)| (tz.map_or(Zone::Local, Zone::Tz), format_str))
+            .collect();
+        Self {
+            attr,
+            clocks,
+            precision,
+        }
     }
 
     fn tick(&self) -> Vec<Text> {
-        let now = chrono::Local::now();
-        let format_time: String = self
-            .format_str
-            .clone()
-            .map_or("%Y-%m-%d %a %I:%M %p".to_string(), |item| item);
-        let text = now.format(&format_time).to_string();
-        let texts = vec![Text {
-            attr: self.attr.clone(),
-            text,
-            stretch: false,
-            markup: true,
-        }];
-        texts
+        let now = chrono::Utc::now();
+        self.clocks
+            .iter()
+            .map(|(zone, format_str)| {
+                let text = match zone {
+                    Zone::Local => now.with_timezone(&chrono::Local).format(format_str),
+                    #[cfg(feature = "timezones")]
+                    Zone::Tz(tz) => now.with_timezone(tz).format(format_str),
+                };
+                Text::new(self.attr.clone(), text.to_string())
+            })
+            .collect()
     }
 }
 
 impl Widget for Clock {
+    fn name(&self) -> &str {
+        "Clock"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
-        // As we're not showing seconds, we can sleep for however long
-        // it takes until the minutes changes between updates.
-        let one_minute = Duration::from_secs(60);
-        let interval = time::interval(one_minute);
-        let stream = IntervalStream::new(interval).map(move |_| Ok(self.tick()));
+        // Show the time immediately, then update on each boundary of the
+        // chosen precision. Sleeping until the boundary means we only wake
+        // up when the displayed value actually changes.
+        let start = Instant::now() + self.precision.until_next_boundary();
+        let interval = time::interval_at(start, self.precision.period());
+        let ticks = IntervalStream::new(interval).map(|_| ());
+        let stream = stream::once(()).chain(ticks).map(move |()| Ok(self.tick()));
 
         Ok(Box::pin(stream))
     }
@@ -0,0 +1,195 @@
+use anyhow::Result;
+use std::time::Duration;
+use tokio_stream::{StreamExt, StreamMap};
+
+use crate::text::{Attributes, OnClick, Text};
+use crate::widgets::{Cleanup, InputHandler, Widget, WidgetStream};
+
+/// Shows several widgets together, as a single block on the bar.
+///
+/// The content of each widget is shown in order, on the background of the
+/// `Combine`'s own [`Attributes`]. The left and right padding of those
+/// attributes is added around the whole block. The widgets' own fonts,
+/// colors and padding are kept.
+///
+/// The block is updated whenever any of the widgets yields new content.
+/// Errors from any of the widgets are passed on, as if they came from the
+/// `Combine` itself. Mouse buttons pressed over a widget's content are passed
+/// to that widget's [`Widget::input_handler()`], and updates are coalesced
+/// using the smallest of the widgets' [`Widget::min_interval()`].
+///
+/// # Examples
+///
+/// ```
+/// # use cnx::text::{Attributes, Background, Color, Padding};
+/// # use cnx::widgets::{Clock, Combine, Precision, Widget};
+/// # use cnx::{Cnx, Position};
+/// let attr = Attributes {
+///     background: Background::Solid(Color::blue()),
+///     padding: Padding::horizontal(8.0),
+///     ..Default::default()
+/// };
+/// let widgets: Vec<Box<dyn Widget>> = vec![
+///     Box::new(Clock::new(Attributes::default(), Some("%H:%M".into()), Precision::Minutes)),
+///     Box::new(Clock::new(Attributes::default(), Some("%d/%m".into()), Precision::Minutes)),
+/// ];
+///
+/// let mut cnx = Cnx::new(Position::Top);
+/// cnx.add_widget(Combine::new(attr, widgets));
+/// ```
+pub struct Combine {
+    attr: Attributes,
+    widgets: Vec<Box<dyn Widget>>,
+    // The widgets' input handlers, in the same order as `widgets`.
+    handlers: Vec<Option<OnClick>>,
+}
+
+impl Combine {
+    /// Creates a new [`Combine`] widget, showing `widgets` in order.
+    pub fn new(attr: Attributes, widgets: Vec<Box<dyn Widget>>) -> Combine {
+        Combine {
+            attr,
+            widgets,
+            handlers: Vec::new(),
+        }
+    }
+}
+
+// Makes mouse buttons pressed over `texts` go to `handler`, unless a text
+// has its own click handler.
+fn attach_handler(texts: &mut [Text], handler: Option<&OnClick>) {
+    if let Some(handler) = handler {
+        for text in texts.iter_mut().filter(|text| text.on_click.is_none()) {
+            text.on_click = Some(handler.clone());
+        }
+    }
+}
+
+// Concatenates the latest content of each widget, giving it all one
+// background.
+fn combine(attr: &Attributes, contents: &[Vec<Text>]) -> Vec<Text> {
+    let mut texts: Vec<Text> = contents
+        .iter()
+        .flatten()
+        .cloned()
+        .map(|mut text| {
+            text.attr.background = attr.background.clone();
+            // Rounding the corners of each text would leave gaps.
+            text.attr.corner_radius = 0.0;
+            text
+        })
+        .collect();
+    if let Some(first) = texts.first_mut() {
+        first.attr.padding.left += attr.padding.left;
+    }
+    if let Some(last) = texts.last_mut() {
+        last.attr.padding.right += attr.padding.right;
+    }
+    texts
+}
+
+impl Widget for Combine {
+    fn name(&self) -> &str {
+        "Combine"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let mut streams = StreamMap::with_capacity(self.widgets.len());
+        for (i, widget) in self.widgets.into_iter().enumerate() {
+            streams.insert(i, widget.into_stream()?);
+        }
+
+        // A widget which has ended keeps showing its last content.
+        let mut contents = vec![Vec::new(); streams.len()];
+        let attr = self.attr;
+        let handlers = self.handlers;
+        let stream = streams.map(move |(i, result)| {
+            let mut texts = result?;
+            attach_handler(&mut texts, handlers.get(i).and_then(Option::as_ref));
+            contents[i] = texts;
+            Ok(combine(&attr, &contents))
+        });
+
+        Ok(Box::pin(stream))
+    }
+
+    fn min_interval(&self) -> Option<Duration> {
+        self.widgets
+            .iter()
+            .filter_map(|widget| widget.min_interval())
+            .min()
+    }
+
+    // The bar only knows which widget was clicked, so each widget's handler
+    // is attached to its texts instead.
+    fn input_handler(&mut self) -> Option<InputHandler> {
+        self.handlers = self
+            .widgets
+            .iter_mut()
+            .map(|widget| widget.input_handler().map(OnClick::new))
+            .collect();
+        None
+    }
+
+    fn cleanup(&mut self) -> Option<Cleanup> {
+        let cleanups: Vec<Cleanup> = self
+            .widgets
+            .iter_mut()
+            .filter_map(|widget| widget.cleanup())
+            .collect();
+        if cleanups.is_empty() {
+            return None;
+        }
+        Some(Box::new(move || {
+            for cleanup in cleanups {
+                cleanup();
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{attach_handler, combine};
+    use crate::text::{Attributes, Background, Color, OnClick, Padding, Text};
+
+    #[test]
+    fn combines_with_one_background() {
+        let attr = Attributes {
+            background: Background::Solid(Color::blue()),
+            padding: Padding::horizontal(8.0),
+            ..Default::default()
+        };
+        let child = Attributes {
+            padding: Padding::horizontal(2.0),
+            ..Default::default()
+        };
+        let contents = vec![
+            vec![Text::new(child.clone(), "cpu")],
+            vec![],
+            vec![Text::new(child.clone(), "mem"), Text::new(child, "swap")],
+        ];
+
+        let texts = combine(&attr, &contents);
+        let strings: Vec<_> = texts.iter().map(|text| text.text.as_str()).collect();
+        assert_eq!(strings, vec!["cpu", "mem", "swap"]);
+        assert!(texts
+            .iter()
+            .all(|text| text.attr.background == attr.background));
+        assert_eq!(texts[0].attr.padding, Padding::new(10.0, 2.0, 0.0, 0.0));
+        assert_eq!(texts[1].attr.padding, Padding::horizontal(2.0));
+        assert_eq!(texts[2].attr.padding, Padding::new(2.0, 10.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn attaches_handler_to_texts_without_one() {
+        let handler = OnClick::new(|_| {});
+        let own = Text::new(Attributes::default(), "own").with_click(|_| {});
+        let own_handler = own.on_click.clone();
+        let mut texts = vec![Text::new(Attributes::default(), "cpu"), own];
+
+        attach_handler(&mut texts, Some(&handler));
+        assert_eq!(texts[0].on_click, Some(handler));
+        assert_eq!(texts[1].on_click, own_handler);
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Context, Result};
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::time;
+use tokio_stream::wrappers::IntervalStream;
+use tokio_stream::StreamExt;
+use xcb_util::ewmh;
+
+use crate::text::{Attributes, Text};
+use crate::widgets::{Widget, WidgetStream};
+use crate::xcb::shared_connection;
+
+/// Shows how long the user has been idle.
+///
+/// This widget uses the X `MIT-SCREEN-SAVER` extension to find out how long
+/// it has been since the last keyboard or mouse input, in the form `idle 2m`.
+/// It checks once a second, so it resets soon after input resumes.
+pub struct Idle {
+    attr: Attributes,
+    update_interval: Duration,
+    render: Option<Box<dyn Fn(Duration) -> String>>,
+}
+
+impl Idle {
+    /// Creates a new Idle widget.
+    ///
+    /// The `render` closure receives the time since the last user input, and
+    /// can be used to control the way it is displayed in the bar.
+    pub fn new(attr: Attributes, render: Option<Box<dyn Fn(Duration) -> String>>) -> Self {
+        Self {
+            attr,
+            update_interval: Duration::from_secs(1),
+            render,
+        }
+    }
+
+    fn tick(&self) -> Result<Vec<Text>> {
+        // Fetch the connection each time, as it changes if the bar
+        // reconnects to the X server.
+        let (conn, screen_idx) = shared_connection()?;
+        let root = root_window(&conn, screen_idx)?;
+        let info = xcb::screensaver::query_info(&conn, root)
+            .get_reply()
+            .context("Failed to query MIT-SCREEN-SAVER info")?;
+        let idle = Duration::from_millis(u64::from(info.ms_since_user_input()));
+
+        let text = match &self.render {
+            Some(render) => (render)(idle),
+            None => {
+                let minutes = idle.as_secs() / 60;
+                if minutes < 60 {
+                    format!("idle {minutes}m")
+                } else {
+                    format!("idle {}h{}m", minutes / 60, minutes % 60)
+                }
+            }
+        };
+
+        Ok(vec![
+            Text::new(self.attr.clone(), text).with_markup(self.render.is_some())
+        ])
+    }
+}
+
+fn root_window(conn: &Rc<ewmh::Connection>, screen_idx: i32) -> Result<xcb::Window> {
+    let screen = conn
+        .get_setup()
+        .roots()
+        .nth(screen_idx as usize)
+        .ok_or_else(|| anyhow!("Invalid screen"))?;
+    Ok(screen.root())
+}
+
+impl Widget for Idle {
+    fn name(&self) -> &str {
+        "Idle"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        let (conn, screen_idx) = shared_connection().context("Initialising Idle")?;
+        root_window(&conn, screen_idx).context("Initialising Idle")?;
+
+        let interval = time::interval(self.update_interval);
+        let stream = IntervalStream::new(interval).map(move |_| self.tick());
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Context, Result};
+use tokio_stream::{self as stream, StreamExt};
+use xcb::xkb;
+use xcb_util::ewmh;
+
+use crate::text::{Attributes, Text};
+use crate::widgets::{Widget, WidgetStream};
+use crate::xcb::{shared_connection, xcb_event_stream};
+
+// The bits of the XKB indicator state for each lock. These are the indices of
+// the "Caps Lock" and "Num Lock" indicators in the standard keymaps.
+const CAPS_LOCK_INDICATOR: u32 = 1 << 0;
+const NUM_LOCK_INDICATOR: u32 = 1 << 1;
+
+/// Shows whether Caps Lock and Num Lock are active.
+///
+/// This widget shows `CAPS` and `NUM` while the corresponding lock is active.
+/// By default, inactive locks aren't shown at all, so the widget takes up no
+/// space when neither lock is active. Use [`LockKeys::with_inactive_attr()`]
+/// to show them with different [`Attributes`] instead.
+///
+/// The lock state is read from the X `XKEYBOARD` extension, and the widget is
+/// updated as soon as it is notified that the keyboard indicators changed.
+pub struct LockKeys {
+    attr: Attributes,
+    inactive_attr: Option<Attributes>,
+}
+
+impl LockKeys {
+    /// Creates a new LockKeys widget.
+    ///
+    /// Active locks are shown with the given [`Attributes`].
+    pub fn new(attr: Attributes) -> Self {
+        Self {
+            attr,
+            inactive_attr: None,
+        }
+    }
+
+    /// Shows inactive locks with the given [`Attributes`], rather than hiding
+    /// them.
+    pub fn with_inactive_attr(mut self, attr: Attributes) -> Self {
+        self.inactive_attr = Some(attr);
+        self
+    }
+
+    fn tick(&self) -> Result<Vec<Text>> {
+        // Fetch the connection each time, as it changes if the bar
+        // reconnects to the X server.
+        let (conn, _) = shared_connection()?;
+        let state = xkb::get_indicator_state(&conn, xkb::ID_USE_CORE_KBD as xkb::DeviceSpec)
+            .get_reply()
+            .context("Failed to query XKB indicator state")?
+            .state();
+
+        let locks = [("CAPS", CAPS_LOCK_INDICATOR), ("NUM", NUM_LOCK_INDICATOR)];
+        let texts = locks
+            .iter()
+            .filter_map(|&(name, indicator)| {
+                let attr = if state & indicator != 0 {
+                    &self.attr
+                } else {
+                    self.inactive_attr.as_ref()?
+                };
+                Some(Text::new(attr.clone(), name.to_owned()).with_markup(false))
+            })
+            .collect();
+
+        Ok(texts)
+    }
+}
+
+// Enables the XKB extension and selects its indicator state notifications,
+// returning the response type of XKB's events.
+fn select_indicator_events(conn: &ewmh::Connection) -> Result<u8> {
+    let first_event = conn
+        .get_extension_data(xkb::id())
+        .filter(|data| data.present())
+        .map(|data| data.first_event())
+        .ok_or_else(|| anyhow!("The XKEYBOARD extension is not available"))?;
+
+    let reply = xkb::use_extension(conn, xkb::MAJOR_VERSION as u16, xkb::MINOR_VERSION as u16)
+        .get_reply()
+        .context("Failed to enable the XKEYBOARD extension")?;
+    if !reply.supported() {
+        return Err(anyhow!("The XKEYBOARD extension version is not supported"));
+    }
+
+    let events = xkb::EVENT_TYPE_INDICATOR_STATE_NOTIFY as u16;
+    xkb::select_events(
+        conn,
+        xkb::ID_USE_CORE_KBD as xkb::DeviceSpec,
+        events,
+        0,
+        events,
+        0,
+        0,
+        None,
+    );
+    conn.flush();
+
+    Ok(first_event)
+}
+
+impl Widget for LockKeys {
+    fn name(&self) -> &str {
+        "LockKeys"
+    }
+
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        // All XKB events share a single response type. We've only selected
+        // indicator state notifications, so any of them is a reason to update.
+        let events = xcb_event_stream(select_indicator_events).context("Initialising LockKeys")?;
+        let stream = stream::once(()).chain(events).map(move |()| self.tick());
+
+        Ok(Box::pin(stream))
+    }
+}
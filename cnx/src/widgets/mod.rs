@@ -3,14 +3,24 @@
 mod active_window_title;
 
 mod clock;
+mod combine;
+mod idle;
+mod lock_keys;
 mod pager;
 pub use self::active_window_title::ActiveWindowTitle;
-pub use self::clock::Clock;
+pub use self::clock::{Clock, Precision};
+pub use self::combine::Combine;
+pub use self::idle::Idle;
+pub use self::lock_keys::LockKeys;
 pub use self::pager::Pager;
 use crate::text::Text;
 use anyhow::Result;
 use futures::stream::Stream;
+use std::future::Future;
 use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{self, Instant, Sleep};
 
 /// The stream of `Vec<Text>` returned by each widget.
 ///
@@ -23,6 +33,29 @@ use std::pin::Pin;
 ///
 pub type WidgetStream = Pin<Box<dyn Stream<Item = Result<Vec<Text>>>>>;
 
+/// A mouse button pressed while the pointer is over a widget.
+///
+/// Scrolling is reported by X as presses of buttons 4 and 5.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    ScrollUp,
+    ScrollDown,
+}
+
+/// A handler for mouse buttons pressed over a widget's content.
+///
+/// See [`Widget::input_handler()`].
+pub type InputHandler = Box<dyn Fn(MouseButton)>;
+
+/// A hook which releases the resources held by a widget's stream.
+///
+/// See [`Widget::cleanup()`].
+pub type Cleanup = Box<dyn FnOnce()>;
+
 /// The main trait implemented by all widgets.
 ///
 /// This simple trait defines a widget. A widget is essentially just a
@@ -32,6 +65,233 @@ pub type WidgetStream = Pin<Box<dyn Stream<Item = Result<Vec<Text>>>>>;
 /// See the [`WidgetStream`] type alias for the exact type of stream that
 /// should be returned.
 ///
+/// If the stream ends, the widget's content is removed from the bar. Widgets
+/// which wrap something that may exit (e.g. a process) should restart it
+/// within their stream if they want to keep showing content.
+///
 pub trait Widget {
     fn into_stream(self: Box<Self>) -> Result<WidgetStream>;
+
+    /// Returns the name of the widget, which is used to say which widget an
+    /// error came from when it is logged.
+    ///
+    /// The built-in widgets return their type name, e.g. `"Clock"`.
+    fn name(&self) -> &str {
+        "unnamed"
+    }
+
+    /// Returns the minimum time between updates of the widget's content.
+    ///
+    /// Widgets which react to events (e.g. a volume slider being dragged)
+    /// can yield many updates a second, each of which redraws part of the
+    /// bar. If this returns a duration, updates which arrive sooner than that
+    /// after the previous one are coalesced, and only the latest is shown
+    /// once the duration has passed. See [`debounce()`].
+    ///
+    /// This trades latency for throughput: the first update after a quiet
+    /// period is shown immediately, but during a burst of updates the bar may
+    /// lag behind by up to this duration.
+    ///
+    /// The default implementation returns `None`, so every update is shown.
+    fn min_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Returns a handler for mouse buttons pressed over the widget's content.
+    ///
+    /// This is called once, before [`Widget::into_stream()`]. The handler is
+    /// called on the bar's event loop, so it shouldn't block for long. It
+    /// doesn't return any content: if the handler changes something that the
+    /// widget shows, the widget's stream should notice and yield new content.
+    ///
+    /// The default implementation returns `None`, so the widget ignores
+    /// mouse buttons.
+    fn input_handler(&mut self) -> Option<InputHandler> {
+        None
+    }
+
+    /// Returns a hook which releases any OS resources (mixers, file
+    /// descriptors, bus connections) held by the widget's stream.
+    ///
+    /// This is called once, before [`Widget::into_stream()`]. The
+    /// [`crate::Cnx`] instance calls the hook exactly once: either when the
+    /// widget's stream has ended, in which case the last item it yielded will
+    /// already have been passed to the bar, or when the bar itself shuts
+    /// down, after the stream has been dropped.
+    ///
+    /// As [`Widget::into_stream()`] takes ownership of the widget, the hook
+    /// should share whatever it releases with the stream, e.g. through an
+    /// `Rc`.
+    ///
+    /// The default implementation returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use anyhow::Result;
+    /// # use cnx::text::Text;
+    /// # use cnx::widgets::{Cleanup, Widget, WidgetStream};
+    /// # use std::cell::RefCell;
+    /// # use std::fs::File;
+    /// # use std::rc::Rc;
+    /// # use tokio_stream::StreamExt;
+    /// struct Example {
+    ///     file: Rc<RefCell<Option<File>>>,
+    /// }
+    ///
+    /// impl Widget for Example {
+    ///     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+    ///         let file = self.file;
+    ///         let stream = tokio_stream::iter(0..3).map(move |_| {
+    ///             // Read from `file`, etc.
+    ///             let _ = file.borrow();
+    ///             Ok(Vec::<Text>::new())
+    ///         });
+    ///         Ok(Box::pin(stream))
+    ///     }
+    ///
+    ///     fn cleanup(&mut self) -> Option<Cleanup> {
+    ///         let file = self.file.clone();
+    ///         Some(Box::new(move || drop(file.borrow_mut().take())))
+    ///     }
+    /// }
+    /// ```
+    fn cleanup(&mut self) -> Option<Cleanup> {
+        None
+    }
+}
+
+/// A [`Widget`] built from a closure which returns its stream.
+///
+/// This avoids having to implement [`Widget`] for a one-off widget. The
+/// closure is called once, when the [`crate::Cnx`] instance starts running.
+/// See also [`crate::Cnx::add_stream()`] for adding a stream which has already
+/// been built.
+///
+/// # Examples
+///
+/// ```
+/// # use cnx::{Cnx, Position};
+/// # use cnx::text::{Attributes, Padding, Text};
+/// # use cnx::widgets::{FnWidget, WidgetStream};
+/// # use std::time::Duration;
+/// # use tokio::time;
+/// # use tokio_stream::wrappers::IntervalStream;
+/// # use tokio_stream::StreamExt;
+/// let attr = Attributes {
+///     font: "SourceCodePro 21".into(),
+///     padding: Padding::horizontal(8.0),
+///     ..Default::default()
+/// };
+///
+/// let mut cnx = Cnx::new(Position::Top);
+/// cnx.add_widget(FnWidget::new(move || {
+///     let interval = time::interval(Duration::from_secs(1));
+///     let stream = IntervalStream::new(interval)
+///         .map(move |_| Ok(vec![Text::new(attr.clone(), "tick".to_owned())]));
+///     Ok(Box::pin(stream) as WidgetStream)
+/// }));
+/// ```
+pub struct FnWidget<F> {
+    f: F,
+}
+
+impl<F> FnWidget<F>
+where
+    F: FnOnce() -> Result<WidgetStream>,
+{
+    /// Creates a new widget whose stream is returned by `f`.
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F> Widget for FnWidget<F>
+where
+    F: FnOnce() -> Result<WidgetStream>,
+{
+    fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
+        (self.f)()
+    }
+}
+
+/// Coalesces updates from `stream` which arrive less than `min_interval`
+/// apart.
+///
+/// The returned stream yields an update as soon as one arrives, unless the
+/// previous update was yielded less than `min_interval` ago. In that case,
+/// it waits until `min_interval` has passed and then yields only the latest
+/// update. Errors are yielded immediately, so that none are lost.
+///
+/// This is used for widgets which return a [`Widget::min_interval()`].
+pub fn debounce(stream: WidgetStream, min_interval: Duration) -> WidgetStream {
+    Box::pin(Debounce {
+        stream,
+        min_interval,
+        delay: Box::pin(time::sleep(Duration::ZERO)),
+        pending: None,
+        ended: false,
+    })
+}
+
+struct Debounce {
+    stream: WidgetStream,
+    min_interval: Duration,
+    // Completes once `min_interval` has passed since the last update.
+    delay: Pin<Box<Sleep>>,
+    // The latest update, which hasn't been yielded yet.
+    pending: Option<Vec<Text>>,
+    ended: bool,
+}
+
+impl Stream for Debounce {
+    type Item = Result<Vec<Text>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Take every update that is ready, keeping only the latest.
+        while !self.ended {
+            match self.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(texts))) => self.pending = Some(texts),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
+                Poll::Ready(None) => self.ended = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if self.pending.is_some() {
+            if self.delay.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            let deadline = Instant::now() + self.min_interval;
+            self.delay.as_mut().reset(deadline);
+            return Poll::Ready(self.pending.take().map(Ok));
+        }
+
+        if self.ended {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::debounce;
+    use crate::text::{Attributes, Text};
+    use std::time::Duration;
+    use tokio_stream::StreamExt;
+
+    fn update(text: &str) -> anyhow::Result<Vec<Text>> {
+        Ok(vec![Text::new(Attributes::default(), text.to_owned())])
+    }
+
+    #[tokio::test]
+    async fn debounce_keeps_latest_update() {
+        let stream = tokio_stream::iter(vec![update("1"), update("2"), update("3")]);
+        let debounced = debounce(Box::pin(stream), Duration::from_millis(10));
+        let updates: Vec<_> = debounced.map(Result::unwrap).collect().await;
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0][0].text, "3");
+    }
 }
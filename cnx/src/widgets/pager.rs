@@ -1,11 +1,12 @@
 use anyhow::{Context, Result};
 use futures::stream::StreamExt;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use xcb_util::ewmh;
 
-use crate::text::{Attributes, PagerAttributes, Text};
-use crate::widgets::{Widget, WidgetStream};
-use crate::xcb::xcb_properties_stream;
+use crate::text::{escape_markup, Attributes, PagerAttributes, Text};
+use crate::widgets::{InputHandler, MouseButton, Widget, WidgetStream};
+use crate::xcb::{shared_connection, xcb_properties_stream};
 
 /// Shows the WM's workspaces/groups.
 ///
@@ -16,11 +17,20 @@ use crate::xcb::xcb_properties_stream;
 /// `_NET_NUMBER_OF_DESKTOPS`, `_NET_DESKTOP_NAMES`, `_NET_CURRENT_DESKTOP`,
 /// `_NET_CLIENT_LIST`, `_NET_WM_WINDOW_TYPE` and `_NET_WM_DESKTOP` properties.
 ///
+/// Some WMs provide a single large desktop which is scrolled around in
+/// screen-sized viewports, rather than several desktops. For these WMs, as
+/// determined by the `_NET_DESKTOP_GEOMETRY` and `_NET_DESKTOP_VIEWPORT`
+/// properties, the Pager shows one entry for each viewport instead. This
+/// only happens if there is at most one desktop.
+///
 /// [`EWMH`]: https://specifications.freedesktop.org/wm-spec/wm-spec-latest.html
 pub struct Pager {
     active_attr: Attributes,
     inactive_attr: Attributes,
     non_empty_attr: Attributes,
+    screen_idx: Option<i32>,
+    show_window_counts: bool,
+    scroll_desktops: bool,
 }
 
 impl Pager {
@@ -30,10 +40,55 @@ impl Pager {
             active_attr: pager_attrs.active_attr,
             inactive_attr: pager_attrs.inactive_attr,
             non_empty_attr: pager_attrs.non_empty_attr,
+            screen_idx: None,
+            show_window_counts: false,
+            scroll_desktops: false,
+        }
+    }
+
+    /// Returns a new Pager widget which appends the number of windows on
+    /// each non-empty desktop to its name, e.g. `code (2)`.
+    pub fn with_window_counts(self) -> Self {
+        Self {
+            show_window_counts: true,
+            ..self
+        }
+    }
+
+    /// Returns a new Pager widget which moves to the previous (scrolling up)
+    /// or next (scrolling down) desktop when scrolled over, wrapping around
+    /// at either end.
+    ///
+    /// If the Pager is showing viewports, it moves between them instead.
+    pub fn with_scroll_desktops(self) -> Self {
+        Self {
+            scroll_desktops: true,
+            ..self
         }
     }
 
+    /// Returns a new Pager widget which shows the desktops of `screen_idx`.
+    ///
+    /// By default, the Pager shows the desktops of the screen that the bar
+    /// is on. On a multi-head setup with separate X screens, this can be used
+    /// to show the desktops of a different screen.
+    pub fn with_screen_idx(self, screen_idx: i32) -> Self {
+        Self {
+            screen_idx: Some(screen_idx),
+            ..self
+        }
+    }
+
+    // Returns the screen whose desktops are shown.
+    fn shown_screen_idx(&self, bar_screen_idx: i32) -> i32 {
+        self.screen_idx.unwrap_or(bar_screen_idx)
+    }
+
     fn on_change(&self, conn: &ewmh::Connection, screen_idx: i32) -> Vec<Text> {
+        if let Some(texts) = self.viewports(conn, screen_idx) {
+            return texts;
+        }
+
         let number = ewmh::get_number_of_desktops(conn, screen_idx)
             .get_reply()
             .unwrap_or(0) as usize;
@@ -57,64 +112,231 @@ impl Pager {
             }
         }
 
+        let window_counts = non_empty_desktops(conn, screen_idx);
+
         names
             .into_iter()
             .enumerate()
             .map(|(i, name)| {
+                let count = window_counts.get(&(i as u32)).copied();
                 let attr = if i == current {
                     self.active_attr.clone()
-                } else if non_empty_desktops(conn, screen_idx).contains(&(i as u32)) {
+                } else if count.is_some() {
                     self.non_empty_attr.clone()
                 } else {
                     self.inactive_attr.clone()
                 };
 
-                Text {
-                    attr,
-                    text: name.to_owned(),
-                    stretch: false,
-                    markup: true,
-                }
+                let text = match count {
+                    Some(count) if self.show_window_counts => {
+                        format!("{} ({count})", escape_markup(name))
+                    }
+                    _ => escape_markup(name),
+                };
+                Text::new(attr, text)
             })
             .collect()
     }
+
+    // Shows an entry for each viewport of the current desktop, if the WM
+    // uses viewports.
+    fn viewports(&self, conn: &ewmh::Connection, screen_idx: i32) -> Option<Vec<Text>> {
+        let viewports = Viewports::get(conn, screen_idx)?;
+        let texts = (0..viewports.count())
+            .map(|cell| {
+                let attr = if cell == viewports.active {
+                    self.active_attr.clone()
+                } else {
+                    self.inactive_attr.clone()
+                };
+                Text::new(attr, (cell + 1).to_string())
+            })
+            .collect();
+        Some(texts)
+    }
 }
 
-fn non_empty_desktops(conn: &ewmh::Connection, screen_idx: i32) -> Vec<u32> {
+// The screen-sized viewports of a desktop which is larger than the screen,
+// numbered from left to right and then top to bottom.
+struct Viewports {
+    columns: u32,
+    rows: u32,
+    // The viewport which is currently shown.
+    active: u32,
+    screen_width: u32,
+    screen_height: u32,
+}
+
+impl Viewports {
+    // Returns the viewports of the current desktop, if it is larger than the
+    // screen. WMs with several desktops may also make each of them larger
+    // than the screen, in which case the desktops are used rather than the
+    // viewports.
+    fn get(conn: &ewmh::Connection, screen_idx: i32) -> Option<Viewports> {
+        let number = ewmh::get_number_of_desktops(conn, screen_idx)
+            .get_reply()
+            .unwrap_or(0);
+        if number > 1 {
+            return None;
+        }
+
+        let (desktop_width, desktop_height) = ewmh::get_desktop_geometry(conn, screen_idx)
+            .get_reply()
+            .ok()?;
+        let screen = conn.get_setup().roots().nth(screen_idx as usize)?;
+        let screen_width = u32::from(screen.width_in_pixels()).max(1);
+        let screen_height = u32::from(screen.height_in_pixels()).max(1);
+
+        let columns = (desktop_width / screen_width).max(1);
+        let rows = (desktop_height / screen_height).max(1);
+        if columns * rows <= 1 {
+            return None;
+        }
+
+        // `_NET_DESKTOP_VIEWPORT` has the top-left corner of the viewport
+        // of each desktop.
+        let current = ewmh::get_current_desktop(conn, screen_idx)
+            .get_reply()
+            .unwrap_or(0) as usize;
+        let viewports = ewmh::get_desktop_viewport(conn, screen_idx)
+            .get_reply()
+            .ok()?;
+        let viewport = viewports.desktop_viewports().get(current)?;
+        let active = (viewport.y() / screen_height) * columns + viewport.x() / screen_width;
+
+        Some(Viewports {
+            columns,
+            rows,
+            active: active.min(columns * rows - 1),
+            screen_width,
+            screen_height,
+        })
+    }
+
+    fn count(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    // Returns the top-left corner of viewport `cell`.
+    fn position(&self, cell: u32) -> (u32, u32) {
+        (
+            (cell % self.columns) * self.screen_width,
+            (cell / self.columns) * self.screen_height,
+        )
+    }
+}
+
+// Asks the WM to switch to the viewport before or after the current one.
+fn change_viewport(conn: &ewmh::Connection, screen_idx: i32, viewports: &Viewports, forward: bool) {
+    let count = viewports.count();
+    let next = if forward {
+        (viewports.active + 1) % count
+    } else {
+        (viewports.active + count - 1) % count
+    };
+    let (x, y) = viewports.position(next);
+    let root = match conn.get_setup().roots().nth(screen_idx as usize) {
+        Some(screen) => screen.root(),
+        None => return,
+    };
+    // xcb-util doesn't wrap `xcb_ewmh_request_change_desktop_viewport()`.
+    ewmh::send_client_message(conn, root, root, conn.DESKTOP_VIEWPORT(), &[x, y]);
+    conn.flush();
+}
+
+// Asks the WM to switch to the desktop before or after the current one.
+fn change_desktop(conn: &ewmh::Connection, screen_idx: i32, forward: bool) {
+    let number = ewmh::get_number_of_desktops(conn, screen_idx)
+        .get_reply()
+        .unwrap_or(0);
+    if number == 0 {
+        return;
+    }
+    let current = ewmh::get_current_desktop(conn, screen_idx)
+        .get_reply()
+        .unwrap_or(0);
+    let next = if forward {
+        (current + 1) % number
+    } else {
+        (current + number - 1) % number
+    };
+    ewmh::request_change_current_desktop(conn, screen_idx, next, xcb::CURRENT_TIME);
+    conn.flush();
+}
+
+/// Returns the number of windows on each non-empty desktop.
+///
+/// Docks and desktop windows (such as the bar itself) aren't counted.
+fn non_empty_desktops(conn: &ewmh::Connection, screen_idx: i32) -> HashMap<u32, usize> {
     let client_list = ewmh::get_client_list(conn, screen_idx).get_reply();
     let windows: &[u32] = match client_list {
         Ok(ref cl) => cl.windows(),
         Err(_) => &[],
     };
 
-    windows
+    let mut counts = HashMap::new();
+    for desktop in windows
         .iter()
         .filter(|&w| match ewmh::get_wm_window_type(conn, *w).get_reply() {
-            Ok(wt) => wt.atoms().first() == Some(&conn.WM_WINDOW_TYPE_NORMAL()),
-            Err(_) => false,
+            Ok(wt) => !wt.atoms().iter().any(|&atom| {
+                atom == conn.WM_WINDOW_TYPE_DOCK() || atom == conn.WM_WINDOW_TYPE_DESKTOP()
+            }),
+            // Windows without a type are treated as normal windows.
+            Err(_) => true,
         })
         .filter_map(|w| ewmh::get_wm_desktop(conn, *w).get_reply().ok())
-        .filter(
-            |&d| match ewmh::get_current_desktop(conn, screen_idx).get_reply() {
-                Ok(c) => c != d,
-                Err(_) => true,
-            },
-        )
-        .collect()
+    {
+        *counts.entry(desktop).or_insert(0) += 1;
+    }
+    counts
 }
 
 impl Widget for Pager {
+    fn name(&self) -> &str {
+        "Pager"
+    }
+
     fn into_stream(self: Box<Self>) -> Result<WidgetStream> {
         let properties = &[
             "_NET_NUMBER_OF_DESKTOPS",
             "_NET_CURRENT_DESKTOP",
             "_NET_DESKTOP_NAMES",
+            "_NET_CLIENT_LIST",
+            "_NET_DESKTOP_GEOMETRY",
+            "_NET_DESKTOP_VIEWPORT",
         ];
-        let screen_idx = 0; // XXX assume
-        let (conn, stream) = xcb_properties_stream(properties).context("Initialising Pager")?;
+        let (_, bar_screen_idx) = shared_connection().context("Initialising Pager")?;
+        let stream = xcb_properties_stream(self.shown_screen_idx(bar_screen_idx), properties)
+            .context("Initialising Pager")?;
 
-        let stream = stream.map(move |()| Ok(self.on_change(&conn, screen_idx)));
+        // Fetch the connection each time, as it changes if the bar
+        // reconnects to the X server.
+        let stream = stream.map(move |()| {
+            let (conn, bar_screen_idx) = shared_connection()?;
+            Ok(self.on_change(&conn, self.shown_screen_idx(bar_screen_idx)))
+        });
 
         Ok(Box::pin(stream))
     }
+
+    fn input_handler(&mut self) -> Option<InputHandler> {
+        if !self.scroll_desktops {
+            return None;
+        }
+        let screen_idx = self.screen_idx;
+        Some(Box::new(move |button| {
+            let forward = match button {
+                MouseButton::ScrollUp => false,
+                MouseButton::ScrollDown => true,
+                _ => return,
+            };
+            if let Ok((conn, bar_screen_idx)) = shared_connection() {
+                let screen_idx = screen_idx.unwrap_or(bar_screen_idx);
+                match Viewports::get(&conn, screen_idx) {
+                    Some(viewports) => change_viewport(&conn, screen_idx, &viewports, forward),
+                    None => change_desktop(&conn, screen_idx, forward),
+                }
+            }
+        }))
+    }
 }
@@ -1,10 +1,14 @@
 use anyhow::{anyhow, Context as _AnyhowContext, Result};
+use log::warn;
+use std::cell::RefCell;
 use std::os::unix::io::AsRawFd;
 use std::os::unix::io::RawFd;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll};
 use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
 use tokio_stream::{self as stream, Stream, StreamExt};
 use xcb::xproto::{PropertyNotifyEvent, PROPERTY_NOTIFY};
 use xcb_util::ewmh;
@@ -24,10 +28,14 @@ impl AsRawFd for XcbEvented {
 }
 
 // A `Stream` of `xcb::GenericEvent` for the provided `xcb::Connection`.
+//
+// If the connection breaks (e.g. because the X server was restarted), the
+// stream yields an error and then ends.
 pub struct XcbEventStream {
     conn: Rc<ewmh::Connection>,
     poll: AsyncFd<XcbEvented>,
     would_block: bool,
+    broken: bool,
 }
 
 impl XcbEventStream {
@@ -39,15 +47,19 @@ impl XcbEventStream {
             conn,
             poll,
             would_block: true,
+            broken: false,
         })
     }
 }
 
 impl Stream for XcbEventStream {
-    type Item = xcb::GenericEvent;
+    type Item = Result<xcb::GenericEvent>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
         let self_ = &mut *self;
+        if self_.broken {
+            return Poll::Ready(None);
+        }
         let mut ready = None;
         if self_.would_block {
             match self_.poll.poll_read_ready(cx) {
@@ -56,15 +68,23 @@ impl Stream for XcbEventStream {
                     self_.would_block = false;
                 }
                 Poll::Ready(Err(e)) => {
-                    // Unsure when this would happen:
-                    panic!("Error polling xcb::Connection: {e}");
+                    self_.broken = true;
+                    let err = anyhow!(e).context("Error polling xcb::Connection");
+                    return Poll::Ready(Some(Err(err)));
                 }
                 Poll::Pending => return Poll::Pending,
             }
         }
         match self_.conn.poll_for_event() {
-            Some(event) => Poll::Ready(Some(event)),
+            Some(event) => Poll::Ready(Some(Ok(event))),
             None => {
+                // Once the connection has broken, its socket is always
+                // readable but there are never any events, so stop polling.
+                if let Err(err) = self_.conn.has_error() {
+                    self_.broken = true;
+                    let err = anyhow!("Lost connection to the X server: {err}");
+                    return Poll::Ready(Some(Err(err)));
+                }
                 self_.would_block = true;
                 match ready {
                     None => self.poll_next(cx),
@@ -79,61 +99,218 @@ impl Stream for XcbEventStream {
     }
 }
 
-// A `Stream` that listens to `PROPERTY_CHANGE` notifications.
+// The `Bar`'s connection, shared with widgets that watch X properties.
 //
-// By default it listens to `PROPERTY_CHANGE` notifications for the provided
-// `properties` on the root window. The `ewhm::Connection` is returned so that
-// the caller may listen to `PROPERTY_CHANGE` notifications on additional
-// windows.
-pub fn xcb_properties_stream(
-    properties: &[&str],
-) -> Result<(Rc<ewmh::Connection>, impl Stream<Item = ()>)> {
-    let (xcb_conn, screen_idx) =
-        xcb::Connection::connect(None).context("Failed to connect to X server")?;
-    let root_window = xcb_conn
-        .get_setup()
-        .roots()
-        .nth(screen_idx as usize)
-        .ok_or_else(|| anyhow!("Invalid screen"))?
-        .root();
-    let ewmh_conn = ewmh::Connection::connect(xcb_conn)
-        .map_err(|(e, _)| e)
-        .context("Failed to wrap xcb::Connection in ewmh::Connection")?;
-    let conn = Rc::new(ewmh_conn);
-
-    let only_if_exists = true;
-    let properties = properties
-        .iter()
-        .map(|property| -> Result<xcb::Atom> {
-            let reply = xcb::intern_atom(&conn, only_if_exists, property).get_reply()?;
-            Ok(reply.atom())
-        })
-        .collect::<Result<Vec<_>>>()
-        .context("Failed to intern atoms")?;
-
-    // Register for all PROPERTY_CHANGE events. We'll filter out the ones
-    // that are interesting below.
-    let attributes = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE)];
-    xcb::change_window_attributes(&conn, root_window, &attributes);
-    conn.flush();
-
-    let xcb_stream = XcbEventStream::new(conn.clone())?;
-    let stream = xcb_stream.filter_map(move |event| {
-        if event.response_type() == PROPERTY_NOTIFY {
-            let event: &PropertyNotifyEvent = unsafe { xcb::cast_event(&event) };
-            if properties.iter().any(|p| *p == event.atom()) {
-                // We don't actually care about the event, just that
-                // it occurred.
-                return Some(());
+// Widgets don't have access to the `Bar`, so `Cnx` shares its connection here
+// before asking widgets for their streams. As the `Bar` owns the only stream
+// of events for the connection, it passes each event to `dispatch_event()`,
+// which forwards `PROPERTY_NOTIFY` events on to each `xcb_properties_stream()`
+// and other events on to each `xcb_event_stream()` interested in them.
+//
+// If the bar reconnects to the X server, it shares its new connection, and
+// each subscription is set up again on it. Widgets should therefore fetch the
+// connection with `shared_connection()` each time they use it, rather than
+// keeping hold of it.
+struct SharedConnection {
+    conn: Rc<ewmh::Connection>,
+    screen_idx: i32,
+    subscribers: Vec<PropertySubscriber>,
+    event_subscribers: Vec<EventSubscriber>,
+}
+
+// Selects the events a `xcb_event_stream()` is interested in, returning their
+// response type.
+type SelectEvents = Box<dyn Fn(&ewmh::Connection) -> Result<u8>>;
+
+struct PropertySubscriber {
+    screen_idx: i32,
+    properties: Vec<String>,
+    // The atoms for `properties`, which are interned again on reconnecting.
+    atoms: Vec<xcb::Atom>,
+    sender: mpsc::UnboundedSender<()>,
+}
+
+impl PropertySubscriber {
+    // Registers for `PROPERTY_CHANGE` events on the root window, and looks up
+    // the atoms of the properties we're interested in.
+    fn select(&mut self, conn: &ewmh::Connection) -> Result<()> {
+        let root_window = conn
+            .get_setup()
+            .roots()
+            .nth(self.screen_idx as usize)
+            .ok_or_else(|| anyhow!("Invalid screen"))?
+            .root();
+
+        let only_if_exists = true;
+        self.atoms = self
+            .properties
+            .iter()
+            .map(|property| -> Result<xcb::Atom> {
+                let reply = xcb::intern_atom(conn, only_if_exists, property).get_reply()?;
+                Ok(reply.atom())
+            })
+            .collect::<Result<Vec<_>>>()
+            .context("Failed to intern atoms")?;
+
+        // Register for all PROPERTY_CHANGE events. We'll filter out the ones
+        // that are interesting in `dispatch_event()`.
+        let attributes = [(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE)];
+        xcb::change_window_attributes(conn, root_window, &attributes);
+        conn.flush();
+        Ok(())
+    }
+}
+
+struct EventSubscriber {
+    select: SelectEvents,
+    response_type: u8,
+    sender: mpsc::UnboundedSender<()>,
+}
+
+thread_local! {
+    static SHARED: RefCell<Option<SharedConnection>> = RefCell::new(None);
+}
+
+// Shares the `Bar`'s connection with widgets created on this thread.
+//
+// If a connection was already shared, e.g. before the bar reconnected to the
+// X server, existing subscriptions are moved to the new connection and woken
+// up, so that widgets fetch their current state from it.
+pub fn share_connection(conn: Rc<ewmh::Connection>, screen_idx: i32) {
+    SHARED.with(|shared| {
+        let mut shared = shared.borrow_mut();
+        let (mut subscribers, mut event_subscribers) = match shared.take() {
+            Some(old) => (old.subscribers, old.event_subscribers),
+            None => (Vec::new(), Vec::new()),
+        };
+
+        subscribers.retain_mut(|subscriber| match subscriber.select(&conn) {
+            Ok(()) => subscriber.sender.send(()).is_ok(),
+            Err(err) => {
+                warn!("Failed to watch X properties after reconnecting: {err}");
+                false
             }
-        }
+        });
+        event_subscribers.retain_mut(|subscriber| match (subscriber.select)(&conn) {
+            Ok(response_type) => {
+                subscriber.response_type = response_type;
+                subscriber.sender.send(()).is_ok()
+            }
+            Err(err) => {
+                warn!("Failed to select X events after reconnecting: {err}");
+                false
+            }
+        });
+
+        *shared = Some(SharedConnection {
+            conn,
+            screen_idx,
+            subscribers,
+            event_subscribers,
+        });
+    });
+}
+
+// Returns the connection shared by `share_connection()` and its screen.
+pub fn shared_connection() -> Result<(Rc<ewmh::Connection>, i32)> {
+    SHARED.with(|shared| {
+        shared
+            .borrow()
+            .as_ref()
+            .map(|shared| (shared.conn.clone(), shared.screen_idx))
+            .ok_or_else(|| anyhow!("No X connection has been shared by the bar"))
+    })
+}
+
+// Forwards `PROPERTY_NOTIFY` events to each `xcb_properties_stream()`, and
+// other events to each `xcb_event_stream()` for their response type.
+pub fn dispatch_event(event: &xcb::GenericEvent) {
+    let response_type = event.response_type() & !0x80;
+    let atom = if response_type == PROPERTY_NOTIFY {
+        let event: &PropertyNotifyEvent = unsafe { xcb::cast_event(event) };
+        Some(event.atom())
+    } else {
         None
+    };
+
+    SHARED.with(|shared| {
+        if let Some(shared) = shared.borrow_mut().as_mut() {
+            shared.event_subscribers.retain(|subscriber| {
+                subscriber.response_type != response_type || subscriber.sender.send(()).is_ok()
+            });
+            if let Some(atom) = atom {
+                shared.subscribers.retain(|subscriber| {
+                    !subscriber.atoms.contains(&atom) || subscriber.sender.send(()).is_ok()
+                });
+            }
+        }
     });
+}
+
+// A `Stream` that yields each time the shared connection receives an event
+// of the type returned by `select`, such as the first event of an extension.
+//
+// `select` should select the events the caller is interested in. It is called
+// straight away, and again with the new connection if the bar reconnects to
+// the X server. The caller should re-query the X server for the current state
+// when woken up.
+pub fn xcb_event_stream<F>(select: F) -> Result<impl Stream<Item = ()>>
+where
+    F: Fn(&ewmh::Connection) -> Result<u8> + 'static,
+{
+    let (conn, _) = shared_connection()?;
+    let response_type = select(&conn)?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    SHARED.with(|shared| {
+        shared
+            .borrow_mut()
+            .as_mut()
+            .map(|shared| {
+                shared.event_subscribers.push(EventSubscriber {
+                    select: Box::new(select),
+                    response_type,
+                    sender,
+                })
+            })
+            .ok_or_else(|| anyhow!("No X connection has been shared by the bar"))
+    })?;
+    Ok(UnboundedReceiverStream::new(receiver))
+}
+
+// A `Stream` that listens to `PROPERTY_CHANGE` notifications.
+//
+// It listens to `PROPERTY_CHANGE` notifications for the provided `properties`
+// on the root window of the given screen, using the `Bar`'s shared connection.
+// The caller may listen to `PROPERTY_CHANGE` notifications on additional
+// windows by registering for them on the same connection.
+pub fn xcb_properties_stream(
+    screen_idx: i32,
+    properties: &[&str],
+) -> Result<impl Stream<Item = ()>> {
+    let (conn, _) = shared_connection()?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let mut subscriber = PropertySubscriber {
+        screen_idx,
+        properties: properties
+            .iter()
+            .map(|&property| property.to_owned())
+            .collect(),
+        atoms: Vec::new(),
+        sender,
+    };
+    subscriber.select(&conn)?;
+    SHARED.with(|shared| {
+        shared
+            .borrow_mut()
+            .as_mut()
+            .map(|shared| shared.subscribers.push(subscriber))
+            .ok_or_else(|| anyhow!("No X connection has been shared by the bar"))
+    })?;
 
     // Pretend there was an initial property change to get the initial
     // contents of the widget, then allow our stream of XCB events to
     // call the callback for actual changes.
-    let stream = stream::once(()).chain(stream);
+    let stream = stream::once(()).chain(UnboundedReceiverStream::new(receiver));
 
-    Ok((conn, stream))
+    Ok(stream)
 }